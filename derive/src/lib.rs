@@ -0,0 +1,144 @@
+// コンポーネント用のderiveマクロ
+//
+// `#[derive(Component)]` を付けるだけでComponentトレイトを実装できるようにします。
+// 追加の設定は `#[component(...)]` 属性で指定します。
+// - name = "..."            : Component::nameが返す名前（省略時は型名）
+// - storage = "table"|"sparse" : ストレージの種類（省略時はtable、sparseは空になるとストレージごと解放される）
+// - serialize               : SerializeComponentを実装する（serdeのSerialize/Deserializeが必要）
+// - reflect                 : Reflectを実装する（serializeも必要）
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, LitStr};
+
+/// コンポーネント属性の設定
+struct ComponentAttributes {
+    name: Option<String>,
+    storage: Option<String>,
+    serialize: bool,
+    reflect: bool,
+}
+
+/// `#[component(...)]` 属性を読み取る
+fn parse_attributes(input: &DeriveInput) -> syn::Result<ComponentAttributes> {
+    let mut attributes = ComponentAttributes {
+        name: None,
+        storage: None,
+        serialize: false,
+        reflect: false,
+    };
+    
+    for attr in &input.attrs {
+        if !attr.path().is_ident("component") {
+            continue;
+        }
+        
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                let value: LitStr = meta.value()?.parse()?;
+                attributes.name = Some(value.value());
+                Ok(())
+            } else if meta.path.is_ident("storage") {
+                let value: LitStr = meta.value()?.parse()?;
+                match value.value().as_str() {
+                    "table" | "sparse" => {
+                        attributes.storage = Some(value.value());
+                        Ok(())
+                    },
+                    other => Err(meta.error(format!(
+                        "不明なストレージの種類です: \"{}\"（\"table\" か \"sparse\" を指定してください）",
+                        other
+                    ))),
+                }
+            } else if meta.path.is_ident("serialize") {
+                attributes.serialize = true;
+                Ok(())
+            } else if meta.path.is_ident("reflect") {
+                attributes.reflect = true;
+                Ok(())
+            } else {
+                Err(meta.error("不明なcomponent属性です（name, storage, serialize, reflect が使えます）"))
+            }
+        })?;
+    }
+    
+    if attributes.reflect && !attributes.serialize {
+        return Err(syn::Error::new(
+            Span::call_site(),
+            "reflectを使うにはserializeも指定してください",
+        ));
+    }
+    
+    Ok(attributes)
+}
+
+/// 構造体のフィールド名の一覧を取得（名前付きフィールド以外は空）
+fn field_names(input: &DeriveInput) -> Vec<String> {
+    match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => fields
+                .named
+                .iter()
+                .filter_map(|field| field.ident.as_ref().map(|ident| ident.to_string()))
+                .collect(),
+            _ => Vec::new(),
+        },
+        _ => Vec::new(),
+    }
+}
+
+/// `#[derive(Component)]` の本体
+#[proc_macro_derive(Component, attributes(component))]
+pub fn derive_component(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    
+    let attributes = match parse_attributes(&input) {
+        Ok(attributes) => attributes,
+        Err(e) => return e.to_compile_error().into(),
+    };
+    
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let name = attributes.name.unwrap_or_else(|| ident.to_string());
+    
+    // 1. Componentトレイトの実装
+    let storage = match attributes.storage.as_deref() {
+        Some("sparse") => quote! { crate::ecs::component::StorageKind::SparseSet },
+        _ => quote! { crate::ecs::component::StorageKind::Table },
+    };
+    let mut expanded = quote! {
+        impl #impl_generics crate::ecs::component::Component for #ident #ty_generics #where_clause {
+            const STORAGE: crate::ecs::component::StorageKind = #storage;
+            
+            fn name(&self) -> &'static str {
+                #name
+            }
+            
+            fn type_name() -> &'static str {
+                #name
+            }
+        }
+    };
+    
+    // 2. シリアライズ
+    if attributes.serialize {
+        expanded.extend(quote! {
+            impl #impl_generics crate::ecs::component::SerializeComponent for #ident #ty_generics #where_clause {}
+        });
+    }
+    
+    // 3. リフレクション
+    if attributes.reflect {
+        let fields = field_names(&input);
+        expanded.extend(quote! {
+            impl #impl_generics crate::ecs::component::Reflect for #ident #ty_generics #where_clause {
+                fn field_names() -> &'static [&'static str] {
+                    &[#(#fields),*]
+                }
+            }
+        });
+    }
+    
+    expanded.into()
+}
@@ -0,0 +1,474 @@
+// ヘッドレス実行
+//
+// ブラウザなしでソリティアを動かし、結果をJSONで標準出力に書き出します。
+// 描画以外のシステム（HeadlessPlugins）を一定の時間刻みで回し、
+// スクリプトに書いた手か、簡単な自動プレイで盤面を進めます。
+// 長時間の動作確認、ソルバーのバッチ実行、CIでの回帰チェックに使います。
+//
+// 使い方:
+//   cargo run --bin headless -- [--seed N] [--games N] [--frames N] [--dt 秒] [--script ファイル] [--auto] [--check-determinism]
+//
+// --check-determinism を付けると同じシードで2回実行し、最後の盤面が一致するかを確かめます。
+//
+// スクリプトは1フレームに1手ずつ順番に実行するJSONの配列です:
+//   [{"action": "draw"}, {"action": "move", "card": "AS", "to": "foundation:3"}]
+// カードは「ランク+スート」（A,2..10,J,Q,K と H,D,C,S）、スタックは
+// stock / waste / tableau:0..6 / foundation:0..3 で指定します。
+
+use std::process::ExitCode;
+use serde::{Serialize, Deserialize};
+use ecs_wasm_game4::error::{GameError, GameResult};
+use ecs_wasm_game4::ecs::app::App;
+use ecs_wasm_game4::ecs::entity::EntityId;
+use ecs_wasm_game4::ecs::component::{CardInfo, StackContainer};
+use ecs_wasm_game4::ecs::resources::GameState;
+use ecs_wasm_game4::ecs::error_log::ErrorLog;
+use ecs_wasm_game4::game::setup::HeadlessPlugins;
+use ecs_wasm_game4::game::solitaire::{self, DealSeed};
+use ecs_wasm_game4::game::registry::StackRegistry;
+use ecs_wasm_game4::game::card_index::{CardIndex, check_card_invariants};
+use ecs_wasm_game4::game::score::Score;
+use ecs_wasm_game4::game::events;
+
+/// スクリプトの1手
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+enum ScriptedMove {
+    /// ストックから1枚引く（空ならウェイストを戻す）
+    Draw,
+    /// カードを別のスタックに移動する
+    Move { card: String, to: String },
+}
+
+/// 実行の設定
+struct Options {
+    seed: u64,
+    games: u64,
+    max_frames: u64,
+    delta_time: f32,
+    script: Vec<ScriptedMove>,
+    auto_play: bool,
+    check_determinism: bool,
+}
+
+impl Options {
+    /// コマンドライン引数から設定を読み込む
+    fn from_args() -> Result<Self, String> {
+        let mut options = Self {
+            seed: 1,
+            games: 1,
+            max_frames: 5000,
+            delta_time: 1.0 / 60.0,
+            script: Vec::new(),
+            auto_play: false,
+            check_determinism: false,
+        };
+        
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or_else(|| format!("{} の値がありません", arg));
+            match arg.as_str() {
+                "--seed" => options.seed = parse_number(&value()?)?,
+                "--games" => options.games = parse_number(&value()?)?,
+                "--frames" => options.max_frames = parse_number(&value()?)?,
+                "--dt" => options.delta_time = parse_number(&value()?)?,
+                "--script" => {
+                    let path = value()?;
+                    let json = std::fs::read_to_string(&path)
+                        .map_err(|e| format!("スクリプト {} を読み込めません: {}", path, e))?;
+                    options.script = serde_json::from_str(&json)
+                        .map_err(|e| format!("スクリプト {} を解析できません: {}", path, e))?;
+                },
+                "--auto" => options.auto_play = true,
+                "--check-determinism" => options.check_determinism = true,
+                _ => return Err(format!("不明な引数です: {}", arg)),
+            }
+        }
+        
+        Ok(options)
+    }
+}
+
+/// 数値の引数を解析
+fn parse_number<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("数値として解析できません: {}", value))
+}
+
+/// 却下された手
+#[derive(Debug, Serialize)]
+struct RejectedMove {
+    frame: u64,
+    action: String,
+    error: String,
+}
+
+/// 1ゲーム分の結果
+#[derive(Debug, Serialize)]
+struct GameReport {
+    seed: u64,
+    frames: u64,
+    simulated_seconds: f64,
+    moves_applied: usize,
+    moves_rejected: Vec<RejectedMove>,
+    foundation_cards: usize,
+    // 得点（Windows版ソリティアの標準ルール）
+    score: i32,
+    cleared: bool,
+    state: String,
+    invariants: Result<(), String>,
+    // システムが返したエラーの数
+    system_errors: u64,
+    // 最後の盤面のスナップショットのダイジェスト（回帰チェックで前回の出力と比べる）
+    digest: String,
+    // 同じシードで2回実行して同じ盤面になったか（--check-determinism のときだけ）
+    deterministic: Option<bool>,
+}
+
+/// 全ゲームの結果
+#[derive(Debug, Serialize)]
+struct RunReport {
+    games: Vec<GameReport>,
+    cleared: usize,
+    invariant_failures: usize,
+    nondeterministic: usize,
+}
+
+fn main() -> ExitCode {
+    let options = match Options::from_args() {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("❌ {}", message);
+            return ExitCode::from(2);
+        },
+    };
+    
+    // 1. シード値を1つずつずらしながらゲームを実行
+    let mut games = Vec::new();
+    for i in 0..options.games {
+        let seed = options.seed + i;
+        let result = run_game(&options, seed).and_then(|mut report| {
+            if options.check_determinism {
+                let again = run_game(&options, seed)?;
+                report.deterministic = Some(again.digest == report.digest && again.frames == report.frames);
+            }
+            Ok(report)
+        });
+        
+        match result {
+            Ok(report) => games.push(report),
+            Err(e) => {
+                eprintln!("❌ シード {} の実行中にエラーが発生しました: {}", seed, e);
+                return ExitCode::FAILURE;
+            },
+        }
+    }
+    
+    // 2. 結果をJSONで出力
+    let report = RunReport {
+        cleared: games.iter().filter(|game| game.cleared).count(),
+        invariant_failures: games.iter().filter(|game| game.invariants.is_err()).count(),
+        nondeterministic: games.iter().filter(|game| game.deterministic == Some(false)).count(),
+        games,
+    };
+    match serde_json::to_string_pretty(&report) {
+        Ok(json) => println!("{}", json),
+        Err(e) => {
+            eprintln!("❌ 結果をJSONに変換できません: {}", e);
+            return ExitCode::FAILURE;
+        },
+    }
+    
+    // 3. 不変条件が崩れていたり、実行ごとに結果が変わったりしたらCIで気付けるように失敗で終了
+    if report.invariant_failures > 0 || report.nondeterministic > 0 {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+/// 1ゲームを実行して結果を返す
+fn run_game(options: &Options, seed: u64) -> GameResult<GameReport> {
+    // 1. シード値を固定して描画以外のプラグインでアプリケーションを組み立てる
+    let mut app = App::new();
+    app.insert_resource(DealSeed(seed));
+    app.add_plugin(HeadlessPlugins);
+    app.setup()?;
+    
+    // 2. 最初のフレームでスタック登録簿とカード索引を作ってもらう
+    app.update(options.delta_time)?;
+    let mut frames = 1;
+    
+    let mut script = options.script.iter();
+    let mut auto_player = AutoPlayer::default();
+    let mut moves_applied = 0;
+    let mut moves_rejected = Vec::new();
+    
+    // 3. 1フレームごとに手を打ってからシステムを実行
+    while frames < options.max_frames {
+        let next_move = match script.next() {
+            Some(scripted) => Some(scripted.clone()),
+            None if options.auto_play => auto_player.next_move(&app),
+            None => None,
+        };
+        
+        // スクリプトも自動プレイの手も尽きたら終了（手を指定していなければ最大フレーム数まで回す）
+        if next_move.is_none() && (options.auto_play || !options.script.is_empty()) {
+            break;
+        }
+        
+        if let Some(next_move) = next_move {
+            match apply_move(&mut app, &next_move) {
+                Ok(()) => moves_applied += 1,
+                Err(e) => moves_rejected.push(RejectedMove {
+                    frame: frames,
+                    action: format!("{:?}", next_move),
+                    error: e.to_string(),
+                }),
+            }
+        }
+        
+        app.update(options.delta_time)?;
+        frames += 1;
+        
+        if current_state(&app) == GameState::Clear {
+            break;
+        }
+    }
+    
+    // 4. 結果をまとめる
+    let foundation_cards = match app.resources().get::<StackRegistry>() {
+        Some(registry) => registry
+            .foundations()
+            .iter()
+            .filter_map(|&id| app.world().get_component::<StackContainer>(id))
+            .map(|stack| stack.card_count())
+            .sum(),
+        None => 0,
+    };
+    let invariants = match app.resources().get::<CardIndex>() {
+        Some(index) => check_card_invariants(app.world(), &index),
+        None => Err("カード索引がありません".to_string()),
+    };
+    let system_errors = app.resources().get::<ErrorLog>().map_or(0, |error_log| error_log.total);
+    let score = app.resources().get::<Score>().map_or(0, |score| score.points);
+    let state = current_state(&app);
+    let digest = digest(&app.world().snapshot().to_json()?);
+    
+    Ok(GameReport {
+        seed,
+        frames,
+        simulated_seconds: frames as f64 * options.delta_time as f64,
+        moves_applied,
+        moves_rejected,
+        foundation_cards,
+        score,
+        cleared: state == GameState::Clear,
+        state: format!("{:?}", state),
+        invariants,
+        system_errors,
+        digest,
+        deterministic: None,
+    })
+}
+
+/// 文字列のダイジェスト（FNV-1a）
+/// 標準のハッシュ関数はRustのバージョンで結果が変わりうるので、出力を比べる用途には使わない
+fn digest(text: &str) -> String {
+    let hash = text.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    });
+    format!("{:016x}", hash)
+}
+
+/// 現在のゲーム状態
+fn current_state(app: &App) -> GameState {
+    app.resources().get::<GameState>().map_or(GameState::Title, |state| *state)
+}
+
+/// 1手を実行
+fn apply_move(app: &mut App, scripted: &ScriptedMove) -> GameResult<()> {
+    let registry = app.resources().get::<StackRegistry>()
+        .map(|registry| (*registry).clone())
+        .ok_or(GameError::ResourceMissing("StackRegistry"))?;
+    
+    // 手を指し、ブラウザで遊んだときと同じように得点と履歴に記録する
+    let before = app.world().snapshot();
+    let game_move = match scripted {
+        ScriptedMove::Draw => {
+            let stock_id = registry.stock().ok_or_else(|| GameError::StackNotFound("ストック".to_string()))?;
+            let waste_id = registry.waste().ok_or_else(|| GameError::StackNotFound("ウェイスト".to_string()))?;
+            solitaire::draw_from_stock(app.world_mut(), stock_id, waste_id)?
+        },
+        ScriptedMove::Move { card, to } => {
+            let card_id = find_card(app, card)?;
+            let target_id = parse_stack(&registry, to)?;
+            Some(solitaire::move_cards(app.world_mut(), card_id, target_id)?)
+        },
+    };
+    if let Some(game_move) = game_move {
+        events::record_move(app.resources(), before, game_move);
+    }
+    Ok(())
+}
+
+/// 「AS」「10H」のような表記からカードのエンティティを探す
+fn find_card(app: &App, notation: &str) -> GameResult<EntityId> {
+    let invalid = || GameError::InvalidOperation(format!("カードの表記が正しくありません: {}", notation));
+    
+    let split = notation.len().checked_sub(1).ok_or_else(invalid)?;
+    let (rank, suit) = notation.split_at(split);
+    let rank = match rank {
+        "A" => 0,
+        "J" => 10,
+        "Q" => 11,
+        "K" => 12,
+        number => match number.parse::<u8>() {
+            Ok(n @ 2..=10) => n - 1,
+            _ => return Err(invalid()),
+        },
+    };
+    let suit = match suit {
+        "H" => 0,
+        "D" => 1,
+        "C" => 2,
+        "S" => 3,
+        _ => return Err(invalid()),
+    };
+    
+    let index = app.resources().get::<CardIndex>().ok_or(GameError::ResourceMissing("CardIndex"))?;
+    index.card_by(suit, rank).ok_or_else(invalid)
+}
+
+/// 「tableau:3」のような表記からスタックのエンティティを探す
+fn parse_stack(registry: &StackRegistry, notation: &str) -> GameResult<EntityId> {
+    let (kind, number) = match notation.split_once(':') {
+        Some((kind, number)) => (kind, number.parse::<usize>().ok()),
+        None => (notation, None),
+    };
+    
+    let stack_id = match (kind, number) {
+        ("stock", None) => registry.stock(),
+        ("waste", None) => registry.waste(),
+        ("tableau", Some(column)) => registry.tableau(column),
+        ("foundation", Some(suit)) => registry.foundation(suit),
+        _ => None,
+    };
+    stack_id.ok_or_else(|| GameError::StackNotFound(notation.to_string()))
+}
+
+/// 簡単な自動プレイ
+/// ファウンデーションに置ける手、裏向きのカードを表にできる手、ウェイストからタブローへの手の順に探し、
+/// なければストックから引く。ストックを一巡しても何も動かせなければ終了する。
+#[derive(Default)]
+struct AutoPlayer {
+    // 最後にカードを動かしてから引いた回数
+    draws_without_progress: usize,
+}
+
+impl AutoPlayer {
+    /// 次の手を選ぶ
+    fn next_move(&mut self, app: &App) -> Option<ScriptedMove> {
+        let world = app.world();
+        let registry = (*app.resources().get::<StackRegistry>()?).clone();
+        
+        if let Some(next_move) = Self::find_card_move(app, &registry) {
+            self.draws_without_progress = 0;
+            return Some(next_move);
+        }
+        
+        // ストックとウェイストを一巡したら詰み
+        let cycle: usize = [registry.stock(), registry.waste()]
+            .iter()
+            .flatten()
+            .filter_map(|&id| world.get_component::<StackContainer>(id))
+            .map(|stack| stack.card_count())
+            .sum();
+        if cycle == 0 || self.draws_without_progress > cycle {
+            return None;
+        }
+        
+        self.draws_without_progress += 1;
+        Some(ScriptedMove::Draw)
+    }
+    
+    /// カードを動かす手を探す
+    fn find_card_move(app: &App, registry: &StackRegistry) -> Option<ScriptedMove> {
+        let world = app.world();
+        let top_of = |stack_id: Option<EntityId>| {
+            stack_id
+                .and_then(|id| world.get_component::<StackContainer>(id))
+                .and_then(|stack| stack.top_card())
+        };
+        let tableaus: Vec<EntityId> = (0..7).filter_map(|column| registry.tableau(column)).collect();
+        let foundations: Vec<EntityId> = (0..4).filter_map(|suit| registry.foundation(suit)).collect();
+        
+        // 1. ウェイストとタブローの一番上のカードをファウンデーションへ
+        let mut tops: Vec<EntityId> = top_of(registry.waste()).into_iter().collect();
+        tops.extend(tableaus.iter().filter_map(|&id| top_of(Some(id))));
+        for &card_id in &tops {
+            for &foundation_id in &foundations {
+                if solitaire::check_move(world, card_id, foundation_id).is_ok() {
+                    return Some(Self::move_of(app, registry, card_id, foundation_id));
+                }
+            }
+        }
+        
+        // 2. タブローの表向きの一番下のカードを動かして、裏向きのカードを表にする（空の列を作る手はKでなければ）
+        for &source_id in &tableaus {
+            let stack = match world.get_component::<StackContainer>(source_id) {
+                Some(stack) => stack,
+                None => continue,
+            };
+            let first_face_up = stack.cards.iter().position(|&card_id| {
+                world.get_component::<CardInfo>(card_id).is_some_and(|info| info.face_up)
+            });
+            let (index, card_id) = match first_face_up {
+                Some(index) => (index, stack.cards[index]),
+                None => continue,
+            };
+            let is_king = world.get_component::<CardInfo>(card_id).is_some_and(|info| info.rank == 12);
+            if index == 0 && is_king {
+                continue;  // 既に列の一番下にあるKを空の列に移しても意味がない
+            }
+            
+            for &target_id in &tableaus {
+                if solitaire::check_move(world, card_id, target_id).is_ok() {
+                    return Some(Self::move_of(app, registry, card_id, target_id));
+                }
+            }
+        }
+        
+        // 3. ウェイストの一番上のカードをタブローへ
+        if let Some(card_id) = top_of(registry.waste()) {
+            for &target_id in &tableaus {
+                if solitaire::check_move(world, card_id, target_id).is_ok() {
+                    return Some(Self::move_of(app, registry, card_id, target_id));
+                }
+            }
+        }
+        
+        None
+    }
+    
+    /// カードとスタックのエンティティから、スクリプトと同じ表記の手を作る
+    fn move_of(app: &App, registry: &StackRegistry, card_id: EntityId, target_id: EntityId) -> ScriptedMove {
+        const RANKS: [&str; 13] = ["A", "2", "3", "4", "5", "6", "7", "8", "9", "10", "J", "Q", "K"];
+        const SUITS: [&str; 4] = ["H", "D", "C", "S"];
+        
+        let card = app.world()
+            .get_component::<CardInfo>(card_id)
+            .map(|info| format!("{}{}", RANKS[info.rank as usize], SUITS[info.suit as usize]))
+            .unwrap_or_default();
+        let to = (0..7)
+            .find(|&column| registry.tableau(column) == Some(target_id))
+            .map(|column| format!("tableau:{}", column))
+            .or_else(|| {
+                (0..4)
+                    .find(|&suit| registry.foundation(suit) == Some(target_id))
+                    .map(|suit| format!("foundation:{}", suit))
+            })
+            .unwrap_or_default();
+        
+        ScriptedMove::Move { card, to }
+    }
+}
@@ -11,6 +11,27 @@
 pub const TARGET_FPS: u32 = 60;
 // FRAME_TIME_MSは1フレームあたりの理想的な時間（ミリ秒）
 pub const FRAME_TIME_MS: f64 = 1000.0 / TARGET_FPS as f64;
+// FIXED_TIME_STEPはゲームロジックを更新する固定間隔（秒）
+pub const FIXED_TIME_STEP: f32 = 1.0 / 60.0;
+// MAX_FIXED_STEPS_PER_FRAMEは1フレームで実行するロジック更新の上限回数
+pub const MAX_FIXED_STEPS_PER_FRAME: u32 = 5;
+// PROFILER_WINDOWはプロファイラーが平均・最小値・最大値の計算に使う直近のサンプル数
+pub const PROFILER_WINDOW: usize = 120;
+// ERROR_LOG_CAPACITYはエラーログが保持する直近のエラーの数
+pub const ERROR_LOG_CAPACITY: usize = 100;
+// MOVE_HISTORY_CAPACITYは元に戻せる手の数
+pub const MOVE_HISTORY_CAPACITY: usize = 200;
+// EVENT_QUEUE_CAPACITYは取り出されずに溜めておけるイベントの数
+pub const EVENT_QUEUE_CAPACITY: usize = 256;
+
+//
+// 得点関連（Windows版ソリティアの標準ルール）
+//
+pub const SCORE_WASTE_TO_TABLEAU: i32 = 5;         // ウェイストからタブローへ
+pub const SCORE_TO_FOUNDATION: i32 = 10;           // ウェイスト・タブローからファウンデーションへ
+pub const SCORE_REVEAL_CARD: i32 = 5;              // タブローの裏向きのカードを表にした
+pub const SCORE_FOUNDATION_TO_TABLEAU: i32 = -15;  // ファウンデーションからタブローへ戻した
+pub const SCORE_RECYCLE_WASTE: i32 = -100;         // ウェイストをストックに戻した
 
 //
 // 画面・表示関連
@@ -58,7 +79,9 @@ pub const WASTE_Y: f64 = 50.0;              // 捨て札（山札の右）のY
 
 // ドラッグ関連の設定
 pub const DRAG_OPACITY: f64 = 0.7;  // ドラッグ中のカードの透明度
+pub const DRAG_Z_INDEX: i32 = 1000; // ドラッグ中のカードの描画順序（最前面）
 pub const ANIMATION_DURATION: f64 = 300.0; // アニメーション時間（ミリ秒）
+pub const CLEAR_MODAL_Z_INDEX: i32 = 10000; // クリアモーダルの描画順序（カードより前面）
 
 //
 // ネットワーク設定
@@ -78,6 +101,16 @@ pub const DRAG_THRESHOLD: f64 = 5.0;
 // エンティティの最大数
 pub const MAX_ENTITIES: usize = 1000;
 
+// システムラベル（before/after制約で実行順序を指定するための名前）
+pub const LABEL_STACK_REGISTRY: &str = "stack_registry"; // スタック登録簿の更新
+pub const LABEL_CARD_INDEX: &str = "card_index";  // カード索引の更新
+pub const LABEL_INPUT: &str = "input";            // 入力の取り込み
+pub const LABEL_DRAG: &str = "drag";              // ドラッグ＆ドロップ
+pub const LABEL_GAME_RULES: &str = "game_rules";  // ゲームのルール判定
+pub const LABEL_GAME_EVENTS: &str = "game_events"; // ページに通知するイベントの検出
+pub const LABEL_LAYOUT: &str = "layout";          // カードの配置更新
+pub const LABEL_TRANSFORM_PROPAGATION: &str = "transform_propagation"; // 親子関係に沿った座標の更新
+
 //
 // カード関連の定数
 //
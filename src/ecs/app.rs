@@ -0,0 +1,239 @@
+use crate::error::GameResult;
+use crate::ecs::world::World;
+use crate::ecs::system::{System, SystemManager, SystemConfig};
+use crate::ecs::system_registry::SystemRegistry;
+use crate::ecs::event::Events;
+use crate::ecs::function_system::IntoSystem;
+use crate::ecs::resources::{ResourceManager, GameState};
+use crate::utils::short_type_name;
+use log::{info, warn};
+
+// 起動時に実行する処理の型
+type StartupFn = Box<dyn Fn(&mut World, &mut ResourceManager) -> GameResult<()>>;
+
+/// プラグイン
+/// システム・リソース・起動時の処理をひとまとめにしてAppに追加する
+/// 入力、描画、ルール、ネットワーク、デバッグなどをビルドごとに追加したり外したりできる
+pub trait Plugin {
+    /// プラグインの名前を返す（同じプラグインの二重追加の検出とログに使う）
+    fn name(&self) -> &'static str {
+        short_type_name(std::any::type_name::<Self>())
+    }
+    
+    /// Appにシステムやリソースを追加する
+    fn build(&self, app: &mut App);
+}
+
+/// アプリケーション
+/// World・SystemManager・ResourceManagerを所有し、プラグインで組み立てる
+pub struct App {
+    world: World,
+    system_manager: SystemManager,
+    resources: ResourceManager,
+    
+    // 追加済みのプラグインの名前
+    plugins: Vec<&'static str>,
+    
+    // 起動時（とリセット時）に実行する処理
+    startup: Vec<StartupFn>,
+}
+
+impl App {
+    /// 空のアプリケーションを作成
+    pub fn new() -> Self {
+        Self {
+            world: World::new(),
+            system_manager: SystemManager::new(),
+            resources: ResourceManager::new(),
+            plugins: Vec::new(),
+            startup: Vec::new(),
+        }
+    }
+    
+    /// プラグインを追加
+    /// 同じ名前のプラグインが既に追加されている場合は何もしない
+    pub fn add_plugin<P: Plugin>(&mut self, plugin: P) -> &mut Self {
+        let name = plugin.name();
+        if self.is_plugin_added(name) {
+            warn!("⚠️ プラグイン {} は既に追加されています", name);
+            return self;
+        }
+        
+        info!("🧩 プラグインを追加: {}", name);
+        self.plugins.push(name);
+        plugin.build(self);
+        self
+    }
+    
+    /// プラグインが追加済みかどうか
+    pub fn is_plugin_added(&self, name: &str) -> bool {
+        self.plugins.contains(&name)
+    }
+    
+    /// 追加済みのプラグインの名前一覧（追加順）
+    pub fn plugin_names(&self) -> &[&'static str] {
+        &self.plugins
+    }
+    
+    /// リソースを追加
+    pub fn insert_resource<T: 'static>(&mut self, resource: T) -> &mut Self {
+        self.resources.add(resource);
+        self
+    }
+    
+    /// イベントのキューを追加（既にあれば何もしない）
+    pub fn add_event<T: 'static>(&mut self) -> &mut Self {
+        self.resources.get_or_insert_with(Events::<T>::new);
+        self
+    }
+    
+    /// システムを追加
+    /// 戻り値のSystemConfigで実行条件を設定できる
+    pub fn add_system<M, S: IntoSystem<M>>(&mut self, system: S) -> SystemConfig<'_> {
+        self.system_manager.add_system(system)
+    }
+    
+    /// 名前からシステムを作れるように登録する（デバッグ用コンソールからの挿入・差し替えに使う）
+    pub fn register_system<S, F>(&mut self, factory: F) -> &mut Self
+    where
+        S: System + 'static,
+        F: Fn() -> S + 'static,
+    {
+        self.resources.get_or_insert_with(SystemRegistry::new).register(factory);
+        self
+    }
+    
+    /// ゲーム状態に入ったときに一度だけ実行するシステムを追加
+    pub fn add_on_enter<M, S: IntoSystem<M>>(&mut self, state: GameState, system: S) -> &mut Self {
+        self.system_manager.add_on_enter(state, system);
+        self
+    }
+    
+    /// ゲーム状態から出るときに一度だけ実行するシステムを追加
+    pub fn add_on_exit<M, S: IntoSystem<M>>(&mut self, state: GameState, system: S) -> &mut Self {
+        self.system_manager.add_on_exit(state, system);
+        self
+    }
+    
+    /// 起動時に実行する処理を追加
+    /// 追加した順に実行され、ゲームのリセット時にも再実行される
+    pub fn add_startup<F>(&mut self, startup: F) -> &mut Self
+    where
+        F: Fn(&mut World, &mut ResourceManager) -> GameResult<()> + 'static,
+    {
+        self.startup.push(Box::new(startup));
+        self
+    }
+    
+    /// アプリケーションの準備
+    /// システムの実行順序を構築し、起動時の処理を実行する
+    pub fn setup(&mut self) -> GameResult<()> {
+        // 1. 実行順序を構築（制約に問題があればエラー）
+        self.system_manager.build_schedule()?;
+        info!("📋 システムの実行順序:\n{}", self.system_manager.describe_schedule());
+        
+        // 2. 起動時の処理を実行
+        self.run_startup()
+    }
+    
+    /// 起動時の処理を追加順に実行
+    pub fn run_startup(&mut self) -> GameResult<()> {
+        for startup in &self.startup {
+            startup(&mut self.world, &mut self.resources)?;
+        }
+        Ok(())
+    }
+    
+    /// ゲームをやり直す
+    /// システムの実行順序はそのままに、ワールドを空にしてシステムの状態を初期化し、起動時の処理をやり直す
+    pub fn reset(&mut self) -> GameResult<()> {
+        self.world.clear();
+        self.system_manager.reset_systems();
+        self.run_startup()
+    }
+    
+    /// 1フレーム分のシステムを実行
+    pub fn update(&mut self, delta_time: f32) -> GameResult<()> {
+        self.world.run_systems(&mut self.system_manager, &mut self.resources, delta_time)
+    }
+    
+    /// ワールドへの参照を取得
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+    
+    /// ワールドへの可変参照を取得
+    pub fn world_mut(&mut self) -> &mut World {
+        &mut self.world
+    }
+    
+    /// リソースマネージャーへの参照を取得
+    pub fn resources(&self) -> &ResourceManager {
+        &self.resources
+    }
+    
+    /// リソースマネージャーへの可変参照を取得
+    pub fn resources_mut(&mut self) -> &mut ResourceManager {
+        &mut self.resources
+    }
+    
+    /// システムマネージャーへの参照を取得
+    pub fn system_manager(&self) -> &SystemManager {
+        &self.system_manager
+    }
+    
+    /// システムマネージャーへの可変参照を取得
+    pub fn system_manager_mut(&mut self) -> &mut SystemManager {
+        &mut self.system_manager
+    }
+}
+
+impl Default for App {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ecs::resources::{FixedTime, NetworkState};
+    use crate::game::setup::{build_app, HeadlessPlugins};
+    
+    // buildが呼ばれた回数を数えるプラグイン
+    struct CountingPlugin;
+    
+    struct BuildCount(u32);
+    
+    impl Plugin for CountingPlugin {
+        fn build(&self, app: &mut App) {
+            match app.resources_mut().get_mut::<BuildCount>() {
+                Some(count) => count.0 += 1,
+                None => {
+                    app.insert_resource(BuildCount(1));
+                },
+            }
+        }
+    }
+    
+    #[test]
+    fn adding_a_plugin_twice_builds_it_once() {
+        let mut app = App::default();
+        app.add_plugin(CountingPlugin).add_plugin(CountingPlugin);
+        
+        assert_eq!(app.plugin_names(), &["CountingPlugin"]);
+        assert_eq!(app.resources().get::<BuildCount>().map(|count| count.0), Some(1));
+    }
+    
+    #[test]
+    fn headless_plugins_run_a_frame_without_web_plugins() {
+        let mut app = build_app(HeadlessPlugins).unwrap();
+        assert!(app.is_plugin_added("CorePlugin"));
+        assert!(!app.is_plugin_added("RenderPlugin"));
+        assert!(!app.is_plugin_added("NetworkPlugin"));
+        assert!(app.resources().get::<NetworkState>().is_none());
+        
+        app.update(1.0 / 60.0).unwrap();
+        assert_eq!(app.resources().get::<FixedTime>().map(|fixed_time| fixed_time.step_count), Some(1));
+    }
+}
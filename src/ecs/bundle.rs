@@ -0,0 +1,205 @@
+use crate::error::{GameError, GameResult};
+use crate::ecs::world::World;
+use crate::ecs::entity::EntityId;
+use crate::ecs::component::{
+    Component, Transform, CardInfo, Renderable, Draggable, Clickable, ClickHandlerType,
+    StackContainer, StackType, Name,
+};
+use crate::constants::{CARD_WIDTH, CARD_HEIGHT};
+
+/// バンドルトレイト
+/// 複数のコンポーネントをまとめて1つのエンティティに追加するための型
+pub trait Bundle: 'static {
+    /// バンドルの全てのコンポーネントをエンティティに追加する
+    fn insert_into(self, world: &mut World, entity_id: EntityId) -> GameResult<()>;
+}
+
+// タプルに対してBundleを実装するマクロ
+// (A,), (A, B), ... のように、コンポーネントのタプルをそのままバンドルとして使える
+macro_rules! impl_bundle_for_tuple {
+    ($($name:ident),+) => {
+        impl<$($name: Component),+> Bundle for ($($name,)+) {
+            #[allow(non_snake_case)]
+            fn insert_into(self, world: &mut World, entity_id: EntityId) -> GameResult<()> {
+                let ($($name,)+) = self;
+                $(world.add_component(entity_id, $name)?;)+
+                Ok(())
+            }
+        }
+    };
+}
+
+impl_bundle_for_tuple!(A);
+impl_bundle_for_tuple!(A, B);
+impl_bundle_for_tuple!(A, B, C);
+impl_bundle_for_tuple!(A, B, C, D);
+impl_bundle_for_tuple!(A, B, C, D, E);
+impl_bundle_for_tuple!(A, B, C, D, E, F);
+impl_bundle_for_tuple!(A, B, C, D, E, F, G);
+impl_bundle_for_tuple!(A, B, C, D, E, F, G, H);
+
+impl World {
+    /// バンドルを持つ新しいエンティティを作成
+    /// 途中で失敗した場合は作りかけのエンティティを削除して、エラーを返す
+    pub fn spawn<B: Bundle>(&mut self, bundle: B) -> GameResult<EntityId> {
+        let entity_id = self.create_entity()?;
+        
+        if let Err(e) = bundle.insert_into(self, entity_id) {
+            self.remove_entity(entity_id);
+            return Err(e);
+        }
+        
+        Ok(entity_id)
+    }
+    
+    /// 既存のエンティティにバンドルのコンポーネントをまとめて追加
+    pub fn insert_bundle<B: Bundle>(&mut self, entity_id: EntityId, bundle: B) -> GameResult<()> {
+        if !self.entity_exists(entity_id) {
+            return Err(GameError::EntityNotFound(entity_id));
+        }
+        
+        bundle.insert_into(self, entity_id)
+    }
+}
+
+/// カードのバンドル
+/// トランプカード1枚分のコンポーネント
+pub struct CardBundle {
+    pub transform: Transform,
+    pub card_info: CardInfo,
+    pub renderable: Renderable,
+    pub clickable: Clickable,
+    pub draggable: Option<Draggable>,  // 表向きのカードのみドラッグ可能
+}
+
+impl CardBundle {
+    pub fn new(suit: u8, rank: u8, x: f64, y: f64, face_up: bool, z_index: i32) -> Self {
+        let mut card_info = CardInfo::new(suit, rank);
+        card_info.face_up = face_up;
+        
+        Self {
+            transform: Transform::new(x, y).with_z_index(z_index),
+            card_info,
+            renderable: Renderable::card(CARD_WIDTH, CARD_HEIGHT),
+            clickable: Clickable::new(ClickHandlerType::FlipCard),
+            draggable: if face_up { Some(Draggable::new().with_drag_children()) } else { None },
+        }
+    }
+}
+
+impl Bundle for CardBundle {
+    fn insert_into(self, world: &mut World, entity_id: EntityId) -> GameResult<()> {
+        (self.transform, self.card_info, self.renderable, self.clickable).insert_into(world, entity_id)?;
+        if let Some(draggable) = self.draggable {
+            world.add_component(entity_id, draggable)?;
+        }
+        Ok(())
+    }
+}
+
+/// スタックのバンドル
+/// 山札・捨て札・場札・組み札などのカードの山
+pub struct StackBundle {
+    pub name: Name,
+    pub transform: Transform,
+    pub stack: StackContainer,
+    pub clickable: Clickable,
+}
+
+impl StackBundle {
+    pub fn new(name: &str, stack_type: StackType, x: f64, y: f64, handler: ClickHandlerType) -> Self {
+        Self {
+            name: Name::new(name),
+            transform: Transform::new(x, y),
+            stack: StackContainer::new(stack_type),
+            clickable: Clickable::new(handler),
+        }
+    }
+}
+
+impl Bundle for StackBundle {
+    fn insert_into(self, world: &mut World, entity_id: EntityId) -> GameResult<()> {
+        (self.name, self.transform, self.stack, self.clickable).insert_into(world, entity_id)
+    }
+}
+
+/// ボタンのバンドル
+/// 角丸の長方形で描画される、クリック可能なUI要素
+pub struct ButtonBundle {
+    pub name: Name,
+    pub transform: Transform,
+    pub renderable: Renderable,
+    pub clickable: Clickable,
+}
+
+impl ButtonBundle {
+    pub fn new(name: &str, x: f64, y: f64, width: f64, height: f64) -> Self {
+        Self {
+            name: Name::new(name),
+            transform: Transform::new(x, y),
+            renderable: Renderable::rectangle(width, height, "#FFFFFF", "#333333", 2.0, 8.0),
+            clickable: Clickable::new(ClickHandlerType::Custom),
+        }
+    }
+    
+    pub fn with_colors(mut self, fill_color: &str, stroke_color: &str) -> Self {
+        self.renderable = Renderable::rectangle(
+            self.renderable.width,
+            self.renderable.height,
+            fill_color,
+            stroke_color,
+            2.0,
+            8.0,
+        );
+        self
+    }
+    
+    pub fn with_z_index(mut self, z_index: i32) -> Self {
+        self.transform.z_index = z_index;
+        self
+    }
+}
+
+impl Bundle for ButtonBundle {
+    fn insert_into(self, world: &mut World, entity_id: EntityId) -> GameResult<()> {
+        (self.name, self.transform, self.renderable, self.clickable).insert_into(world, entity_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn card_bundle_is_draggable_only_face_up() {
+        let mut world = World::new();
+        let face_up = world.spawn(CardBundle::new(0, 1, 10.0, 20.0, true, 3)).unwrap();
+        let face_down = world.spawn(CardBundle::new(1, 2, 10.0, 20.0, false, 4)).unwrap();
+        
+        assert!(world.get_component::<CardInfo>(face_up).is_some_and(|card| card.face_up));
+        assert_eq!(world.get_component::<Transform>(face_up).map(|t| t.z_index), Some(3));
+        assert!(world.has_component::<Renderable>(face_up));
+        assert!(world.get_component::<Draggable>(face_up).is_some_and(|draggable| draggable.drag_children));
+        assert!(!world.has_component::<Draggable>(face_down));
+    }
+    
+    #[test]
+    fn stack_bundle_is_found_by_name() {
+        let mut world = World::new();
+        let stock = world.spawn(StackBundle::new("stock", StackType::Stock, 0.0, 0.0, ClickHandlerType::DrawFromStock)).unwrap();
+        
+        assert_eq!(world.find_by_name("stock"), Some(stock));
+        assert!(world.get_component::<StackContainer>(stock).is_some_and(|stack| stack.stack_type == StackType::Stock));
+    }
+    
+    #[test]
+    fn insert_bundle_into_a_missing_entity_fails() {
+        let mut world = World::new();
+        let entity_id = world.spawn((Transform::new(0.0, 0.0),)).unwrap();
+        world.remove_entity(entity_id);
+        world.update();
+        
+        assert!(world.insert_bundle(entity_id, (Name::new("ghost"),)).is_err());
+        assert_eq!(world.find_by_name("ghost"), None);
+    }
+}
@@ -1,39 +1,155 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::any::{Any, TypeId};
-use wasm_bindgen::prelude::*;
+use std::marker::PhantomData;
+use serde::{Serialize, Deserialize};
 use crate::ecs::entity::EntityId;
 use crate::utils::Vec2;
+use ecs_wasm_game4_derive::Component;
 
 /// コンポーネントのデータを格納するためのトレイト
 /// 任意の型をコンポーネントとして使用可能にする
+/// 通常は `#[derive(Component)]` で実装する
 pub trait Component: 'static {
+    /// ストレージの種類
+    const STORAGE: StorageKind = StorageKind::Table;
+    
     /// コンポーネントの名前を返す
     /// デバッグ用
     fn name(&self) -> &'static str;
+    
+    /// インスタンスなしでコンポーネントの名前を返す
+    fn type_name() -> &'static str
+    where
+        Self: Sized,
+    {
+        std::any::type_name::<Self>()
+    }
+}
+
+/// コンポーネントのストレージの種類
+/// Tableはほぼ全てのエンティティが持つ安定したデータ向けで、空になってもストレージを残す
+/// SparseSetは頻繁に追加・削除される一時的なデータ向けで、空になったらストレージごと解放する
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StorageKind {
+    Table,
+    SparseSet,
+}
+
+/// シリアライズ可能なコンポーネント
+/// `#[component(serialize)]` で実装される
+pub trait SerializeComponent: Component + Clone + Serialize + serde::de::DeserializeOwned {
+    /// JSONの値に変換
+    fn to_json_value(&self) -> Result<serde_json::Value, String> {
+        serde_json::to_value(self).map_err(|e| e.to_string())
+    }
+    
+    /// JSONの値から作成
+    fn from_json_value(value: serde_json::Value) -> Result<Self, String>
+    where
+        Self: Sized,
+    {
+        serde_json::from_value(value).map_err(|e| e.to_string())
+    }
+}
+
+/// フィールド単位で読み書きできるコンポーネント
+/// `#[component(serialize, reflect)]` で実装される
+pub trait Reflect: SerializeComponent {
+    /// フィールド名の一覧
+    fn field_names() -> &'static [&'static str]
+    where
+        Self: Sized;
+    
+    /// フィールドの値をJSONで取得
+    fn get_field(&self, field: &str) -> Option<serde_json::Value> {
+        match self.to_json_value() {
+            Ok(serde_json::Value::Object(mut map)) => map.remove(field),
+            _ => None,
+        }
+    }
+    
+    /// フィールドの値をJSONで設定
+    fn set_field(&mut self, field: &str, value: serde_json::Value) -> Result<(), String>
+    where
+        Self: Sized,
+    {
+        if !Self::field_names().contains(&field) {
+            return Err(format!("{} にフィールド {} はありません", Self::type_name(), field));
+        }
+        
+        let mut map = match self.to_json_value()? {
+            serde_json::Value::Object(map) => map,
+            _ => return Err(format!("{} はフィールドを持つ構造体ではありません", Self::type_name())),
+        };
+        map.insert(field.to_string(), value);
+        *self = Self::from_json_value(serde_json::Value::Object(map))?;
+        Ok(())
+    }
+}
+
+/// 変更検出用のティック情報
+/// コンポーネントが追加・変更されたタイミングを記録する
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ComponentTicks {
+    pub added: u32,    // コンポーネントが追加されたティック
+    pub changed: u32,  // コンポーネントが最後に変更されたティック
+}
+
+impl ComponentTicks {
+    /// 新しいティック情報を作成（追加と変更を同じティックで記録）
+    pub fn new(tick: u32) -> Self {
+        Self {
+            added: tick,
+            changed: tick,
+        }
+    }
+    
+    /// 指定したティックより後に追加されたかどうか
+    pub fn is_added_since(&self, since_tick: u32) -> bool {
+        self.added > since_tick
+    }
+    
+    /// 指定したティックより後に変更されたかどうか（追加も変更として扱う）
+    pub fn is_changed_since(&self, since_tick: u32) -> bool {
+        self.changed > since_tick
+    }
 }
 
 /// コンポーネントストレージ
 /// 特定の型のコンポーネントを複数のエンティティに対して保存する
+/// 走査は常にエンティティID順（リプレイやネットワーク同期で実行ごとに結果が変わらないように）
 pub struct ComponentStorage<T: Component> {
     // エンティティIDからコンポーネントへのマップ
-    components: HashMap<EntityId, T>,
+    components: BTreeMap<EntityId, T>,
+    
+    // エンティティIDから変更検出用のティックへのマップ
+    ticks: BTreeMap<EntityId, ComponentTicks>,
 }
 
 impl<T: Component> ComponentStorage<T> {
     /// 新しいコンポーネントストレージを作成
     pub fn new() -> Self {
         Self {
-            components: HashMap::new(),
+            components: BTreeMap::new(),
+            ticks: BTreeMap::new(),
         }
     }
     
     /// エンティティにコンポーネントを追加
-    pub fn add(&mut self, entity_id: EntityId, component: T) {
+    /// 既に同じ型のコンポーネントがある場合は置き換え、変更として記録する
+    pub fn add(&mut self, entity_id: EntityId, component: T, tick: u32) {
+        match self.ticks.get_mut(&entity_id) {
+            Some(ticks) => ticks.changed = tick,
+            None => {
+                self.ticks.insert(entity_id, ComponentTicks::new(tick));
+            },
+        }
         self.components.insert(entity_id, component);
     }
     
     /// エンティティからコンポーネントを削除
     pub fn remove(&mut self, entity_id: &EntityId) -> Option<T> {
+        self.ticks.remove(entity_id);
         self.components.remove(entity_id)
     }
     
@@ -43,8 +159,13 @@ impl<T: Component> ComponentStorage<T> {
     }
     
     /// エンティティのコンポーネントへの可変参照を取得
-    pub fn get_mut(&mut self, entity_id: &EntityId) -> Option<&mut T> {
-        self.components.get_mut(entity_id)
+    /// 可変参照を取得した時点で変更されたものとして記録する
+    pub fn get_mut(&mut self, entity_id: &EntityId, tick: u32) -> Option<&mut T> {
+        let component = self.components.get_mut(entity_id)?;
+        if let Some(ticks) = self.ticks.get_mut(entity_id) {
+            ticks.changed = tick;
+        }
+        Some(component)
     }
     
     /// エンティティがこのタイプのコンポーネントを持っているかチェック
@@ -52,33 +173,155 @@ impl<T: Component> ComponentStorage<T> {
         self.components.contains_key(entity_id)
     }
     
-    /// 全コンポーネントへの参照のイテレータを返す
+    /// エンティティのコンポーネントのティック情報を取得
+    pub fn ticks(&self, entity_id: &EntityId) -> Option<&ComponentTicks> {
+        self.ticks.get(entity_id)
+    }
+    
+    /// 全コンポーネントへの参照のイテレータを返す（エンティティID順）
     pub fn iter(&self) -> impl Iterator<Item = (&EntityId, &T)> {
         self.components.iter()
     }
     
-    /// 全コンポーネントへの可変参照のイテレータを返す
-    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&EntityId, &mut T)> {
+    /// 全コンポーネントへの可変参照のイテレータを返す（エンティティID順）
+    /// 全てのコンポーネントが変更されたものとして記録される
+    pub fn iter_mut(&mut self, tick: u32) -> impl Iterator<Item = (&EntityId, &mut T)> {
+        for ticks in self.ticks.values_mut() {
+            ticks.changed = tick;
+        }
         self.components.iter_mut()
     }
     
+    /// 指定したティックより後に追加されたエンティティのIDを返す
+    pub fn added_since(&self, since_tick: u32) -> Vec<EntityId> {
+        self.ticks
+            .iter()
+            .filter(|(_, ticks)| ticks.is_added_since(since_tick))
+            .map(|(entity_id, _)| *entity_id)
+            .collect()
+    }
+    
+    /// 指定したティックより後に変更されたエンティティのIDを返す
+    pub fn changed_since(&self, since_tick: u32) -> Vec<EntityId> {
+        self.ticks
+            .iter()
+            .filter(|(_, ticks)| ticks.is_changed_since(since_tick))
+            .map(|(entity_id, _)| *entity_id)
+            .collect()
+    }
+    
     /// 指定したエンティティを削除
-    pub fn remove_entity(&mut self, entity_id: &EntityId) {
-        self.components.remove(entity_id);
+    /// 実際にコンポーネントを持っていた場合はtrueを返す
+    pub fn remove_entity(&mut self, entity_id: &EntityId) -> bool {
+        self.ticks.remove(entity_id);
+        self.components.remove(entity_id).is_some()
     }
     
-    /// 全てのコンポーネントを削除
-    pub fn clear(&mut self) {
+    /// 全てのコンポーネントを削除し、削除したエンティティのIDを返す
+    pub fn clear(&mut self) -> Vec<EntityId> {
+        let removed: Vec<EntityId> = self.components.keys().copied().collect();
         self.components.clear();
+        self.ticks.clear();
+        removed
+    }
+    
+    /// コンポーネントが1つもないかどうか
+    pub fn is_empty(&self) -> bool {
+        self.components.is_empty()
+    }
+}
+
+/// 型を消したコンポーネントストレージ
+/// ComponentManagerが型を知らなくてもエンティティの削除や全削除をできるように、ストレージ自身が処理する
+/// コンポーネントを追加したときにストレージと一緒に登録されるので、新しいコンポーネントの型を手で登録する必要はない
+trait AnyStorage {
+    /// 指定したエンティティのコンポーネントを削除（持っていた場合はtrue）
+    fn remove_entity(&mut self, entity_id: &EntityId) -> bool;
+    
+    /// 全てのコンポーネントを削除し、削除したエンティティのIDを返す
+    fn clear(&mut self) -> Vec<EntityId>;
+    
+    /// 空になったら解放してよいストレージかどうか
+    fn is_disposable(&self) -> bool;
+    
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
+}
+
+impl<T: Component> AnyStorage for ComponentStorage<T> {
+    fn remove_entity(&mut self, entity_id: &EntityId) -> bool {
+        ComponentStorage::remove_entity(self, entity_id)
+    }
+    
+    fn clear(&mut self) -> Vec<EntityId> {
+        ComponentStorage::clear(self)
+    }
+    
+    fn is_disposable(&self) -> bool {
+        T::STORAGE == StorageKind::SparseSet && self.is_empty()
+    }
+    
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+    
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+/// 削除されたコンポーネントの一覧
+/// `World::removed_components` から取得し、前回のシステム実行以降に
+/// 型Tのコンポーネントを失ったエンティティを列挙する
+pub struct RemovedComponents<T: Component> {
+    entities: Vec<EntityId>,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Component> RemovedComponents<T> {
+    /// 新しい削除一覧を作成
+    pub fn new(entities: Vec<EntityId>) -> Self {
+        Self {
+            entities,
+            _marker: PhantomData,
+        }
+    }
+    
+    /// 削除されたエンティティのIDのイテレータを返す
+    pub fn iter(&self) -> impl Iterator<Item = &EntityId> {
+        self.entities.iter()
+    }
+    
+    /// 指定したエンティティが含まれているかチェック
+    pub fn contains(&self, entity_id: EntityId) -> bool {
+        self.entities.contains(&entity_id)
+    }
+    
+    /// 削除されたエンティティの数
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+    
+    /// 削除されたエンティティがないかどうか
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
     }
 }
 
 /// コンポーネントマネージャー
 /// 全ての型のコンポーネントストレージを管理する
 pub struct ComponentManager {
-    // TypeIdからAny型へのマップ
-    // 各ComponentStorage<T>はAny型としてダウンキャストできる
-    storages: HashMap<TypeId, Box<dyn Any>>,
+    // TypeIdから型を消したストレージへのマップ
+    // 各ComponentStorage<T>はas_anyでダウンキャストできる
+    storages: HashMap<TypeId, Box<dyn AnyStorage>>,
+    
+    // TypeIdから削除されたコンポーネントの記録（エンティティIDと削除時のティック）へのマップ
+    removed: HashMap<TypeId, Vec<(EntityId, u32)>>,
 }
 
 impl ComponentManager {
@@ -86,6 +329,7 @@ impl ComponentManager {
     pub fn new() -> Self {
         Self {
             storages: HashMap::new(),
+            removed: HashMap::new(),
         }
     }
     
@@ -101,6 +345,7 @@ impl ComponentManager {
         self.storages
             .get_mut(&type_id)
             .unwrap()
+            .as_any_mut()
             .downcast_mut::<ComponentStorage<T>>()
             .unwrap()
     }
@@ -111,7 +356,7 @@ impl ComponentManager {
         
         self.storages
             .get(&type_id)
-            .and_then(|boxed| boxed.downcast_ref::<ComponentStorage<T>>())
+            .and_then(|boxed| boxed.as_any().downcast_ref::<ComponentStorage<T>>())
     }
     
     /// コンポーネントストレージへの可変参照を取得
@@ -120,19 +365,58 @@ impl ComponentManager {
         
         self.storages
             .get_mut(&type_id)
-            .and_then(|boxed| boxed.downcast_mut::<ComponentStorage<T>>())
+            .and_then(|boxed| boxed.as_any_mut().downcast_mut::<ComponentStorage<T>>())
+    }
+    
+    /// 指定した型のコンポーネントストレージを一時的に取り出す（なければ空のストレージ）
+    /// 関数システムが複数の型のコンポーネントを同時に借りるために使い、実行後に`restore_storage`で戻す
+    pub fn take_storage<T: Component>(&mut self) -> ComponentStorage<T> {
+        self.storages
+            .remove(&TypeId::of::<T>())
+            .and_then(|boxed| boxed.into_any().downcast::<ComponentStorage<T>>().ok())
+            .map(|boxed| *boxed)
+            .unwrap_or_else(ComponentStorage::new)
+    }
+    
+    /// `take_storage`で取り出したコンポーネントストレージを戻す
+    pub fn restore_storage<T: Component>(&mut self, storage: ComponentStorage<T>) {
+        if !AnyStorage::is_disposable(&storage) {
+            self.storages.insert(TypeId::of::<T>(), Box::new(storage));
+        }
+    }
+    
+    /// 空になったSparseSetのストレージを解放する
+    fn dispose_empty_storages(&mut self) {
+        self.storages.retain(|_, storage| !storage.is_disposable());
+    }
+    
+    /// 削除されたコンポーネントを記録
+    fn record_removed(&mut self, type_id: TypeId, entity_id: EntityId, tick: u32) {
+        self.removed
+            .entry(type_id)
+            .or_default()
+            .push((entity_id, tick));
     }
     
     /// エンティティにコンポーネントを追加
-    pub fn add_component<T: Component>(&mut self, entity_id: EntityId, component: T) {
+    pub fn add_component<T: Component>(&mut self, entity_id: EntityId, component: T, tick: u32) {
         let storage = self.get_or_create_storage::<T>();
-        storage.add(entity_id, component);
+        storage.add(entity_id, component, tick);
     }
     
     /// エンティティからコンポーネントを削除
-    pub fn remove_component<T: Component>(&mut self, entity_id: &EntityId) -> Option<T> {
-        self.get_storage_mut::<T>()
-            .and_then(|storage| storage.remove(entity_id))
+    pub fn remove_component<T: Component>(&mut self, entity_id: &EntityId, tick: u32) -> Option<T> {
+        let removed = self.get_storage_mut::<T>()
+            .and_then(|storage| storage.remove(entity_id));
+        
+        if removed.is_some() {
+            self.record_removed(TypeId::of::<T>(), *entity_id, tick);
+            if T::STORAGE == StorageKind::SparseSet {
+                self.dispose_empty_storages();
+            }
+        }
+        
+        removed
     }
     
     /// エンティティのコンポーネントへの参照を取得
@@ -142,18 +426,25 @@ impl ComponentManager {
     }
     
     /// エンティティのコンポーネントへの可変参照を取得
-    pub fn get_component_mut<T: Component>(&mut self, entity_id: &EntityId) -> Option<&mut T> {
+    /// 取得したコンポーネントは指定したティックで変更されたものとして記録される
+    pub fn get_component_mut<T: Component>(&mut self, entity_id: &EntityId, tick: u32) -> Option<&mut T> {
         self.get_storage_mut::<T>()
-            .and_then(|storage| storage.get_mut(entity_id))
+            .and_then(|storage| storage.get_mut(entity_id, tick))
+    }
+    
+    /// エンティティのコンポーネントのティック情報を取得
+    pub fn get_component_ticks<T: Component>(&self, entity_id: &EntityId) -> Option<ComponentTicks> {
+        self.get_storage::<T>()
+            .and_then(|storage| storage.ticks(entity_id).copied())
     }
     
     /// エンティティがコンポーネントを持っているかチェック
     pub fn has_component<T: Component>(&self, entity_id: &EntityId) -> bool {
         self.get_storage::<T>()
-            .map_or(false, |storage| storage.has(entity_id))
+            .is_some_and(|storage| storage.has(entity_id))
     }
     
-    /// 特定の型のコンポーネントを持つ全てのエンティティIDのイテレータを返す
+    /// 特定の型のコンポーネントを持つ全てのエンティティIDを返す（ID順）
     pub fn entities_with_component<T: Component>(&self) -> Vec<EntityId> {
         if let Some(storage) = self.get_storage::<T>() {
             storage.iter().map(|(entity_id, _)| *entity_id).collect()
@@ -162,70 +453,74 @@ impl ComponentManager {
         }
     }
     
-    /// エンティティに関連付けられたすべてのコンポーネントを削除
-    pub fn remove_entity(&mut self, entity_id: &EntityId) {
-        for (_type_id, storage) in self.storages.iter_mut() {
-            // 各ストレージタイプに対してエンティティを削除するメソッドを呼び出す
-            // Any型なので実行時に型を判断して適切なメソッドを呼ぶ必要がある
-            // これは少し複雑なので、以下のようなヘルパーを作る
-            remove_entity_from_storage(storage.as_mut(), entity_id);
-        }
+    /// 指定したティックより後に型Tのコンポーネントが追加されたエンティティIDを返す
+    pub fn entities_with_added<T: Component>(&self, since_tick: u32) -> Vec<EntityId> {
+        self.get_storage::<T>()
+            .map(|storage| storage.added_since(since_tick))
+            .unwrap_or_default()
     }
     
-    /// 全てのコンポーネントを削除
-    pub fn clear(&mut self) {
-        for (_type_id, storage) in self.storages.iter_mut() {
-            clear_storage(storage.as_mut());
+    /// 指定したティックより後に型Tのコンポーネントが変更されたエンティティIDを返す
+    pub fn entities_with_changed<T: Component>(&self, since_tick: u32) -> Vec<EntityId> {
+        self.get_storage::<T>()
+            .map(|storage| storage.changed_since(since_tick))
+            .unwrap_or_default()
+    }
+    
+    /// 指定したティックより後に型Tのコンポーネントが削除されたエンティティIDを返す
+    pub fn removed_since<T: Component>(&self, since_tick: u32) -> Vec<EntityId> {
+        self.removed
+            .get(&TypeId::of::<T>())
+            .map(|removed| {
+                removed
+                    .iter()
+                    .filter(|(_, tick)| *tick > since_tick)
+                    .map(|(entity_id, _)| *entity_id)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+    
+    /// 指定したティックより前に記録された削除情報を破棄する
+    pub fn prune_removed(&mut self, before_tick: u32) {
+        for removed in self.removed.values_mut() {
+            removed.retain(|(_, tick)| *tick >= before_tick);
         }
     }
-}
-
-// ヘルパー関数：Any型のストレージからエンティティを削除
-fn remove_entity_from_storage(storage: &mut dyn Any, entity_id: &EntityId) {
-    // ダウンキャストして、特定の型のComponentStorageとして処理
-    macro_rules! try_downcast_and_remove {
-        ($type:ty) => {
-            if let Some(typed_storage) = storage.downcast_mut::<ComponentStorage<$type>>() {
-                typed_storage.remove_entity(entity_id);
-                return;
+    
+    /// エンティティに関連付けられたすべてのコンポーネントを削除
+    pub fn remove_entity(&mut self, entity_id: &EntityId, tick: u32) {
+        let mut removed_types = Vec::new();
+        
+        for (type_id, storage) in self.storages.iter_mut() {
+            if storage.remove_entity(entity_id) {
+                removed_types.push(*type_id);
             }
-        };
+        }
+        
+        // 削除したコンポーネントを記録
+        for type_id in removed_types {
+            self.record_removed(type_id, *entity_id, tick);
+        }
+        self.dispose_empty_storages();
     }
     
-    // サポートする全てのコンポーネント型に対してダウンキャストを試みる
-    try_downcast_and_remove!(Transform);
-    try_downcast_and_remove!(CardInfo);
-    try_downcast_and_remove!(Renderable);
-    try_downcast_and_remove!(Draggable);
-    try_downcast_and_remove!(Clickable);
-    try_downcast_and_remove!(StackContainer);
-    try_downcast_and_remove!(Position);
-    try_downcast_and_remove!(Sprite);
-    try_downcast_and_remove!(Droppable);
-}
-
-// ヘルパー関数：Any型のストレージをクリア
-fn clear_storage(storage: &mut dyn Any) {
-    // ダウンキャストして、特定の型のComponentStorageとして処理
-    macro_rules! try_downcast_and_clear {
-        ($type:ty) => {
-            if let Some(typed_storage) = storage.downcast_mut::<ComponentStorage<$type>>() {
-                typed_storage.clear();
-                return;
+    /// 全てのコンポーネントを削除
+    pub fn clear(&mut self, tick: u32) {
+        let mut removed_entries = Vec::new();
+        
+        for (type_id, storage) in self.storages.iter_mut() {
+            for entity_id in storage.clear() {
+                removed_entries.push((*type_id, entity_id));
             }
-        };
+        }
+        
+        // 削除したコンポーネントを記録
+        for (type_id, entity_id) in removed_entries {
+            self.record_removed(type_id, entity_id, tick);
+        }
+        self.dispose_empty_storages();
     }
-    
-    // サポートする全てのコンポーネント型に対してダウンキャストを試みる
-    try_downcast_and_clear!(Transform);
-    try_downcast_and_clear!(CardInfo);
-    try_downcast_and_clear!(Renderable);
-    try_downcast_and_clear!(Draggable);
-    try_downcast_and_clear!(Clickable);
-    try_downcast_and_clear!(StackContainer);
-    try_downcast_and_clear!(Position);
-    try_downcast_and_clear!(Sprite);
-    try_downcast_and_clear!(Droppable);
 }
 
 //
@@ -234,7 +529,8 @@ fn clear_storage(storage: &mut dyn Any) {
 
 /// トランスフォームコンポーネント
 /// エンティティの位置、スケール、回転などを管理
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize, Component)]
+#[component(serialize, reflect)]
 pub struct Transform {
     pub position: Vec2,
     pub scale: Vec2,
@@ -258,15 +554,10 @@ impl Transform {
     }
 }
 
-impl Component for Transform {
-    fn name(&self) -> &'static str {
-        "Transform"
-    }
-}
-
 /// カード情報コンポーネント
 /// トランプカードの情報（スート、数字、表裏など）を管理
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize, Component)]
+#[component(serialize, reflect)]
 pub struct CardInfo {
     pub suit: u8,        // 0=ハート, 1=ダイヤ, 2=クラブ, 3=スペード
     pub rank: u8,        // 0=A, 1=2, ..., 12=K
@@ -313,15 +604,10 @@ impl CardInfo {
     }
 }
 
-impl Component for CardInfo {
-    fn name(&self) -> &'static str {
-        "CardInfo"
-    }
-}
-
 /// レンダラブルコンポーネント
 /// エンティティの描画方法を定義
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize, Component)]
+#[component(serialize, reflect)]
 pub struct Renderable {
     pub width: f64,
     pub height: f64,
@@ -332,7 +618,7 @@ pub struct Renderable {
 
 /// レンダリングタイプ
 /// エンティティの表示方法を指定
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum RenderType {
     // カードの描画情報
     Card,
@@ -413,15 +699,10 @@ impl Renderable {
     }
 }
 
-impl Component for Renderable {
-    fn name(&self) -> &'static str {
-        "Renderable"
-    }
-}
-
 /// ドラッグ可能コンポーネント
 /// エンティティをドラッグ可能にする
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize, Component)]
+#[component(storage = "sparse", serialize, reflect)]
 pub struct Draggable {
     pub is_dragging: bool,
     pub drag_offset: Vec2,  // ドラッグ開始位置からのオフセット
@@ -457,15 +738,10 @@ impl Draggable {
     }
 }
 
-impl Component for Draggable {
-    fn name(&self) -> &'static str {
-        "Draggable"
-    }
-}
-
 /// クリック可能コンポーネント
 /// エンティティをクリック可能にする
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize, Component)]
+#[component(serialize, reflect)]
 pub struct Clickable {
     pub is_hovering: bool,
     pub was_clicked: bool,
@@ -474,7 +750,7 @@ pub struct Clickable {
 
 /// クリックハンドラータイプ
 /// クリック時の動作を指定
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum ClickHandlerType {
     /// カードをめくる動作
     FlipCard,
@@ -500,15 +776,10 @@ impl Clickable {
     }
 }
 
-impl Component for Clickable {
-    fn name(&self) -> &'static str {
-        "Clickable"
-    }
-}
-
 /// スタックコンテナコンポーネント
 /// カードの山を表現するコンポーネント
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize, Component)]
+#[component(serialize, reflect)]
 pub struct StackContainer {
     pub stack_type: StackType,
     pub cards: Vec<EntityId>,
@@ -517,7 +788,7 @@ pub struct StackContainer {
 
 /// スタックタイプ
 /// カードの山の種類を指定
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum StackType {
     /// 山札
     Stock,
@@ -594,14 +865,8 @@ impl StackContainer {
     }
 }
 
-impl Component for StackContainer {
-    fn name(&self) -> &'static str {
-        "StackContainer"
-    }
-}
-
 // 位置情報を表すコンポーネント
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Component)]
 pub struct Position {
     pub x: f64,
     pub y: f64,
@@ -617,14 +882,8 @@ impl Position {
     }
 }
 
-impl Component for Position {
-    fn name(&self) -> &'static str {
-        "Position"
-    }
-}
-
 // スプライト表示用コンポーネント
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Component)]
 pub struct Sprite {
     pub width: f64,
     pub height: f64,
@@ -650,15 +909,10 @@ impl Sprite {
     }
 }
 
-impl Component for Sprite {
-    fn name(&self) -> &'static str {
-        "Sprite"
-    }
-}
-
 /// ドロップ可能なコンポーネント
 /// エンティティをドロップ対象として指定する
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize, Component)]
+#[component(serialize, reflect)]
 pub struct Droppable {
     pub width: f64,  // ドロップ可能な領域の幅
     pub height: f64,  // ドロップ可能な領域の高さ
@@ -687,8 +941,106 @@ impl Droppable {
     }
 }
 
-impl Component for Droppable {
-    fn name(&self) -> &'static str {
-        "Droppable"
+/// 名前コンポーネント
+/// エンティティに一意な名前を付け、World::find_by_nameで検索できるようにする
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Component)]
+#[component(serialize, reflect)]
+pub struct Name(pub String);
+
+impl Name {
+    pub fn new(name: &str) -> Self {
+        Self(name.to_string())
+    }
+    
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    // 名前も属性も指定しないコンポーネント
+    #[derive(Component)]
+    struct Plain;
+    
+    // 属性を全て指定したコンポーネント
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Component)]
+    #[component(name = "Marker", storage = "sparse", serialize, reflect)]
+    struct TestMarker {
+        value: i32,
+    }
+    
+    #[test]
+    fn derive_uses_type_name_and_table_storage_by_default() {
+        assert_eq!(Plain.name(), "Plain");
+        assert_eq!(Plain::type_name(), "Plain");
+        assert_eq!(Plain::STORAGE, StorageKind::Table);
+    }
+    
+    #[test]
+    fn derive_applies_component_attributes() {
+        let mut marker = TestMarker { value: 1 };
+        assert_eq!(marker.name(), "Marker");
+        assert_eq!(TestMarker::type_name(), "Marker");
+        assert_eq!(TestMarker::STORAGE, StorageKind::SparseSet);
+        assert_eq!(TestMarker::field_names(), &["value"]);
+        
+        marker.set_field("value", serde_json::json!(5)).unwrap();
+        assert_eq!(marker.get_field("value"), Some(serde_json::json!(5)));
+        assert!(marker.set_field("missing", serde_json::json!(0)).is_err());
+    }
+    
+    #[test]
+    fn remove_entity_removes_components_of_any_type() {
+        let mut manager = ComponentManager::new();
+        manager.add_component(0, Plain, 1);
+        manager.add_component(0, TestMarker { value: 1 }, 1);
+        manager.add_component(1, Plain, 1);
+        
+        manager.remove_entity(&0, 2);
+        assert!(!manager.has_component::<Plain>(&0));
+        assert!(!manager.has_component::<TestMarker>(&0));
+        assert!(manager.has_component::<Plain>(&1));
+        assert_eq!(manager.removed_since::<Plain>(1), vec![0]);
+        assert_eq!(manager.removed_since::<TestMarker>(1), vec![0]);
     }
-} 
\ No newline at end of file
+    
+    #[test]
+    fn empty_sparse_storage_is_disposed() {
+        let mut manager = ComponentManager::new();
+        manager.add_component(0, Plain, 1);
+        manager.add_component(0, TestMarker { value: 1 }, 1);
+        
+        manager.remove_component::<TestMarker>(&0, 2);
+        assert!(!manager.storages.contains_key(&TypeId::of::<TestMarker>()));
+        
+        // Tableのストレージは空になっても残す
+        manager.remove_component::<Plain>(&0, 2);
+        assert!(manager.storages.contains_key(&TypeId::of::<Plain>()));
+        
+        // 解放したあとでも追加し直せる
+        manager.add_component(1, TestMarker { value: 2 }, 3);
+        assert_eq!(manager.get_component::<TestMarker>(&1), Some(&TestMarker { value: 2 }));
+        
+        manager.clear(4);
+        assert!(!manager.storages.contains_key(&TypeId::of::<TestMarker>()));
+        assert!(manager.storages.contains_key(&TypeId::of::<Plain>()));
+        assert_eq!(manager.removed_since::<TestMarker>(3), vec![1]);
+    }
+    
+    #[test]
+    fn iter_mut_marks_every_component_changed_even_without_writes() {
+        let mut storage = ComponentStorage::new();
+        storage.add(0, Plain, 1);
+        storage.add(1, Plain, 1);
+        assert!(storage.changed_since(1).is_empty());
+        
+        // 可変参照を渡した時点で変更扱いになる（書き込んだかどうかは見ない）
+        let _ = storage.iter_mut(2).count();
+        assert_eq!(storage.changed_since(1), vec![0, 1]);
+        assert!(storage.added_since(1).is_empty());
+    }
+}
@@ -0,0 +1,33 @@
+use crate::ecs::world::World;
+use crate::ecs::resources::{ResourceManager, GameState};
+
+/// 実行条件
+/// システムをこのフレームで実行するかどうかを判定する関数
+pub type RunCondition = Box<dyn Fn(&World, &ResourceManager) -> bool>;
+
+/// 指定したゲーム状態のときだけ実行する条件
+pub fn in_state(state: GameState) -> impl Fn(&World, &ResourceManager) -> bool + 'static {
+    move |_world: &World, resources: &ResourceManager| {
+        resources.get::<GameState>().is_some_and(|current| *current == state)
+    }
+}
+
+/// 指定したゲーム状態のいずれかのときだけ実行する条件
+pub fn in_any_state(states: &[GameState]) -> impl Fn(&World, &ResourceManager) -> bool + 'static {
+    let states = states.to_vec();
+    move |_world: &World, resources: &ResourceManager| {
+        resources.get::<GameState>().is_some_and(|current| states.contains(&*current))
+    }
+}
+
+/// 指定したゲーム状態以外のときだけ実行する条件
+pub fn not_in_state(state: GameState) -> impl Fn(&World, &ResourceManager) -> bool + 'static {
+    move |_world: &World, resources: &ResourceManager| {
+        resources.get::<GameState>().map(|current| *current) != Some(state)
+    }
+}
+
+/// 指定した型のリソースが存在するときだけ実行する条件
+pub fn resource_exists<T: 'static>() -> impl Fn(&World, &ResourceManager) -> bool + 'static {
+    |_world: &World, resources: &ResourceManager| resources.has::<T>()
+}
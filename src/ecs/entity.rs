@@ -1,5 +1,5 @@
-use std::collections::HashSet;
-use wasm_bindgen::prelude::*;
+use std::collections::BTreeSet;
+use crate::error::{GameError, GameResult};
 use crate::constants::MAX_ENTITIES;
 
 /// エンティティIDの型定義
@@ -13,11 +13,12 @@ pub struct EntityManager {
     next_entity_id: EntityId,
     
     // 現在アクティブなエンティティのIDセット
-    active_entities: HashSet<EntityId>,
+    // IDは作成順に増えていくので、ID順に走査すれば作成順になる（実行ごとに順序が変わらない）
+    active_entities: BTreeSet<EntityId>,
     
     // 削除予定のエンティティのIDセット
     // 次のフレーム更新時に実際に削除される
-    entities_to_remove: HashSet<EntityId>,
+    entities_to_remove: BTreeSet<EntityId>,
 }
 
 impl EntityManager {
@@ -25,16 +26,16 @@ impl EntityManager {
     pub fn new() -> Self {
         Self {
             next_entity_id: 0,
-            active_entities: HashSet::new(),
-            entities_to_remove: HashSet::new(),
+            active_entities: BTreeSet::new(),
+            entities_to_remove: BTreeSet::new(),
         }
     }
     
     /// 新しいエンティティを作成し、そのIDを返す
-    pub fn create_entity(&mut self) -> Result<EntityId, JsValue> {
+    pub fn create_entity(&mut self) -> GameResult<EntityId> {
         // エンティティの最大数をチェック
         if self.active_entities.len() >= MAX_ENTITIES {
-            return Err(JsValue::from_str(&format!(
+            return Err(GameError::Capacity(format!(
                 "エンティティの最大数（{}）に達しました！😱",
                 MAX_ENTITIES
             )));
@@ -74,7 +75,7 @@ impl EntityManager {
         self.active_entities.contains(&entity_id)
     }
     
-    /// 現在アクティブなエンティティのIDのイテレータを返す
+    /// 現在アクティブなエンティティのIDのイテレータを返す（ID順）
     pub fn active_entities(&self) -> impl Iterator<Item = &EntityId> {
         self.active_entities.iter()
     }
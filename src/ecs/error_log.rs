@@ -0,0 +1,104 @@
+use std::collections::VecDeque;
+use serde::Serialize;
+use crate::error::GameError;
+use crate::constants::ERROR_LOG_CAPACITY;
+
+/// エラーログの1件
+#[derive(Clone, Debug, Serialize)]
+pub struct ErrorLogEntry {
+    pub system: &'static str,  // エラーを返したシステムの名前
+    pub message: String,       // エラーの内容
+    pub frame: u64,            // 発生したフレーム（TimeInfoのフレーム数）
+    pub disabled: bool,        // このエラーでシステムが無効になったかどうか
+}
+
+/// エラーログ
+/// システムが返したエラーを直近の一定数だけ記録するリソース
+/// UIでの表示や、バグ報告用のエクスポートに使う
+#[derive(Default, Serialize)]
+pub struct ErrorLog {
+    pub entries: VecDeque<ErrorLogEntry>,  // 直近のエラー（古い順）
+    pub total: u64,                        // 記録したエラーの総数
+}
+
+impl ErrorLog {
+    /// 新しいエラーログを作成
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// エラーを記録する
+    /// 保持できる数を超えたら古いものから捨てる
+    pub fn record(&mut self, system: &'static str, error: &GameError, frame: u64, disabled: bool) {
+        self.entries.push_back(ErrorLogEntry {
+            system,
+            message: error.to_string(),
+            frame,
+            disabled,
+        });
+        if self.entries.len() > ERROR_LOG_CAPACITY {
+            self.entries.pop_front();
+        }
+        self.total += 1;
+    }
+    
+    /// 直近のエラーを新しい順に最大count件取得
+    pub fn recent(&self, count: usize) -> Vec<&ErrorLogEntry> {
+        self.entries.iter().rev().take(count).collect()
+    }
+    
+    /// 記録されているエラーがないかどうか
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+    
+    /// 記録をすべて消す
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+    
+    /// 直近のエラーを新しい順に最大count件、JSON文字列に変換
+    pub fn to_json(&self, count: usize) -> String {
+        serde_json::to_string(&self.recent(count)).unwrap_or_else(|_| "[]".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn error(frame: u64) -> GameError {
+        GameError::InvalidOperation(format!("フレーム {}", frame))
+    }
+    
+    #[test]
+    fn record_keeps_only_the_latest_entries() {
+        let mut log = ErrorLog::new();
+        let count = ERROR_LOG_CAPACITY as u64 + 2;
+        for frame in 0..count {
+            log.record("TestSystem", &error(frame), frame, false);
+        }
+        
+        // 古い2件が捨てられ、総数は全件を数える
+        assert_eq!(log.entries.len(), ERROR_LOG_CAPACITY);
+        assert_eq!(log.entries.front().map(|entry| entry.frame), Some(2));
+        assert_eq!(log.total, count);
+    }
+    
+    #[test]
+    fn recent_returns_newest_first() {
+        let mut log = ErrorLog::new();
+        for frame in 0..3 {
+            log.record("TestSystem", &error(frame), frame, frame == 2);
+        }
+        
+        let frames: Vec<u64> = log.recent(2).iter().map(|entry| entry.frame).collect();
+        assert_eq!(frames, vec![2, 1]);
+        assert!(log.recent(1)[0].disabled);
+        assert!(log.recent(1)[0].message.contains("フレーム 2"));
+        
+        log.clear();
+        assert!(log.is_empty());
+        assert_eq!(log.to_json(5), "[]");
+    }
+}
@@ -0,0 +1,71 @@
+use std::collections::VecDeque;
+use crate::constants::EVENT_QUEUE_CAPACITY;
+use log::warn;
+
+/// イベントのキュー
+/// システムが送ったイベントを、受け取る側（ページへの通知など）が取り出すまで溜めておくリソース
+/// 取り出されないまま上限を超えたら古いものから捨てる
+pub struct Events<T> {
+    queue: VecDeque<T>,
+    
+    // 上限を超えて捨てたイベントの数
+    dropped: u64,
+}
+
+impl<T> Default for Events<T> {
+    fn default() -> Self {
+        Self {
+            queue: VecDeque::new(),
+            dropped: 0,
+        }
+    }
+}
+
+impl<T> Events<T> {
+    /// 空のキューを作成
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// イベントを送る
+    pub fn send(&mut self, event: T) {
+        self.queue.push_back(event);
+        if self.queue.len() > EVENT_QUEUE_CAPACITY {
+            self.queue.pop_front();
+            self.dropped += 1;
+            if self.dropped == 1 {
+                warn!("⚠️ イベントが取り出されないまま溜まったため、古いものから捨てています");
+            }
+        }
+    }
+    
+    /// 溜まっているイベントを送られた順に全て取り出す
+    pub fn drain(&mut self) -> Vec<T> {
+        self.queue.drain(..).collect()
+    }
+    
+    /// 溜まっているイベントを送られた順に参照する（取り出さない）
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.queue.iter()
+    }
+    
+    /// 溜まっているイベントの数
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+    
+    /// イベントが溜まっていないかどうか
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+    
+    /// 上限を超えて捨てたイベントの数
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+    
+    /// 溜まっているイベントを捨てる
+    pub fn clear(&mut self) {
+        self.queue.clear();
+    }
+}
@@ -0,0 +1,808 @@
+use std::any::TypeId;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::marker::PhantomData;
+use crate::error::{GameError, GameResult};
+use crate::ecs::world::World;
+use crate::ecs::entity::EntityId;
+use crate::ecs::component::{Component, ComponentStorage};
+use crate::ecs::resources::{ResourceManager, Res, ResMut};
+use crate::utils::short_type_name;
+use crate::ecs::system::{System, SystemPhase, SystemPriority, SystemLabel};
+
+/// アクセス対象の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessKind {
+    Resource,   // リソース
+    Component,  // コンポーネント
+}
+
+/// アクセス対象（リソースまたはコンポーネントの型）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccessItem {
+    pub kind: AccessKind,
+    pub type_id: TypeId,
+    pub type_name: &'static str,
+}
+
+impl AccessItem {
+    /// リソースへのアクセス
+    pub fn resource<T: 'static>() -> Self {
+        Self {
+            kind: AccessKind::Resource,
+            type_id: TypeId::of::<T>(),
+            type_name: short_type_name(std::any::type_name::<T>()),
+        }
+    }
+    
+    /// コンポーネントへのアクセス
+    pub fn component<T: Component>() -> Self {
+        Self {
+            kind: AccessKind::Component,
+            type_id: TypeId::of::<T>(),
+            type_name: T::type_name(),
+        }
+    }
+    
+    /// 同じ対象かどうか
+    fn is_same(&self, other: &AccessItem) -> bool {
+        self.kind == other.kind && self.type_id == other.type_id
+    }
+    
+    /// 表示用の名前
+    pub fn describe(&self) -> String {
+        match self.kind {
+            AccessKind::Resource => format!("リソース {}", self.type_name),
+            AccessKind::Component => format!("コンポーネント {}", self.type_name),
+        }
+    }
+}
+
+/// システムが宣言したデータアクセス
+/// 関数システムのパラメータから自動的に作成され、システム同士の競合の分析に使う
+#[derive(Debug, Clone, Default)]
+pub struct SystemAccess {
+    reads: Vec<AccessItem>,
+    writes: Vec<AccessItem>,
+    
+    // 同じシステムのパラメータ同士の競合（例：ResMut<T>とRes<T>）
+    self_conflicts: Vec<String>,
+}
+
+impl SystemAccess {
+    /// 空のアクセス情報を作成
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// 読み取りアクセスを追加
+    /// コンポーネントはクエリがストレージをWorldから取り出して借りるので、読み取り同士でも2回は借りられない
+    pub fn add_read(&mut self, item: AccessItem) {
+        let already_read = self.reads.iter().any(|read| read.is_same(&item));
+        if self.writes.iter().any(|write| write.is_same(&item)) {
+            self.self_conflicts.push(format!("{} を書き込みと読み取りの両方で借りています", item.type_name));
+        } else if already_read && item.kind == AccessKind::Component {
+            self.self_conflicts.push(format!("{} を2回クエリで借りています", item.type_name));
+        }
+        if !already_read {
+            self.reads.push(item);
+        }
+    }
+    
+    /// 書き込みアクセスを追加
+    pub fn add_write(&mut self, item: AccessItem) {
+        if self.writes.iter().any(|write| write.is_same(&item)) {
+            self.self_conflicts.push(format!("{} を2回書き込みで借りています", item.type_name));
+        } else if self.reads.iter().any(|read| read.is_same(&item)) {
+            self.self_conflicts.push(format!("{} を読み取りと書き込みの両方で借りています", item.type_name));
+        }
+        if !self.writes.iter().any(|write| write.is_same(&item)) {
+            self.writes.push(item);
+        }
+    }
+    
+    /// 読み取りアクセスの一覧
+    pub fn reads(&self) -> &[AccessItem] {
+        &self.reads
+    }
+    
+    /// 書き込みアクセスの一覧
+    pub fn writes(&self) -> &[AccessItem] {
+        &self.writes
+    }
+    
+    /// 同じシステムのパラメータ同士の競合の一覧
+    pub fn self_conflicts(&self) -> &[String] {
+        &self.self_conflicts
+    }
+    
+    /// 他のシステムのアクセスと競合している対象の一覧を返す
+    /// 片方が書き込み、もう片方が読み取りか書き込みで同じ対象を借りている場合に競合とみなす
+    pub fn conflicts_with(&self, other: &SystemAccess) -> Vec<AccessItem> {
+        let mut conflicts: Vec<AccessItem> = Vec::new();
+        let mut push = |item: &AccessItem| {
+            if !conflicts.iter().any(|conflict| conflict.is_same(item)) {
+                conflicts.push(*item);
+            }
+        };
+        
+        for write in &self.writes {
+            if other.reads.iter().chain(&other.writes).any(|item| item.is_same(write)) {
+                push(write);
+            }
+        }
+        for read in &self.reads {
+            if other.writes.iter().any(|item| item.is_same(read)) {
+                push(read);
+            }
+        }
+        
+        conflicts
+    }
+}
+
+/// 取り出したデータを戻す処理
+type ReturnFn = Box<dyn FnOnce(&mut World, &mut ResourceManager)>;
+
+/// 返却キュー
+/// Queryはドロップされるときに、取り出したストレージを戻す処理をここに積む
+/// 関数システムは関数の呼び出し後にキューを処理してWorld/ResourceManagerに戻す
+#[derive(Clone, Default)]
+pub struct ReturnQueue {
+    returns: Rc<RefCell<Vec<ReturnFn>>>,
+}
+
+impl ReturnQueue {
+    /// 戻す処理を積む
+    fn push<F>(&self, return_fn: F)
+    where
+        F: FnOnce(&mut World, &mut ResourceManager) + 'static,
+    {
+        self.returns.borrow_mut().push(Box::new(return_fn));
+    }
+    
+    /// 積まれた処理を全て実行してデータを戻す
+    fn apply(&self, world: &mut World, resources: &mut ResourceManager) {
+        let returns: Vec<ReturnFn> = self.returns.borrow_mut().drain(..).collect();
+        for return_fn in returns {
+            return_fn(world, resources);
+        }
+    }
+}
+
+/// 関数システムのパラメータ
+/// 実行前にWorldやResourceManagerから取り出し、ドロップ時に返却キューを通して戻す
+pub trait SystemParam: Sized {
+    /// このパラメータのデータアクセスを記録する
+    fn access(access: &mut SystemAccess);
+    
+    /// 取り出せるかどうかを確認する（必要なリソースがない場合はエラー）
+    fn validate(world: &World, resources: &ResourceManager) -> Result<(), String>;
+    
+    /// World/ResourceManagerから取り出す（`validate`が成功した後に呼ばれる）
+    fn fetch(world: &mut World, resources: &mut ResourceManager, returns: &ReturnQueue) -> Self;
+}
+
+// リソースの読み取り用パラメータ
+// システムの実行中はガードで読み取りとして借用される
+impl<T: 'static> SystemParam for Res<T> {
+    fn access(access: &mut SystemAccess) {
+        access.add_read(AccessItem::resource::<T>());
+    }
+    
+    fn validate(_world: &World, resources: &ResourceManager) -> Result<(), String> {
+        resources.try_borrow::<T>().map(|_| ()).map_err(|e| e.to_string())
+    }
+    
+    fn fetch(_world: &mut World, resources: &mut ResourceManager, _returns: &ReturnQueue) -> Self {
+        resources.borrow::<T>()
+    }
+}
+
+// リソースの書き込み用パラメータ
+// システムの実行中はガードで書き込みとして借用される
+impl<T: 'static> SystemParam for ResMut<T> {
+    fn access(access: &mut SystemAccess) {
+        access.add_write(AccessItem::resource::<T>());
+    }
+    
+    fn validate(_world: &World, resources: &ResourceManager) -> Result<(), String> {
+        resources.try_borrow_mut::<T>().map(|_| ()).map_err(|e| e.to_string())
+    }
+    
+    fn fetch(_world: &mut World, resources: &mut ResourceManager, _returns: &ReturnQueue) -> Self {
+        resources.borrow_mut::<T>()
+    }
+}
+
+/// 存在しないかもしれないリソースなどのパラメータ
+/// 取り出せない場合はNoneになる
+impl<P: SystemParam> SystemParam for Option<P> {
+    fn access(access: &mut SystemAccess) {
+        P::access(access);
+    }
+    
+    fn validate(_world: &World, _resources: &ResourceManager) -> Result<(), String> {
+        Ok(())
+    }
+    
+    fn fetch(world: &mut World, resources: &mut ResourceManager, returns: &ReturnQueue) -> Self {
+        match P::validate(world, resources) {
+            Ok(()) => Some(P::fetch(world, resources, returns)),
+            Err(_) => None,
+        }
+    }
+}
+
+/// クエリで取得するデータ
+/// `&T`で読み取り、`&mut T`で書き込み、タプルで複数のコンポーネントをまとめて取得する
+pub trait QueryData {
+    /// 実行中に保持するストレージ
+    type Fetch: 'static;
+    
+    /// 1エンティティ分の取得結果
+    type Item<'a>;
+    
+    /// データアクセスを記録する
+    fn access(access: &mut SystemAccess);
+    
+    /// Worldからストレージを取り出す
+    fn take(world: &mut World) -> Self::Fetch;
+    
+    /// ストレージをWorldに戻す
+    fn restore(world: &mut World, fetch: Self::Fetch);
+    
+    /// 対象になりうるエンティティの一覧
+    fn candidates(fetch: &Self::Fetch) -> Vec<EntityId>;
+    
+    /// エンティティが全てのコンポーネントを持っているか
+    fn matches(fetch: &Self::Fetch, entity_id: EntityId) -> bool;
+    
+    /// エンティティのデータを取得する
+    fn get<'a>(fetch: &'a mut Self::Fetch, entity_id: EntityId, tick: u32) -> Option<Self::Item<'a>>;
+}
+
+impl<T: Component> QueryData for &T {
+    type Fetch = ComponentStorage<T>;
+    type Item<'a> = &'a T;
+    
+    fn access(access: &mut SystemAccess) {
+        access.add_read(AccessItem::component::<T>());
+    }
+    
+    fn take(world: &mut World) -> Self::Fetch {
+        world.take_component_storage::<T>()
+    }
+    
+    fn restore(world: &mut World, fetch: Self::Fetch) {
+        world.restore_component_storage(fetch);
+    }
+    
+    fn candidates(fetch: &Self::Fetch) -> Vec<EntityId> {
+        fetch.iter().map(|(entity_id, _)| *entity_id).collect()
+    }
+    
+    fn matches(fetch: &Self::Fetch, entity_id: EntityId) -> bool {
+        fetch.has(&entity_id)
+    }
+    
+    fn get<'a>(fetch: &'a mut Self::Fetch, entity_id: EntityId, _tick: u32) -> Option<Self::Item<'a>> {
+        fetch.get(&entity_id)
+    }
+}
+
+impl<T: Component> QueryData for &mut T {
+    type Fetch = ComponentStorage<T>;
+    type Item<'a> = &'a mut T;
+    
+    fn access(access: &mut SystemAccess) {
+        access.add_write(AccessItem::component::<T>());
+    }
+    
+    fn take(world: &mut World) -> Self::Fetch {
+        world.take_component_storage::<T>()
+    }
+    
+    fn restore(world: &mut World, fetch: Self::Fetch) {
+        world.restore_component_storage(fetch);
+    }
+    
+    fn candidates(fetch: &Self::Fetch) -> Vec<EntityId> {
+        fetch.iter().map(|(entity_id, _)| *entity_id).collect()
+    }
+    
+    fn matches(fetch: &Self::Fetch, entity_id: EntityId) -> bool {
+        fetch.has(&entity_id)
+    }
+    
+    // 可変参照を取得した時点で変更されたものとして記録される
+    fn get<'a>(fetch: &'a mut Self::Fetch, entity_id: EntityId, tick: u32) -> Option<Self::Item<'a>> {
+        fetch.get_mut(&entity_id, tick)
+    }
+}
+
+// タプルのクエリを実装するマクロ
+// 候補は先頭のコンポーネントを持つエンティティから絞り込む
+macro_rules! impl_query_data_tuple {
+    ($first:ident $(, $rest:ident)*) => {
+        #[allow(non_snake_case)]
+        impl<$first: QueryData $(, $rest: QueryData)*> QueryData for ($first, $($rest,)*) {
+            type Fetch = ($first::Fetch, $($rest::Fetch,)*);
+            type Item<'a> = ($first::Item<'a>, $($rest::Item<'a>,)*);
+            
+            fn access(access: &mut SystemAccess) {
+                $first::access(access);
+                $($rest::access(access);)*
+            }
+            
+            fn take(world: &mut World) -> Self::Fetch {
+                ($first::take(world), $($rest::take(world),)*)
+            }
+            
+            fn restore(world: &mut World, fetch: Self::Fetch) {
+                let ($first, $($rest,)*) = fetch;
+                $first::restore(world, $first);
+                $($rest::restore(world, $rest);)*
+            }
+            
+            fn candidates(fetch: &Self::Fetch) -> Vec<EntityId> {
+                let ($first, $($rest,)*) = fetch;
+                $first::candidates($first)
+                    .into_iter()
+                    .filter(|&_entity_id| true $(&& $rest::matches($rest, _entity_id))*)
+                    .collect()
+            }
+            
+            fn matches(fetch: &Self::Fetch, entity_id: EntityId) -> bool {
+                let ($first, $($rest,)*) = fetch;
+                $first::matches($first, entity_id) $(&& $rest::matches($rest, entity_id))*
+            }
+            
+            fn get<'a>(fetch: &'a mut Self::Fetch, entity_id: EntityId, tick: u32) -> Option<Self::Item<'a>> {
+                // 先に全て持っているか確認してから取得する（途中で失敗して変更が記録されないように）
+                if !Self::matches(fetch, entity_id) {
+                    return None;
+                }
+                let ($first, $($rest,)*) = fetch;
+                Some(($first::get($first, entity_id, tick)?, $($rest::get($rest, entity_id, tick)?,)*))
+            }
+        }
+    };
+}
+
+impl_query_data_tuple!(A);
+impl_query_data_tuple!(A, B);
+impl_query_data_tuple!(A, B, C);
+impl_query_data_tuple!(A, B, C, D);
+
+/// コンポーネントのクエリ
+/// `Query<&mut CardInfo>`や`Query<(&Transform, &mut Renderable)>`のように関数システムの引数で使う
+/// システムの実行中は対象の型のコンポーネントストレージがWorldから取り出されている
+/// Nameを書き込みで借りた場合、名前の索引は更新されないので注意
+pub struct Query<Q: QueryData + 'static> {
+    // ドロップ時に返却するためOptionで保持する
+    fetch: Option<Q::Fetch>,
+    
+    // 可変参照を取得したときに変更として記録するティック
+    tick: u32,
+    
+    returns: ReturnQueue,
+}
+
+impl<Q: QueryData + 'static> Drop for Query<Q> {
+    fn drop(&mut self) {
+        if let Some(fetch) = self.fetch.take() {
+            self.returns.push(move |world, _| Q::restore(world, fetch));
+        }
+    }
+}
+
+impl<Q: QueryData + 'static> Query<Q> {
+    /// 取り出しているストレージ
+    fn fetch(&self) -> &Q::Fetch {
+        self.fetch.as_ref().unwrap()
+    }
+    
+    /// 条件を満たすエンティティの一覧（ID順）
+    pub fn entities(&self) -> Vec<EntityId> {
+        let mut entities = Q::candidates(self.fetch());
+        entities.sort_unstable();
+        entities
+    }
+    
+    /// エンティティが条件を満たしているか
+    pub fn contains(&self, entity_id: EntityId) -> bool {
+        Q::matches(self.fetch(), entity_id)
+    }
+    
+    /// エンティティのデータを取得
+    pub fn get(&mut self, entity_id: EntityId) -> Option<Q::Item<'_>> {
+        Q::get(self.fetch.as_mut().unwrap(), entity_id, self.tick)
+    }
+    
+    /// 条件を満たす全てのエンティティに対して処理を行う（ID順）
+    pub fn for_each<F>(&mut self, mut f: F)
+    where
+        F: FnMut(EntityId, Q::Item<'_>),
+    {
+        for entity_id in self.entities() {
+            if let Some(item) = Q::get(self.fetch.as_mut().unwrap(), entity_id, self.tick) {
+                f(entity_id, item);
+            }
+        }
+    }
+    
+    /// 条件を満たすエンティティの数
+    pub fn len(&self) -> usize {
+        Q::candidates(self.fetch()).len()
+    }
+    
+    /// 条件を満たすエンティティがないかどうか
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<Q: QueryData + 'static> SystemParam for Query<Q> {
+    fn access(access: &mut SystemAccess) {
+        Q::access(access);
+    }
+    
+    fn validate(_world: &World, _resources: &ResourceManager) -> Result<(), String> {
+        Ok(())
+    }
+    
+    fn fetch(world: &mut World, _resources: &mut ResourceManager, returns: &ReturnQueue) -> Self {
+        Self {
+            tick: world.change_tick(),
+            fetch: Some(Q::take(world)),
+            returns: returns.clone(),
+        }
+    }
+}
+
+// パラメータのタプルを実装するマクロ
+// 全てのパラメータが取り出せることを確認してから、引数の順に取り出す
+macro_rules! impl_system_param_tuple {
+    ($($param:ident),*) => {
+        #[allow(non_snake_case, unused_variables)]
+        impl<$($param: SystemParam),*> SystemParam for ($($param,)*) {
+            fn access(access: &mut SystemAccess) {
+                $($param::access(access);)*
+            }
+            
+            fn validate(world: &World, resources: &ResourceManager) -> Result<(), String> {
+                $($param::validate(world, resources)?;)*
+                Ok(())
+            }
+            
+            fn fetch(world: &mut World, resources: &mut ResourceManager, returns: &ReturnQueue) -> Self {
+                ($($param::fetch(world, resources, returns),)*)
+            }
+        }
+    };
+}
+
+// 引数のない関数システム用
+impl SystemParam for () {
+    fn access(_access: &mut SystemAccess) {}
+    
+    fn validate(_world: &World, _resources: &ResourceManager) -> Result<(), String> {
+        Ok(())
+    }
+    
+    fn fetch(_world: &mut World, _resources: &mut ResourceManager, _returns: &ReturnQueue) -> Self {}
+}
+
+impl_system_param_tuple!(P0);
+impl_system_param_tuple!(P0, P1);
+impl_system_param_tuple!(P0, P1, P2);
+impl_system_param_tuple!(P0, P1, P2, P3);
+impl_system_param_tuple!(P0, P1, P2, P3, P4);
+impl_system_param_tuple!(P0, P1, P2, P3, P4, P5);
+impl_system_param_tuple!(P0, P1, P2, P3, P4, P5, P6);
+impl_system_param_tuple!(P0, P1, P2, P3, P4, P5, P6, P7);
+
+/// 関数システムの戻り値
+/// `()`または`GameResult<()>`を返す関数をシステムとして使える
+pub trait SystemOutput {
+    fn into_result(self) -> GameResult<()>;
+}
+
+impl SystemOutput for () {
+    fn into_result(self) -> GameResult<()> {
+        Ok(())
+    }
+}
+
+impl SystemOutput for GameResult<()> {
+    fn into_result(self) -> GameResult<()> {
+        self
+    }
+}
+
+/// システムとして使える関数
+/// Markerは引数の型で実装を区別するための型（`fn(P0, P1) -> Out`）
+pub trait SystemParamFunction<Marker>: 'static {
+    /// 引数をまとめたタプル
+    type Params: SystemParam;
+    
+    /// 関数を呼び出す
+    fn call(&mut self, params: Self::Params) -> GameResult<()>;
+}
+
+// 引数の数ごとにSystemParamFunctionを実装するマクロ
+macro_rules! impl_system_param_function {
+    ($($param:ident),*) => {
+        impl<Func, Out, $($param),*> SystemParamFunction<fn($($param,)*) -> Out> for Func
+        where
+            Func: FnMut($($param),*) -> Out + 'static,
+            Out: SystemOutput + 'static,
+            $($param: SystemParam + 'static,)*
+        {
+            type Params = ($($param,)*);
+            
+            #[allow(non_snake_case)]
+            fn call(&mut self, params: Self::Params) -> GameResult<()> {
+                let ($($param,)*) = params;
+                (self)($($param),*).into_result()
+            }
+        }
+    };
+}
+
+impl_system_param_function!();
+impl_system_param_function!(P0);
+impl_system_param_function!(P0, P1);
+impl_system_param_function!(P0, P1, P2);
+impl_system_param_function!(P0, P1, P2, P3);
+impl_system_param_function!(P0, P1, P2, P3, P4);
+impl_system_param_function!(P0, P1, P2, P3, P4, P5);
+impl_system_param_function!(P0, P1, P2, P3, P4, P5, P6);
+impl_system_param_function!(P0, P1, P2, P3, P4, P5, P6, P7);
+
+/// 関数システム
+/// `fn flip_system(query: Query<&mut CardInfo>, time: Res<TimeInfo>)`のような関数をSystemとして実行する
+/// 引数は実行のたびにWorld/ResourceManagerから自動的に取り出される
+pub struct FunctionSystem<Marker, F> {
+    func: F,
+    name: &'static str,
+    phase: SystemPhase,
+    priority: SystemPriority,
+    labels: Vec<SystemLabel>,
+    before: Vec<SystemLabel>,
+    after: Vec<SystemLabel>,
+    access: SystemAccess,
+    _marker: PhantomData<fn() -> Marker>,
+}
+
+impl<Marker: 'static, F: SystemParamFunction<Marker>> FunctionSystem<Marker, F> {
+    /// 関数から関数システムを作成
+    /// 名前は関数名、フェーズはUpdate
+    pub fn new(func: F) -> Self {
+        let mut access = SystemAccess::new();
+        F::Params::access(&mut access);
+        
+        Self {
+            func,
+            name: short_type_name(std::any::type_name::<F>()),
+            phase: SystemPhase::Update,
+            priority: SystemPriority(0),
+            labels: Vec::new(),
+            before: Vec::new(),
+            after: Vec::new(),
+            access,
+            _marker: PhantomData,
+        }
+    }
+    
+    /// 名前を設定
+    pub fn with_name(mut self, name: &'static str) -> Self {
+        self.name = name;
+        self
+    }
+    
+    /// フェーズを設定
+    pub fn with_phase(mut self, phase: SystemPhase) -> Self {
+        self.phase = phase;
+        self
+    }
+    
+    /// 優先度を設定
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = SystemPriority(priority);
+        self
+    }
+    
+    /// ラベルを追加
+    pub fn with_label(mut self, label: SystemLabel) -> Self {
+        self.labels.push(label);
+        self
+    }
+    
+    /// このシステムより後に実行されるべきラベルを追加
+    pub fn before(mut self, label: SystemLabel) -> Self {
+        self.before.push(label);
+        self
+    }
+    
+    /// このシステムより先に実行されるべきラベルを追加
+    pub fn after(mut self, label: SystemLabel) -> Self {
+        self.after.push(label);
+        self
+    }
+}
+
+impl<Marker: 'static, F: SystemParamFunction<Marker>> System for FunctionSystem<Marker, F> {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+    
+    fn phase(&self) -> SystemPhase {
+        self.phase
+    }
+    
+    fn priority(&self) -> SystemPriority {
+        self.priority
+    }
+    
+    fn labels(&self) -> Vec<SystemLabel> {
+        let mut labels = vec![self.name];
+        labels.extend(self.labels.iter().copied());
+        labels
+    }
+    
+    fn before(&self) -> Vec<SystemLabel> {
+        self.before.clone()
+    }
+    
+    fn after(&self) -> Vec<SystemLabel> {
+        self.after.clone()
+    }
+    
+    fn access(&self) -> Option<&SystemAccess> {
+        Some(&self.access)
+    }
+    
+    fn run(&mut self, world: &mut World, resources: &mut ResourceManager, _delta_time: f32) -> GameResult<()> {
+        // 1. 引数を取り出せるか確認
+        // 引数同士が競合しているとストレージを二重に取り出して上書きしてしまうので、実行順序の構築を経ずに呼ばれても実行しない
+        if let Some(conflict) = self.access.self_conflicts().first() {
+            return Err(GameError::Schedule(format!("システム {} の引数のアクセスが競合しています: {}", self.name, conflict)));
+        }
+        F::Params::validate(world, resources)
+            .map_err(|e| GameError::InvalidOperation(format!("{} の引数を取得できません: {}", self.name, e)))?;
+        
+        // 2. 引数を取り出して関数を呼び出す
+        let returns = ReturnQueue::default();
+        let params = F::Params::fetch(world, resources, &returns);
+        let result = self.func.call(params);
+        
+        // 3. 引数はドロップ時に返却キューに積まれているので、World/ResourceManagerに戻す
+        returns.apply(world, resources);
+        
+        result
+    }
+}
+
+/// システムに変換できる型
+/// Systemを実装した構造体と、引数がSystemParamの関数の両方を`add_system`に渡せるようにする
+pub trait IntoSystem<Marker> {
+    type System: System + 'static;
+    
+    /// システムに変換する
+    fn into_system(self) -> Self::System;
+}
+
+impl<S: System + 'static> IntoSystem<()> for S {
+    type System = S;
+    
+    fn into_system(self) -> S {
+        self
+    }
+}
+
+/// 関数システム用のIntoSystemの実装を区別するための型
+pub struct IsFunctionSystem;
+
+impl<Marker: 'static, F: SystemParamFunction<Marker>> IntoSystem<(IsFunctionSystem, Marker)> for F {
+    type System = FunctionSystem<Marker, F>;
+    
+    fn into_system(self) -> Self::System {
+        FunctionSystem::new(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ecs::component::Transform;
+    use crate::ecs::system::SystemManager;
+    
+    fn world_with_transform() -> (World, EntityId) {
+        let mut world = World::new();
+        let entity_id = world.create_entity().unwrap();
+        world.add_component(entity_id, Transform::new(10.0, 20.0)).unwrap();
+        (world, entity_id)
+    }
+    
+    #[test]
+    fn two_queries_of_same_component_are_rejected() {
+        let (mut world, entity_id) = world_with_transform();
+        let mut resources = ResourceManager::new();
+        let mut system = FunctionSystem::new(|_a: Query<&Transform>, _b: Query<&Transform>| {});
+        assert!(!system.access.self_conflicts().is_empty());
+        
+        assert!(system.run(&mut world, &mut resources, 1.0 / 60.0).is_err());
+        assert_eq!(world.get_component::<Transform>(entity_id).map(|t| t.position.x), Some(10.0));
+    }
+    
+    #[test]
+    fn repeated_component_in_one_query_is_rejected() {
+        let (mut world, entity_id) = world_with_transform();
+        let mut resources = ResourceManager::new();
+        let mut system = FunctionSystem::new(|_query: Query<(&Transform, &Transform)>| {});
+        
+        assert!(system.run(&mut world, &mut resources, 1.0 / 60.0).is_err());
+        assert!(world.get_component::<Transform>(entity_id).is_some());
+    }
+    
+    #[test]
+    fn schedule_rejects_two_queries_of_same_component() {
+        let (mut world, entity_id) = world_with_transform();
+        let mut resources = ResourceManager::new();
+        let mut manager = SystemManager::new();
+        manager.add_system(|_a: Query<&Transform>, _b: Query<&Transform>| {});
+        
+        assert!(manager.build_schedule().is_err());
+        assert!(manager.run_systems(&mut world, &mut resources, 1.0 / 60.0).is_err());
+        assert!(world.get_component::<Transform>(entity_id).is_some());
+    }
+    
+    #[test]
+    fn single_query_keeps_storage() {
+        let (mut world, entity_id) = world_with_transform();
+        let mut resources = ResourceManager::new();
+        let mut system = FunctionSystem::new(|query: Query<&Transform>| {
+            assert_eq!(query.len(), 1);
+        });
+        
+        system.run(&mut world, &mut resources, 1.0 / 60.0).unwrap();
+        assert!(world.get_component::<Transform>(entity_id).is_some());
+    }
+    
+    struct Speed(f64);
+    
+    #[test]
+    fn query_mut_with_res_writes_components_and_marks_them_changed() {
+        let (mut world, entity_id) = world_with_transform();
+        let mut resources = ResourceManager::new();
+        resources.add(Speed(5.0));
+        let mut manager = SystemManager::new();
+        manager.add_system(|mut query: Query<&mut Transform>, speed: Res<Speed>| {
+            query.for_each(|_, transform| transform.position.x += speed.0);
+        });
+        
+        // 1回目の実行で書き込まれ、ストレージとリソースが戻される
+        // 基準のティックは、この前に別のシステムが実行されたものとして進めておく
+        let before_tick = world.increment_change_tick();
+        manager.run_systems(&mut world, &mut resources, 1.0 / 60.0).unwrap();
+        assert_eq!(world.get_component::<Transform>(entity_id).map(|t| t.position.x), Some(15.0));
+        assert_eq!(world.get_entities_with_changed_since::<Transform>(before_tick), vec![entity_id]);
+        assert!(resources.try_borrow_mut::<Speed>().is_ok());
+        
+        // 2回目の実行は1回目の書き込みを見る
+        let second_tick = world.increment_change_tick();
+        manager.run_systems(&mut world, &mut resources, 1.0 / 60.0).unwrap();
+        assert_eq!(world.get_component::<Transform>(entity_id).map(|t| t.position.x), Some(20.0));
+        assert_eq!(world.get_entities_with_changed_since::<Transform>(second_tick), vec![entity_id]);
+    }
+    
+    #[test]
+    fn zero_argument_function_runs() {
+        let mut world = World::new();
+        let mut resources = ResourceManager::new();
+        let mut system = FunctionSystem::new(|| {});
+        assert!(system.run(&mut world, &mut resources, 1.0 / 60.0).is_ok());
+    }
+}
@@ -0,0 +1,283 @@
+use crate::error::{GameError, GameResult};
+use crate::ecs::world::World;
+use crate::ecs::entity::EntityId;
+use crate::ecs::component::Transform;
+use crate::ecs::system::{System, SystemPhase, SystemLabel};
+use crate::ecs::resources::ResourceManager;
+use crate::constants::{LABEL_LAYOUT, LABEL_TRANSFORM_PROPAGATION};
+use crate::utils::Vec2;
+use serde::{Serialize, Deserialize};
+use ecs_wasm_game4_derive::Component;
+
+/// 親コンポーネント
+/// このエンティティのTransformは親のTransformとLocalTransformから計算される
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, Component)]
+#[component(storage = "sparse", serialize)]
+pub struct Parent(pub EntityId);
+
+/// 子コンポーネント
+/// このエンティティを親に持つエンティティの一覧
+#[derive(Clone, Debug, Default, Serialize, Deserialize, Component)]
+#[component(storage = "sparse", serialize)]
+pub struct Children(pub Vec<EntityId>);
+
+/// ローカルトランスフォームコンポーネント
+/// 親からの相対位置と相対的な描画順序
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, Component)]
+#[component(serialize, reflect)]
+pub struct LocalTransform {
+    pub position: Vec2,
+    pub z_index: i32,
+}
+
+impl LocalTransform {
+    pub fn new(x: f64, y: f64) -> Self {
+        Self {
+            position: Vec2::new(x, y),
+            z_index: 0,
+        }
+    }
+    
+    pub fn with_z_index(mut self, z_index: i32) -> Self {
+        self.z_index = z_index;
+        self
+    }
+}
+
+/// エンティティの親を設定する
+/// 既に別の親がいる場合は、その親の子リストから外してから付け替える
+/// 自分自身や自分の子孫を親にしようとした場合は、親子関係が循環するのでエラーを返す
+pub fn set_parent(world: &mut World, child: EntityId, parent: EntityId, local: LocalTransform) -> GameResult<()> {
+    // 0. 新しい親から祖先を辿り、子が含まれていないか確認
+    let mut ancestor = Some(parent);
+    while let Some(id) = ancestor {
+        if id == child {
+            return Err(GameError::InvalidOperation(format!(
+                "エンティティ {} を {} の子にすると親子関係が循環します", child, parent
+            )));
+        }
+        ancestor = world.get_component::<Parent>(id).map(|p| p.0);
+    }
+    
+    // 1. 今の親から外す
+    let current_parent = world.get_component::<Parent>(child).map(|p| p.0);
+    if current_parent != Some(parent) {
+        if let Some(old_parent) = current_parent {
+            remove_from_children(world, old_parent, child);
+        }
+        world.add_component(child, Parent(parent))?;
+        
+        // 2. 新しい親の子リストに追加
+        match world.get_component_mut::<Children>(parent) {
+            Some(children) => children.0.push(child),
+            None => world.add_component(parent, Children(vec![child]))?,
+        }
+    }
+    
+    // 3. 相対位置を設定（変わらない場合は触らない）
+    let unchanged = world.get_component::<LocalTransform>(child).is_some_and(|current| {
+        current.position.x == local.position.x
+            && current.position.y == local.position.y
+            && current.z_index == local.z_index
+    });
+    if !unchanged {
+        world.add_component(child, local)?;
+    }
+    
+    Ok(())
+}
+
+/// エンティティを親から切り離す
+/// Transformは切り離した時点のワールド座標のまま残る
+pub fn remove_parent(world: &mut World, child: EntityId) {
+    if let Some(Parent(parent)) = world.remove_component::<Parent>(child) {
+        remove_from_children(world, parent, child);
+    }
+    world.remove_component::<LocalTransform>(child);
+}
+
+/// 親の子リストから指定したエンティティを取り除く
+fn remove_from_children(world: &mut World, parent: EntityId, child: EntityId) {
+    let now_empty = match world.get_component_mut::<Children>(parent) {
+        Some(children) => {
+            children.0.retain(|&id| id != child);
+            children.0.is_empty()
+        },
+        None => false,
+    };
+    
+    if now_empty {
+        world.remove_component::<Children>(parent);
+    }
+}
+
+/// 親のTransformから子のTransformを計算する
+pub fn propagate_transforms(world: &mut World) {
+    // 親を持たない、子を持つエンティティから辿る
+    let mut roots: Vec<EntityId> = world
+        .get_entities_with_component::<Children>()
+        .into_iter()
+        .filter(|&id| !world.has_component::<Parent>(id))
+        .collect();
+    roots.sort_unstable();
+    
+    for root in roots {
+        propagate_from(world, root);
+    }
+}
+
+/// 指定したエンティティの子孫のTransformを再帰的に更新する
+fn propagate_from(world: &mut World, parent: EntityId) {
+    let (parent_position, parent_z_index) = match world.get_component::<Transform>(parent) {
+        Some(transform) => (transform.position, transform.z_index),
+        None => return,
+    };
+    let children = match world.get_component::<Children>(parent) {
+        Some(children) => children.0.clone(),
+        None => return,
+    };
+    
+    for child in children {
+        let local = match world.get_component::<LocalTransform>(child) {
+            Some(local) => *local,
+            None => LocalTransform::default(),
+        };
+        
+        let x = parent_position.x + local.position.x;
+        let y = parent_position.y + local.position.y;
+        let z_index = parent_z_index + local.z_index;
+        
+        // 値が変わらない場合はTransformに触れず、変更検出を汚さないようにする
+        let needs_update = match world.get_component::<Transform>(child) {
+            Some(transform) => {
+                transform.position.x != x || transform.position.y != y || transform.z_index != z_index
+            },
+            None => false,
+        };
+        if needs_update {
+            if let Some(transform) = world.get_component_mut::<Transform>(child) {
+                transform.position.x = x;
+                transform.position.y = y;
+                transform.z_index = z_index;
+            }
+        }
+        
+        propagate_from(world, child);
+    }
+}
+
+/// トランスフォーム伝播システム
+/// 毎フレーム、親子関係に沿ってワールド座標のTransformを更新する
+pub struct TransformPropagationSystem;
+
+impl System for TransformPropagationSystem {
+    fn name(&self) -> &'static str {
+        "TransformPropagationSystem"
+    }
+    
+    fn phase(&self) -> SystemPhase {
+        SystemPhase::Render  // 配置が確定してから描画の直前に実行
+    }
+    
+    fn labels(&self) -> Vec<SystemLabel> {
+        vec![self.name(), LABEL_TRANSFORM_PROPAGATION]
+    }
+    
+    fn after(&self) -> Vec<SystemLabel> {
+        vec![LABEL_LAYOUT]  // スタックのレイアウトで親子関係が決まった後に実行
+    }
+    
+    fn run(&mut self, world: &mut World, _resources: &mut ResourceManager, _delta_time: f32) -> GameResult<()> {
+        propagate_transforms(world);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn spawn(world: &mut World, x: f64, y: f64) -> EntityId {
+        let entity_id = world.create_entity().unwrap();
+        world.add_component(entity_id, Transform::new(x, y)).unwrap();
+        entity_id
+    }
+    
+    fn children(world: &World, parent: EntityId) -> Vec<EntityId> {
+        world.get_component::<Children>(parent).map(|children| children.0.clone()).unwrap_or_default()
+    }
+    
+    fn position(world: &World, entity_id: EntityId) -> (f64, f64, i32) {
+        let transform = world.get_component::<Transform>(entity_id).unwrap();
+        (transform.position.x, transform.position.y, transform.z_index)
+    }
+    
+    #[test]
+    fn reparenting_moves_the_child_between_children_lists() {
+        let mut world = World::new();
+        let a = spawn(&mut world, 0.0, 0.0);
+        let b = spawn(&mut world, 0.0, 0.0);
+        let child = spawn(&mut world, 0.0, 0.0);
+        
+        set_parent(&mut world, child, a, LocalTransform::default()).unwrap();
+        set_parent(&mut world, child, b, LocalTransform::default()).unwrap();
+        
+        assert_eq!(world.get_component::<Parent>(child), Some(&Parent(b)));
+        assert_eq!(children(&world, b), vec![child]);
+        // 子がいなくなった親からはChildrenが外れる
+        assert!(!world.has_component::<Children>(a));
+    }
+    
+    #[test]
+    fn removing_the_parent_clears_both_sides() {
+        let mut world = World::new();
+        let parent = spawn(&mut world, 0.0, 0.0);
+        let first = spawn(&mut world, 0.0, 0.0);
+        let second = spawn(&mut world, 0.0, 0.0);
+        set_parent(&mut world, first, parent, LocalTransform::default()).unwrap();
+        set_parent(&mut world, second, parent, LocalTransform::default()).unwrap();
+        
+        remove_parent(&mut world, first);
+        assert!(!world.has_component::<Parent>(first));
+        assert!(!world.has_component::<LocalTransform>(first));
+        assert_eq!(children(&world, parent), vec![second]);
+        
+        remove_parent(&mut world, second);
+        assert!(!world.has_component::<Children>(parent));
+    }
+    
+    #[test]
+    fn cycles_are_rejected() {
+        let mut world = World::new();
+        let root = spawn(&mut world, 0.0, 0.0);
+        let middle = spawn(&mut world, 0.0, 0.0);
+        let leaf = spawn(&mut world, 0.0, 0.0);
+        set_parent(&mut world, middle, root, LocalTransform::default()).unwrap();
+        set_parent(&mut world, leaf, middle, LocalTransform::default()).unwrap();
+        
+        assert!(set_parent(&mut world, root, root, LocalTransform::default()).is_err());
+        assert!(set_parent(&mut world, root, leaf, LocalTransform::default()).is_err());
+        // 失敗しても親子関係は変わらない
+        assert!(!world.has_component::<Parent>(root));
+        assert_eq!(children(&world, leaf), Vec::<EntityId>::new());
+    }
+    
+    #[test]
+    fn transforms_propagate_through_two_levels() {
+        let mut world = World::new();
+        let root = spawn(&mut world, 100.0, 50.0);
+        let middle = spawn(&mut world, 0.0, 0.0);
+        let leaf = spawn(&mut world, 0.0, 0.0);
+        set_parent(&mut world, middle, root, LocalTransform::new(10.0, 20.0).with_z_index(1)).unwrap();
+        set_parent(&mut world, leaf, middle, LocalTransform::new(0.0, 30.0).with_z_index(1)).unwrap();
+        
+        propagate_transforms(&mut world);
+        assert_eq!(position(&world, middle), (110.0, 70.0, 1));
+        assert_eq!(position(&world, leaf), (110.0, 100.0, 2));
+        
+        // 根を動かすと孫まで追従する
+        world.get_component_mut::<Transform>(root).unwrap().position.x = 200.0;
+        propagate_transforms(&mut world);
+        assert_eq!(position(&world, leaf), (210.0, 100.0, 2));
+    }
+}
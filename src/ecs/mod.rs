@@ -10,12 +10,34 @@
 pub mod entity;      // エンティティ管理
 pub mod component;   // コンポーネント定義
 pub mod system;      // システム定義
+pub mod system_registry; // 名前からシステムを作る登録簿
+pub mod function_system; // 関数システムと引数（Res/ResMut/Query）
 pub mod world;       // ワールド（ゲーム全体の状態）
 pub mod resources;   // リソース（グローバルな状態）
+pub mod condition;   // システムの実行条件
+pub mod profiler;    // システムの実行時間の計測
+pub mod error_log;   // システムが返したエラーの記録
+pub mod event;       // システムから送るイベントのキュー
+pub mod snapshot;    // ワールドのシリアライズと復元
+pub mod hierarchy;   // 親子関係とトランスフォームの伝播
+pub mod bundle;      // 複数コンポーネントをまとめて追加するバンドル
+pub mod reflect;     // コンポーネントのリフレクション（型レジストリ）
+pub mod app;         // アプリケーションとプラグイン
 
 // モジュール内で使用する型をエクスポート
 pub use self::entity::*;
 pub use self::component::*;
 pub use self::system::*;
+pub use self::system_registry::*;
+pub use self::function_system::*;
 pub use self::world::*;
-pub use self::resources::*; 
\ No newline at end of file
+pub use self::resources::*;
+pub use self::condition::*;
+pub use self::profiler::*;
+pub use self::error_log::*;
+pub use self::event::*;
+pub use self::snapshot::*;
+pub use self::hierarchy::*;
+pub use self::bundle::*;
+pub use self::reflect::*;
+pub use self::app::*; 
\ No newline at end of file
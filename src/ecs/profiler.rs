@@ -0,0 +1,122 @@
+use std::collections::{HashMap, VecDeque};
+use serde::Serialize;
+use crate::constants::PROFILER_WINDOW;
+
+/// 計測値の統計
+/// 直近の一定数のサンプルから平均・最小値・最大値を計算する
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct TimingStats {
+    pub last_ms: f64,   // 最後に計測した時間（ミリ秒）
+    pub avg_ms: f64,    // 直近のサンプルの平均（ミリ秒）
+    pub min_ms: f64,    // 直近のサンプルの最小値（ミリ秒）
+    pub max_ms: f64,    // 直近のサンプルの最大値（ミリ秒）
+    pub count: u64,     // 計測した総回数
+    #[serde(skip)]
+    samples: VecDeque<f64>,
+}
+
+impl TimingStats {
+    /// 計測値を記録する
+    pub fn record(&mut self, elapsed_ms: f64) {
+        self.samples.push_back(elapsed_ms);
+        if self.samples.len() > PROFILER_WINDOW {
+            self.samples.pop_front();
+        }
+        
+        self.last_ms = elapsed_ms;
+        self.count += 1;
+        self.avg_ms = self.samples.iter().sum::<f64>() / self.samples.len() as f64;
+        self.min_ms = self.samples.iter().cloned().fold(f64::INFINITY, f64::min);
+        self.max_ms = self.samples.iter().cloned().fold(0.0, f64::max);
+    }
+}
+
+/// システムのプロファイラー
+/// システムごとの実行時間と、フレーム全体・描画の時間を記録するリソース
+#[derive(Default, Serialize)]
+pub struct SystemProfiler {
+    pub frame: TimingStats,                    // フレーム間隔（TimeInfoの経過時間）
+    pub render: TimingStats,                   // Renderer::renderの実行時間
+    pub systems: HashMap<&'static str, TimingStats>, // システム名ごとの実行時間
+}
+
+impl SystemProfiler {
+    /// 新しいプロファイラーを作成
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// フレーム間隔を記録する
+    pub fn record_frame(&mut self, delta_time: f32) {
+        self.frame.record(delta_time as f64 * 1000.0);
+    }
+    
+    /// システムの実行時間を記録する
+    /// 毎フレーム呼ばれるので、キーはシステム名の&'static strのまま使い、文字列を確保しない
+    pub fn record_system(&mut self, name: &'static str, elapsed_ms: f64) {
+        self.systems
+            .entry(name)
+            .or_default()
+            .record(elapsed_ms);
+    }
+    
+    /// 描画の実行時間を記録する
+    pub fn record_render(&mut self, elapsed_ms: f64) {
+        self.render.record(elapsed_ms);
+    }
+    
+    /// 平均実行時間の長い順にシステムの統計を取得
+    pub fn slowest_systems(&self) -> Vec<(&str, &TimingStats)> {
+        let mut systems: Vec<_> = self.systems
+            .iter()
+            .map(|(name, stats)| (*name, stats))
+            .collect();
+        systems.sort_by(|a, b| b.1.avg_ms.total_cmp(&a.1.avg_ms));
+        systems
+    }
+    
+    /// 統計をリセットする
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+    
+    /// 統計をJSON文字列に変換
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_else(|_| "{}".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn timing_stats_use_only_the_recent_window() {
+        let mut stats = TimingStats::default();
+        stats.record(100.0);
+        for _ in 1..PROFILER_WINDOW {
+            stats.record(2.0);
+        }
+        assert_eq!((stats.min_ms, stats.max_ms), (2.0, 100.0));
+        
+        // 窓から外れた古いサンプルは平均・最小値・最大値に含まれない
+        stats.record(4.0);
+        assert_eq!(stats.count, PROFILER_WINDOW as u64 + 1);
+        assert_eq!(stats.last_ms, 4.0);
+        assert_eq!((stats.min_ms, stats.max_ms), (2.0, 4.0));
+        let expected_avg = (2.0 * (PROFILER_WINDOW - 1) as f64 + 4.0) / PROFILER_WINDOW as f64;
+        assert!((stats.avg_ms - expected_avg).abs() < 1e-9);
+    }
+    
+    #[test]
+    fn slowest_systems_are_sorted_by_average() {
+        let mut profiler = SystemProfiler::new();
+        profiler.record_system("Fast", 1.0);
+        profiler.record_system("Slow", 5.0);
+        profiler.record_system("Slow", 3.0);
+        
+        let names: Vec<&str> = profiler.slowest_systems().into_iter().map(|(name, _)| name).collect();
+        assert_eq!(names, vec!["Slow", "Fast"]);
+        assert_eq!(profiler.systems["Slow"].avg_ms, 4.0);
+    }
+}
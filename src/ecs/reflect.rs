@@ -0,0 +1,241 @@
+use std::collections::BTreeMap;
+use crate::error::{GameError, GameResult};
+use serde_json::Value;
+use crate::ecs::world::World;
+use crate::ecs::entity::EntityId;
+use crate::ecs::component::{
+    Reflect, Transform, CardInfo, Renderable, Draggable, Clickable, StackContainer, Droppable, Name,
+};
+use crate::ecs::hierarchy::LocalTransform;
+
+// 型消去された操作の型
+type HasFn = Box<dyn Fn(&World, EntityId) -> bool>;
+type GetFn = Box<dyn Fn(&World, EntityId) -> Option<Value>>;
+type SetFn = Box<dyn Fn(&mut World, EntityId, Value) -> GameResult<()>>;
+type SetFieldFn = Box<dyn Fn(&mut World, EntityId, &str, Value) -> GameResult<()>>;
+type InsertDefaultFn = Box<dyn Fn(&mut World, EntityId) -> GameResult<()>>;
+type RemoveFn = Box<dyn Fn(&mut World, EntityId) -> bool>;
+
+/// 1つのコンポーネント型の登録情報
+/// 具体的な型を知らなくても、名前だけでJSONとして読み書きできるようにする
+pub struct ComponentRegistration {
+    pub name: &'static str,
+    pub field_names: &'static [&'static str],
+    has: HasFn,
+    get: GetFn,
+    set: SetFn,
+    set_field: SetFieldFn,
+    insert_default: Option<InsertDefaultFn>,
+    remove: RemoveFn,
+}
+
+impl ComponentRegistration {
+    /// 型Tの登録情報を作成
+    fn of<T: Reflect>() -> Self {
+        Self {
+            name: T::type_name(),
+            field_names: T::field_names(),
+            has: Box::new(|world, entity_id| world.has_component::<T>(entity_id)),
+            get: Box::new(|world, entity_id| {
+                world.get_component::<T>(entity_id).and_then(|component| component.to_json_value().ok())
+            }),
+            set: Box::new(|world, entity_id, value| {
+                // 置き換えもadd_componentで行い、名前の索引などWorldが持つ情報も更新する
+                let component = T::from_json_value(value).map_err(|e| to_reflection_error(T::type_name(), e))?;
+                world.add_component(entity_id, component)
+            }),
+            set_field: Box::new(|world, entity_id, field, value| {
+                let component = world.get_component_mut::<T>(entity_id).ok_or(GameError::ComponentMissing {
+                    entity: entity_id,
+                    component: T::type_name(),
+                })?;
+                component.set_field(field, value).map_err(|e| to_reflection_error(T::type_name(), e))
+            }),
+            insert_default: None,
+            remove: Box::new(|world, entity_id| world.remove_component::<T>(entity_id).is_some()),
+        }
+    }
+    
+    /// デフォルト値の生成関数を設定
+    fn with_default<T: Reflect>(mut self, make_default: fn() -> T) -> Self {
+        self.insert_default = Some(Box::new(move |world, entity_id| {
+            world.add_component(entity_id, make_default())
+        }));
+        self
+    }
+}
+
+/// リフレクションのエラーをGameErrorに変換
+fn to_reflection_error(name: &str, message: String) -> GameError {
+    GameError::Reflection(format!("{} の読み書きに失敗しました: {}", name, message))
+}
+
+/// 型レジストリ
+/// コンポーネント名から、フィールドの読み書きやデフォルト値の生成を行う関数を引けるようにするリソース
+/// デバッグ用インスペクター、セーブデータ、ネットワーク同期、コンソールコマンドで使う
+#[derive(Default)]
+pub struct TypeRegistry {
+    registrations: BTreeMap<&'static str, ComponentRegistration>,
+}
+
+impl TypeRegistry {
+    /// 空の型レジストリを作成
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// ゲームで使うコンポーネントを登録済みの型レジストリを作成
+    /// デフォルト値は、決まった初期値があるもの（Transform, Draggable, LocalTransform）だけ登録する
+    /// カードの種類や名前のように、エンティティごとに決めないといけない値を持つものにはデフォルト値を付けない
+    /// ParentとChildrenは片方だけ書き換えると親子関係が食い違うので登録せず、hierarchyのset_parent/remove_parentで変更する
+    pub fn with_game_components() -> Self {
+        let mut registry = Self::new();
+        registry.register_with_default(|| Transform::new(0.0, 0.0));
+        registry.register::<CardInfo>();
+        registry.register::<Renderable>();
+        registry.register_with_default(Draggable::new);
+        registry.register::<Clickable>();
+        registry.register::<StackContainer>();
+        registry.register::<Droppable>();
+        registry.register::<Name>();
+        registry.register_with_default(LocalTransform::default);
+        registry
+    }
+    
+    /// コンポーネント型を登録
+    pub fn register<T: Reflect>(&mut self) {
+        let registration = ComponentRegistration::of::<T>();
+        self.registrations.insert(registration.name, registration);
+    }
+    
+    /// デフォルト値の生成関数と一緒にコンポーネント型を登録
+    pub fn register_with_default<T: Reflect>(&mut self, make_default: fn() -> T) {
+        let registration = ComponentRegistration::of::<T>().with_default(make_default);
+        self.registrations.insert(registration.name, registration);
+    }
+    
+    /// 登録されているコンポーネント名の一覧（名前順）
+    pub fn names(&self) -> Vec<&'static str> {
+        self.registrations.keys().copied().collect()
+    }
+    
+    /// 名前から登録情報を取得
+    pub fn get_registration(&self, name: &str) -> Option<&ComponentRegistration> {
+        self.registrations.get(name)
+    }
+    
+    /// 名前から登録情報を取得（見つからなければエラー）
+    fn registration(&self, name: &str) -> GameResult<&ComponentRegistration> {
+        self.registrations
+            .get(name)
+            .ok_or_else(|| GameError::Reflection(format!("コンポーネント {} は登録されていません", name)))
+    }
+    
+    /// エンティティが持っている登録済みコンポーネントの名前一覧
+    pub fn list_components(&self, world: &World, entity_id: EntityId) -> Vec<&'static str> {
+        self.registrations
+            .values()
+            .filter(|registration| (registration.has)(world, entity_id))
+            .map(|registration| registration.name)
+            .collect()
+    }
+    
+    /// エンティティの登録済みコンポーネントを全て、名前をキーにしたJSONで取得
+    pub fn entity_to_json(&self, world: &World, entity_id: EntityId) -> Value {
+        let components = self.registrations
+            .values()
+            .filter_map(|registration| {
+                (registration.get)(world, entity_id).map(|value| (registration.name.to_string(), value))
+            })
+            .collect();
+        Value::Object(components)
+    }
+    
+    /// コンポーネントをJSONで取得
+    pub fn get(&self, world: &World, entity_id: EntityId, name: &str) -> GameResult<Option<Value>> {
+        Ok((self.registration(name)?.get)(world, entity_id))
+    }
+    
+    /// コンポーネントをJSONで設定（持っていなければ追加）
+    pub fn set(&self, world: &mut World, entity_id: EntityId, name: &str, value: Value) -> GameResult<()> {
+        (self.registration(name)?.set)(world, entity_id, value)
+    }
+    
+    /// コンポーネントのフィールドを1つだけJSONで設定
+    pub fn set_field(
+        &self,
+        world: &mut World,
+        entity_id: EntityId,
+        name: &str,
+        field: &str,
+        value: Value,
+    ) -> GameResult<()> {
+        (self.registration(name)?.set_field)(world, entity_id, field, value)
+    }
+    
+    /// デフォルト値のコンポーネントを追加
+    pub fn insert_default(&self, world: &mut World, entity_id: EntityId, name: &str) -> GameResult<()> {
+        let registration = self.registration(name)?;
+        match &registration.insert_default {
+            Some(insert_default) => insert_default(world, entity_id),
+            None => Err(GameError::Reflection(format!("コンポーネント {} にはデフォルト値がありません", name))),
+        }
+    }
+    
+    /// コンポーネントを削除（持っていた場合はtrue）
+    pub fn remove(&self, world: &mut World, entity_id: EntityId, name: &str) -> GameResult<bool> {
+        Ok((self.registration(name)?.remove)(world, entity_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    
+    #[test]
+    fn game_components_are_registered() {
+        let registry = TypeRegistry::with_game_components();
+        let names = registry.names();
+        for name in ["Transform", "CardInfo", "Renderable", "Draggable", "Clickable", "StackContainer", "Droppable", "Name", "LocalTransform"] {
+            assert!(names.contains(&name), "{} が登録されていません", name);
+        }
+        assert!(!names.contains(&"Parent"));
+        assert!(!names.contains(&"Children"));
+    }
+    
+    #[test]
+    fn defaults_are_inserted_only_where_registered() {
+        let registry = TypeRegistry::with_game_components();
+        let mut world = World::new();
+        let entity_id = world.create_entity().unwrap();
+        
+        registry.insert_default(&mut world, entity_id, "LocalTransform").unwrap();
+        assert!(world.has_component::<LocalTransform>(entity_id));
+        assert!(registry.insert_default(&mut world, entity_id, "Name").is_err());
+    }
+    
+    #[test]
+    fn setting_name_updates_the_name_index() {
+        let registry = TypeRegistry::with_game_components();
+        let mut world = World::new();
+        let entity_id = world.create_entity().unwrap();
+        world.add_component(entity_id, Name::new("stock")).unwrap();
+        
+        registry.set(&mut world, entity_id, "Name", json!("waste")).unwrap();
+        assert_eq!(world.find_by_name("waste"), Some(entity_id));
+        assert_eq!(world.find_by_name("stock"), None);
+        assert_eq!(registry.get(&world, entity_id, "Name").unwrap(), Some(json!("waste")));
+    }
+    
+    #[test]
+    fn local_transform_fields_can_be_set() {
+        let registry = TypeRegistry::with_game_components();
+        let mut world = World::new();
+        let entity_id = world.create_entity().unwrap();
+        world.add_component(entity_id, LocalTransform::new(0.0, 10.0)).unwrap();
+        
+        registry.set_field(&mut world, entity_id, "LocalTransform", "z_index", json!(3)).unwrap();
+        assert_eq!(world.get_component::<LocalTransform>(entity_id).map(|local| local.z_index), Some(3));
+    }
+}
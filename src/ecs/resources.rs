@@ -1,13 +1,158 @@
 use std::collections::HashMap;
 use std::any::{Any, TypeId};
-use wasm_bindgen::prelude::*;
-use crate::utils::Vec2;
+use std::cell::{Cell, UnsafeCell};
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+use std::rc::Rc;
+use serde::Serialize;
+use crate::utils::{Vec2, short_type_name};
+
+// 書き込みで借用されていることを表す借用状態
+const BORROWED_MUT: isize = -1;
+
+/// リソースの格納場所
+/// 借用状態を実行時に記録し、Res/ResMutのガードを通して共有する
+struct ResourceCell {
+    type_name: &'static str,
+    
+    // 借用状態（0: 未借用、正の値: 読み取り中のガードの数、-1: 書き込み中）
+    borrow: Cell<isize>,
+    
+    value: UnsafeCell<Box<dyn Any>>,
+}
+
+impl ResourceCell {
+    /// 新しい格納場所を作成
+    fn new<T: 'static>(resource: T) -> Self {
+        Self {
+            type_name: short_type_name(std::any::type_name::<T>()),
+            borrow: Cell::new(0),
+            value: UnsafeCell::new(Box::new(resource)),
+        }
+    }
+}
+
+/// リソースの借用エラー
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BorrowError {
+    NotFound(&'static str),                // リソースが存在しない
+    AlreadyBorrowed(&'static str),         // 読み取りで借用中のため書き込みで借りられない
+    AlreadyMutablyBorrowed(&'static str),  // 書き込みで借用中のため借りられない
+}
+
+impl fmt::Display for BorrowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BorrowError::NotFound(name) => write!(f, "リソース {} が見つかりません", name),
+            BorrowError::AlreadyBorrowed(name) => {
+                write!(f, "リソース {} は読み取りで借用中のため、書き込みで借用できません", name)
+            },
+            BorrowError::AlreadyMutablyBorrowed(name) => {
+                write!(f, "リソース {} は既に書き込みで借用されています", name)
+            },
+        }
+    }
+}
+
+/// リソースの読み取り用ガード
+/// 生きている間はリソースを読み取りで借用したものとして記録する
+/// 関数システムの引数としても使える
+pub struct Res<T: 'static> {
+    cell: Rc<ResourceCell>,
+    _marker: PhantomData<T>,
+}
+
+impl<T: 'static> Res<T> {
+    /// 読み取りで借用する
+    fn new(cell: &Rc<ResourceCell>) -> Result<Self, BorrowError> {
+        let borrow = cell.borrow.get();
+        if borrow == BORROWED_MUT {
+            return Err(BorrowError::AlreadyMutablyBorrowed(cell.type_name));
+        }
+        cell.borrow.set(borrow + 1);
+        
+        Ok(Self {
+            cell: Rc::clone(cell),
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<T: 'static> Deref for Res<T> {
+    type Target = T;
+    
+    fn deref(&self) -> &T {
+        // SAFETY: このガードが生きている間は借用状態が正の値なので、ResMutは作られない
+        // また、ガードがRcを共有しているため、&mut ResourceManager経由の可変アクセスもできない
+        let value = unsafe { &*self.cell.value.get() };
+        value.downcast_ref::<T>().unwrap()
+    }
+}
+
+impl<T: 'static> Drop for Res<T> {
+    fn drop(&mut self) {
+        self.cell.borrow.set(self.cell.borrow.get() - 1);
+    }
+}
+
+/// リソースの書き込み用ガード
+/// 生きている間はリソースを書き込みで借用したものとして記録する
+/// 関数システムの引数としても使える
+pub struct ResMut<T: 'static> {
+    cell: Rc<ResourceCell>,
+    _marker: PhantomData<T>,
+}
+
+impl<T: 'static> ResMut<T> {
+    /// 書き込みで借用する
+    fn new(cell: &Rc<ResourceCell>) -> Result<Self, BorrowError> {
+        match cell.borrow.get() {
+            0 => {},
+            BORROWED_MUT => return Err(BorrowError::AlreadyMutablyBorrowed(cell.type_name)),
+            _ => return Err(BorrowError::AlreadyBorrowed(cell.type_name)),
+        }
+        cell.borrow.set(BORROWED_MUT);
+        
+        Ok(Self {
+            cell: Rc::clone(cell),
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<T: 'static> Deref for ResMut<T> {
+    type Target = T;
+    
+    fn deref(&self) -> &T {
+        // SAFETY: このガードが生きている間は借用状態が-1なので、他のガードは作られない
+        let value = unsafe { &*self.cell.value.get() };
+        value.downcast_ref::<T>().unwrap()
+    }
+}
+
+impl<T: 'static> DerefMut for ResMut<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        // SAFETY: このガードが生きている間は借用状態が-1なので、他のガードは作られない
+        // また、ガードがRcを共有しているため、&mut ResourceManager経由の可変アクセスもできない
+        let value = unsafe { &mut *self.cell.value.get() };
+        value.downcast_mut::<T>().unwrap()
+    }
+}
+
+impl<T: 'static> Drop for ResMut<T> {
+    fn drop(&mut self) {
+        self.cell.borrow.set(0);
+    }
+}
 
 /// リソースマネージャー
 /// グローバルな状態やシステム間で共有される情報を管理する
+/// `get`/`borrow`/`borrow_mut`はガードを返すので、複数のリソースを同時に借りられる
+/// 同じリソースを書き込みで二重に借りようとした場合は、どのリソースかが分かるメッセージでpanicする
 pub struct ResourceManager {
-    // TypeIdからAny型へのマップ
-    resources: HashMap<TypeId, Box<dyn Any>>,
+    // TypeIdからリソースの格納場所へのマップ
+    resources: HashMap<TypeId, Rc<ResourceCell>>,
 }
 
 impl ResourceManager {
@@ -19,33 +164,80 @@ impl ResourceManager {
     }
     
     /// リソースを追加
+    /// 既に同じ型のリソースがある場合は置き換える（借用中のガードは古いリソースを指したまま）
     pub fn add<T: 'static>(&mut self, resource: T) {
         let type_id = TypeId::of::<T>();
-        self.resources.insert(type_id, Box::new(resource));
+        self.resources.insert(type_id, Rc::new(ResourceCell::new(resource)));
     }
     
-    /// リソースを取得
-    pub fn get<T: 'static>(&self) -> Option<&T> {
-        let type_id = TypeId::of::<T>();
-        self.resources
-            .get(&type_id)
-            .and_then(|boxed| boxed.downcast_ref::<T>())
+    /// リソースを読み取りで取得
+    /// 存在しない場合はNone、書き込みで借用中の場合はpanicする
+    pub fn get<T: 'static>(&self) -> Option<Res<T>> {
+        match self.try_borrow::<T>() {
+            Ok(resource) => Some(resource),
+            Err(BorrowError::NotFound(_)) => None,
+            Err(e) => panic!("{}", e),
+        }
     }
     
     /// リソースを可変で取得
+    /// 存在しない場合はNone、ガードで借用中の場合はpanicする
     pub fn get_mut<T: 'static>(&mut self) -> Option<&mut T> {
         let type_id = TypeId::of::<T>();
+        let cell = self.resources.get_mut(&type_id)?;
+        let type_name = cell.type_name;
+        
+        // ガードが残っていればRcが共有されているので、可変で取得できない
+        let cell = match Rc::get_mut(cell) {
+            Some(cell) => cell,
+            None => panic!("リソース {} はガードで借用中のため、可変で取得できません", type_name),
+        };
+        cell.value.get_mut().downcast_mut::<T>()
+    }
+    
+    /// リソースを読み取りで借用する
+    /// 存在しない場合や書き込みで借用中の場合はpanicする
+    pub fn borrow<T: 'static>(&self) -> Res<T> {
+        self.try_borrow::<T>().unwrap_or_else(|e| panic!("{}", e))
+    }
+    
+    /// リソースを書き込みで借用する
+    /// 存在しない場合や既に借用中の場合はpanicする
+    pub fn borrow_mut<T: 'static>(&self) -> ResMut<T> {
+        self.try_borrow_mut::<T>().unwrap_or_else(|e| panic!("{}", e))
+    }
+    
+    /// リソースを読み取りで借用する（失敗した場合はエラー）
+    pub fn try_borrow<T: 'static>(&self) -> Result<Res<T>, BorrowError> {
+        Res::new(self.cell::<T>()?)
+    }
+    
+    /// リソースを書き込みで借用する（失敗した場合はエラー）
+    pub fn try_borrow_mut<T: 'static>(&self) -> Result<ResMut<T>, BorrowError> {
+        ResMut::new(self.cell::<T>()?)
+    }
+    
+    /// リソースの格納場所を取得
+    fn cell<T: 'static>(&self) -> Result<&Rc<ResourceCell>, BorrowError> {
         self.resources
-            .get_mut(&type_id)
-            .and_then(|boxed| boxed.downcast_mut::<T>())
+            .get(&TypeId::of::<T>())
+            .ok_or_else(|| BorrowError::NotFound(short_type_name(std::any::type_name::<T>())))
     }
     
     /// リソースを削除
+    /// ガードで借用中の場合はpanicする
     pub fn remove<T: 'static>(&mut self) -> Option<T> {
         let type_id = TypeId::of::<T>();
-        self.resources
-            .remove(&type_id)
-            .and_then(|boxed| boxed.downcast::<T>().ok())
+        let cell = self.resources.remove(&type_id)?;
+        
+        let cell = match Rc::try_unwrap(cell) {
+            Ok(cell) => cell,
+            Err(cell) => panic!("リソース {} はガードで借用中のため、削除できません", cell.type_name),
+        };
+        cell.value
+            .into_inner()
+            .downcast::<T>()
+            .ok()
             .map(|boxed| *boxed)
     }
     
@@ -60,17 +252,11 @@ impl ResourceManager {
     where
         F: FnOnce() -> T,
     {
-        let type_id = TypeId::of::<T>();
-        
-        if !self.resources.contains_key(&type_id) {
-            let resource = f();
-            self.resources.insert(type_id, Box::new(resource));
+        if !self.has::<T>() {
+            self.add(f());
         }
         
-        self.resources
-            .get_mut(&type_id)
-            .and_then(|boxed| boxed.downcast_mut::<T>())
-            .unwrap()
+        self.get_mut::<T>().unwrap()
     }
 }
 
@@ -107,6 +293,15 @@ impl InputState {
         self.mouse_position = Vec2::new(x, y);
     }
     
+    /// 1回のクリック（押して離す）を入力する
+    /// ページのclickイベントのように、押下と解放を別々に受け取れないときに使う
+    /// ボタンは押したままにしないので、ドラッグは始まらずInputSystemがクリックとして処理する
+    pub fn click(&mut self, x: f64, y: f64) {
+        self.update_mouse_position(x, y);
+        self.mouse_down_position = Vec2::new(x, y);
+        self.is_mouse_down = true;
+    }
+    
     /// マウスボタンの状態を更新
     pub fn update_mouse_button(&mut self, button: usize, pressed: bool) {
         if button < self.mouse_buttons.len() {
@@ -206,8 +401,55 @@ impl TimeInfo {
     }
 }
 
+/// 固定タイムステップを管理するリソース
+/// ロジックをフレームレートに依存せず一定間隔で更新するための蓄積器
+/// 描画は最後のステップの状態をそのまま描き、ステップ間の補間は行わない
+/// （カードは入力に合わせて位置が決まるので、前の状態を保持して補間する必要がない）
+pub struct FixedTime {
+    pub step: f32,              // 1ステップの時間（秒）
+    pub max_steps_per_frame: u32, // 1フレームで実行する最大ステップ数
+    pub accumulator: f32,       // 未消化の経過時間（秒）
+    pub step_count: u64,        // 実行したステップの総数
+}
+
+impl FixedTime {
+    /// 新しい固定タイムステップを作成
+    pub fn new(step: f32, max_steps_per_frame: u32) -> Self {
+        Self {
+            step,
+            max_steps_per_frame,
+            accumulator: 0.0,
+            step_count: 0,
+        }
+    }
+    
+    /// フレームの経過時間を蓄積する
+    pub fn accumulate(&mut self, delta_time: f32) {
+        self.accumulator += delta_time.max(0.0);
+    }
+    
+    /// 1ステップ分の時間が溜まっていれば消費してtrueを返す
+    pub fn expend(&mut self) -> bool {
+        if self.accumulator >= self.step {
+            self.accumulator -= self.step;
+            self.step_count += 1;
+            true
+        } else {
+            false
+        }
+    }
+    
+    /// 1フレームのステップ数が上限に達したとき、残りの時間を切り捨てる
+    /// 処理落ちしたときに追いつこうとして更に遅くなるのを防ぐ
+    pub fn discard_overflow(&mut self) {
+        if self.accumulator >= self.step {
+            self.accumulator %= self.step;
+        }
+    }
+}
+
 /// ゲームの状態を管理するリソース
-#[derive(Clone, Copy, PartialEq, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize)]
 pub enum GameState {
     /// タイトル画面
     Title,
@@ -277,4 +519,88 @@ impl NetworkState {
     pub fn clear_error(&mut self) {
         self.connection_error = None;
     }
-} 
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    const STEP: f32 = 1.0 / 60.0;
+    
+    fn expend_all(fixed_time: &mut FixedTime) -> u32 {
+        let mut steps = 0;
+        while fixed_time.expend() {
+            steps += 1;
+        }
+        steps
+    }
+    
+    #[test]
+    fn fixed_time_runs_one_step_per_elapsed_step() {
+        let mut fixed_time = FixedTime::new(STEP, 5);
+        
+        // 1ステップに満たない時間は次のフレームに持ち越される
+        fixed_time.accumulate(STEP * 0.5);
+        assert_eq!(expend_all(&mut fixed_time), 0);
+        fixed_time.accumulate(STEP * 2.0);
+        assert_eq!(expend_all(&mut fixed_time), 2);
+        assert_eq!(fixed_time.step_count, 2);
+        assert!((0.0..STEP).contains(&fixed_time.accumulator));
+        
+        // 負の経過時間は無視される
+        fixed_time.accumulate(-1.0);
+        assert!(fixed_time.accumulator >= 0.0);
+    }
+    
+    #[test]
+    fn discard_overflow_keeps_less_than_one_step() {
+        let mut fixed_time = FixedTime::new(STEP, 5);
+        fixed_time.accumulate(STEP * 10.5);
+        fixed_time.discard_overflow();
+        assert!((0.0..STEP).contains(&fixed_time.accumulator));
+        assert_eq!(expend_all(&mut fixed_time), 0);
+    }
+    
+    struct Score(u32);
+    
+    fn resources_with_score() -> ResourceManager {
+        let mut resources = ResourceManager::new();
+        resources.add(Score(0));
+        resources
+    }
+    
+    #[test]
+    #[should_panic(expected = "Score")]
+    fn two_res_mut_of_the_same_resource_panic() {
+        let resources = resources_with_score();
+        let _first = resources.borrow_mut::<Score>();
+        let _second = resources.borrow_mut::<Score>();
+    }
+    
+    #[test]
+    #[should_panic(expected = "Score")]
+    fn res_mut_while_a_res_is_held_panics() {
+        let resources = resources_with_score();
+        let _reader = resources.borrow::<Score>();
+        let _writer = resources.borrow_mut::<Score>();
+    }
+    
+    #[test]
+    fn dropping_a_guard_releases_the_borrow() {
+        let mut resources = resources_with_score();
+        
+        // 読み取りは同時に複数借りられ、全て返すと書き込みで借りられる
+        let first = resources.borrow::<Score>();
+        let second = resources.borrow::<Score>();
+        assert_eq!(resources.try_borrow_mut::<Score>().err(), Some(BorrowError::AlreadyBorrowed("Score")));
+        drop(first);
+        drop(second);
+        
+        resources.borrow_mut::<Score>().0 += 1;
+        assert_eq!(resources.borrow::<Score>().0, 1);
+        
+        // ガードが残っていなければ&mutでも取得できる
+        resources.get_mut::<Score>().unwrap().0 += 1;
+        assert_eq!(resources.get::<Score>().map(|score| score.0), Some(2));
+    }
+}
@@ -0,0 +1,232 @@
+use std::collections::HashMap;
+use crate::error::{GameError, GameResult};
+use serde::{Serialize, Deserialize};
+use crate::ecs::entity::EntityId;
+use crate::ecs::world::World;
+use crate::ecs::component::{
+    Component, Transform, CardInfo, StackContainer, Renderable, Draggable, Droppable, Clickable, Name,
+};
+use crate::ecs::hierarchy::{Parent, Children, LocalTransform};
+use log::{debug, warn};
+
+/// スナップショットの形式のバージョン
+/// 形式を変更したら上げて、古いデータを読み込めないようにする
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// 1つのエンティティのスナップショット
+/// 保存対象のコンポーネントを持っていればSomeになる
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct EntitySnapshot {
+    pub id: EntityId,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<Name>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transform: Option<Transform>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub card_info: Option<CardInfo>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stack_container: Option<StackContainer>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub renderable: Option<Renderable>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub draggable: Option<Draggable>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub droppable: Option<Droppable>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clickable: Option<Clickable>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<Parent>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub children: Option<Children>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub local_transform: Option<LocalTransform>,
+}
+
+/// ワールド全体のスナップショット
+/// セーブデータ、ネットワークの全体同期、バグ報告の添付に使う
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct WorldSnapshot {
+    pub version: u32,
+    pub entities: Vec<EntitySnapshot>,
+}
+
+impl WorldSnapshot {
+    /// JSON文字列に変換
+    pub fn to_json(&self) -> GameResult<String> {
+        serde_json::to_string(self)
+            .map_err(|e| GameError::Serialization(format!("スナップショットのシリアライズに失敗しました: {}", e)))
+    }
+    
+    /// JSON文字列から読み込む
+    pub fn from_json(json: &str) -> GameResult<Self> {
+        let snapshot: Self = serde_json::from_str(json)
+            .map_err(|e| GameError::Serialization(format!("スナップショットのデシリアライズに失敗しました: {}", e)))?;
+        
+        if snapshot.version != SNAPSHOT_VERSION {
+            return Err(GameError::Serialization(format!(
+                "スナップショットのバージョンが違います（期待値: {}, 実際: {}）",
+                SNAPSHOT_VERSION, snapshot.version
+            )));
+        }
+        
+        Ok(snapshot)
+    }
+}
+
+/// コンポーネントを持っていればクローンして返す
+fn clone_component<T: Component + Clone>(world: &World, entity_id: EntityId) -> Option<T> {
+    world.get_component::<T>(entity_id).cloned()
+}
+
+/// スナップショット内のIDを新しいIDに付け替える
+/// スナップショットに無いエンティティを参照していたら警告してNoneを返す
+fn remap_id(id_map: &HashMap<EntityId, EntityId>, entity_id: EntityId) -> Option<EntityId> {
+    let mapped = id_map.get(&entity_id).copied();
+    if mapped.is_none() {
+        warn!("⚠️ スナップショットに存在しないエンティティID: {} を参照しています", entity_id);
+    }
+    mapped
+}
+
+/// コンポーネントがあればエンティティに追加する
+fn restore_component<T: Component>(world: &mut World, entity_id: EntityId, component: Option<T>) -> GameResult<()> {
+    match component {
+        Some(component) => world.add_component(entity_id, component),
+        None => Ok(()),
+    }
+}
+
+impl World {
+    /// ワールドの状態をスナップショットとして取得
+    /// エンティティはID順に並べるので、同じ状態からは同じ出力になる
+    pub fn snapshot(&self) -> WorldSnapshot {
+        let entities = self
+            .get_all_entities()
+            .into_iter()
+            .map(|id| EntitySnapshot {
+                id,
+                name: clone_component(self, id),
+                transform: clone_component(self, id),
+                card_info: clone_component(self, id),
+                stack_container: clone_component(self, id),
+                renderable: clone_component(self, id),
+                draggable: clone_component(self, id),
+                droppable: clone_component(self, id),
+                clickable: clone_component(self, id),
+                parent: clone_component(self, id),
+                children: clone_component(self, id),
+                local_transform: clone_component(self, id),
+            })
+            .collect();
+        
+        WorldSnapshot {
+            version: SNAPSHOT_VERSION,
+            entities,
+        }
+    }
+    
+    /// スナップショットからワールドを再構築する
+    /// 既存のエンティティは全て削除され、新しいIDで作り直される
+    /// 戻り値はスナップショット内のIDから新しいIDへの対応表
+    pub fn restore(&mut self, snapshot: &WorldSnapshot) -> GameResult<HashMap<EntityId, EntityId>> {
+        // 1. 既存のエンティティを全て削除
+        self.clear();
+        
+        // 2. 全てのエンティティを先に作成して、IDの対応表を作る
+        let mut id_map = HashMap::new();
+        for entity in &snapshot.entities {
+            let new_id = self.create_entity()?;
+            if id_map.insert(entity.id, new_id).is_some() {
+                return Err(GameError::InvalidOperation(format!(
+                    "スナップショットにエンティティID: {} が重複しています",
+                    entity.id
+                )));
+            }
+        }
+        
+        // 3. コンポーネントを復元（エンティティへの参照は新しいIDに付け替える）
+        for entity in &snapshot.entities {
+            let new_id = id_map[&entity.id];
+            
+            let stack_container = entity.stack_container.clone().map(|mut stack| {
+                stack.cards = stack.cards.iter().filter_map(|&card_id| remap_id(&id_map, card_id)).collect();
+                stack
+            });
+            let parent = entity.parent.and_then(|parent| remap_id(&id_map, parent.0)).map(Parent);
+            let children = entity.children.as_ref().map(|children| {
+                Children(children.0.iter().filter_map(|&child| remap_id(&id_map, child)).collect())
+            });
+            
+            restore_component(self, new_id, entity.name.clone())?;
+            restore_component(self, new_id, entity.transform.clone())?;
+            restore_component(self, new_id, entity.card_info.clone())?;
+            restore_component(self, new_id, stack_container)?;
+            restore_component(self, new_id, entity.renderable.clone())?;
+            restore_component(self, new_id, entity.draggable.clone())?;
+            restore_component(self, new_id, entity.droppable.clone())?;
+            restore_component(self, new_id, entity.clickable.clone())?;
+            restore_component(self, new_id, parent)?;
+            restore_component(self, new_id, children)?;
+            restore_component(self, new_id, entity.local_transform)?;
+        }
+        
+        debug!("📥 スナップショットから{}個のエンティティを復元しました", id_map.len());
+        Ok(id_map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ecs::component::StackType;
+    use crate::ecs::hierarchy::set_parent;
+    
+    // スタックにカードを2枚載せ、2枚目を1枚目の子にしたワールド
+    fn world_with_hierarchy() -> (World, EntityId, EntityId, EntityId) {
+        let mut world = World::new();
+        let stack = world.create_entity().unwrap();
+        let bottom = world.create_entity().unwrap();
+        let top = world.create_entity().unwrap();
+        world.add_component(bottom, CardInfo::new(0, 0)).unwrap();
+        world.add_component(top, CardInfo::new(1, 1)).unwrap();
+        let mut container = StackContainer::new(StackType::Tableau { column: 0 });
+        container.cards = vec![bottom, top];
+        world.add_component(stack, container).unwrap();
+        set_parent(&mut world, top, bottom, LocalTransform::new(0.0, 30.0).with_z_index(1)).unwrap();
+        (world, stack, bottom, top)
+    }
+    
+    #[test]
+    fn hierarchy_is_restored_with_new_ids() {
+        let (world, _stack, bottom, top) = world_with_hierarchy();
+        let snapshot = WorldSnapshot::from_json(&world.snapshot().to_json().unwrap()).unwrap();
+        
+        // 作り直すとIDがずれるように、先にエンティティを作っておく
+        let mut restored = World::new();
+        restored.create_entity().unwrap();
+        let id_map = restored.restore(&snapshot).unwrap();
+        let (new_bottom, new_top) = (id_map[&bottom], id_map[&top]);
+        assert_ne!(new_top, top);
+        
+        assert_eq!(restored.get_component::<Parent>(new_top), Some(&Parent(new_bottom)));
+        assert_eq!(restored.get_component::<Children>(new_bottom).map(|children| children.0.clone()), Some(vec![new_top]));
+        let local = restored.get_component::<LocalTransform>(new_top).unwrap();
+        assert_eq!((local.position.y, local.z_index), (30.0, 1));
+        assert!(restored.get_component::<Parent>(new_bottom).is_none());
+    }
+    
+    #[test]
+    fn references_to_missing_entities_are_dropped() {
+        let (world, _stack, bottom, top) = world_with_hierarchy();
+        let mut snapshot = world.snapshot();
+        snapshot.entities.retain(|entity| entity.id != bottom);
+        
+        let mut restored = World::new();
+        let id_map = restored.restore(&snapshot).unwrap();
+        let new_top = id_map[&top];
+        
+        assert!(restored.get_component::<Parent>(new_top).is_none());
+        let stack_id = restored.get_entities_with_component::<StackContainer>()[0];
+        assert_eq!(restored.get_component::<StackContainer>(stack_id).unwrap().cards, vec![new_top]);
+    }
+}
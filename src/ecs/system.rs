@@ -1,7 +1,15 @@
-use wasm_bindgen::prelude::*;
+use crate::error::{GameError, GameResult};
 use crate::ecs::world::World;
-use crate::ecs::resources::ResourceManager;
+use crate::ecs::resources::{ResourceManager, GameState, FixedTime, TimeInfo};
+use crate::ecs::condition::RunCondition;
+use crate::ecs::profiler::SystemProfiler;
+use crate::ecs::error_log::ErrorLog;
+use crate::ecs::function_system::{IntoSystem, SystemAccess, AccessItem};
+use crate::utils::now_ms;
 use std::cmp::Ordering;
+use std::collections::HashMap;
+use serde::Serialize;
+use log::{debug, info, warn, error};
 
 /// システムフェーズ
 /// システムの実行順序を決定するためのフェーズ
@@ -14,8 +22,12 @@ pub enum SystemPhase {
     Render,      // 描画
 }
 
+/// システムラベル
+/// before/after制約でシステムを参照するための名前
+pub type SystemLabel = &'static str;
+
 /// システム優先度
-/// 同じフェーズ内でのシステムの実行順序を決定する
+/// 同じフェーズ内で、before/after制約のないシステム同士の実行順序を決定する
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct SystemPriority(pub i32);
 
@@ -38,6 +50,19 @@ impl Ord for SystemPriority {
     }
 }
 
+/// エラーポリシー
+/// システムがエラーを返したときの扱い
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ErrorPolicy {
+    /// エラーを記録して、このフレームはそのシステムだけ飛ばす
+    #[default]
+    Skip,
+    /// エラーを記録し、指定した回数失敗したらシステムを無効にする
+    DisableAfter(u32),
+    /// エラーを記録して、フレームの残りのシステムを実行せずにエラーを返す
+    Halt,
+}
+
 /// システムトレイト
 /// 全てのシステムが実装する必要があるトレイト
 pub trait System {
@@ -52,22 +77,251 @@ pub trait System {
     }
     
     /// システムの優先度を返す
-    /// 同じフェーズ内での実行順序を決定する
+    /// 同じフェーズ内で、制約のないシステム同士の実行順序を決定する
     fn priority(&self) -> SystemPriority {
         SystemPriority(0)
     }
     
+    /// システムのラベルを返す
+    /// 他のシステムがbefore/afterで参照する名前（デフォルトはシステム名のみ）
+    fn labels(&self) -> Vec<SystemLabel> {
+        vec![self.name()]
+    }
+    
+    /// このシステムより後に実行されるべきラベルを返す
+    fn before(&self) -> Vec<SystemLabel> {
+        Vec::new()
+    }
+    
+    /// このシステムより先に実行されるべきラベルを返す
+    fn after(&self) -> Vec<SystemLabel> {
+        Vec::new()
+    }
+    
+    /// システムが宣言したデータアクセスを返す
+    /// 関数システムは引数から自動的に作成する（Noneは宣言なし）
+    fn access(&self) -> Option<&SystemAccess> {
+        None
+    }
+    
+    /// ゲームをやり直すときに呼ばれる
+    /// フレームをまたいで保持している状態（ドラッグ中のカードなど）を初期状態に戻す
+    fn reset(&mut self) {}
+    
     /// システムの実行メソッド
     /// world: エンティティとコンポーネントを含むゲームの世界
     /// resources: グローバルなリソース（例：入力状態、時間など）
     /// delta_time: 前回のフレームからの経過時間（秒）
-    fn run(&mut self, world: &mut World, resources: &mut ResourceManager, delta_time: f32) -> Result<(), JsValue>;
+    fn run(&mut self, world: &mut World, resources: &mut ResourceManager, delta_time: f32) -> GameResult<()>;
+}
+
+/// システムエントリー
+/// システム本体と、実行条件・変更検出のための実行記録をまとめて保持する
+struct SystemEntry {
+    system: Box<dyn System>,
+    
+    // 実行条件（全てを満たすときだけ実行する）
+    conditions: Vec<RunCondition>,
+    
+    // このシステムが前回実行されたときのティック（0は未実行）
+    last_run_tick: u32,
+    
+    // エラーを返したときの扱い
+    error_policy: ErrorPolicy,
+    
+    // エラーを返した回数
+    failures: u32,
+    
+    // 無効にされているかどうか（エラーポリシーか、デバッグツールから）
+    disabled: bool,
+    
+    // 追加時に指定された実行順序の制約（システム自身のbefore/afterに加える）
+    before: Vec<SystemLabel>,
+    after: Vec<SystemLabel>,
+}
+
+impl SystemEntry {
+    /// 新しいシステムエントリーを作成
+    fn new(system: Box<dyn System>) -> Self {
+        Self {
+            system,
+            conditions: Vec::new(),
+            last_run_tick: 0,
+            error_policy: ErrorPolicy::default(),
+            failures: 0,
+            disabled: false,
+            before: Vec::new(),
+            after: Vec::new(),
+        }
+    }
+    
+    /// このシステムより後に実行されるべきラベル（システム自身の制約と追加時の制約）
+    fn before_labels(&self) -> Vec<SystemLabel> {
+        let mut labels = self.system.before();
+        labels.extend(self.before.iter().copied());
+        labels
+    }
+    
+    /// このシステムより先に実行されるべきラベル（システム自身の制約と追加時の制約）
+    fn after_labels(&self) -> Vec<SystemLabel> {
+        let mut labels = self.system.after();
+        labels.extend(self.after.iter().copied());
+        labels
+    }
+    
+    /// 無効にされておらず、実行条件を全て満たしているかチェック
+    fn should_run(&self, world: &World, resources: &ResourceManager) -> bool {
+        !self.disabled && self.conditions.iter().all(|condition| condition(world, resources))
+    }
+    
+    /// システムを実行し、実行時のティックを記録する
+    fn run(&mut self, world: &mut World, resources: &mut ResourceManager, delta_time: f32) -> GameResult<()> {
+        // 前回の実行以降の変更を検出できるように基準ティックを設定
+        world.set_last_change_tick(self.last_run_tick);
+        
+        let start = now_ms();
+        let result = self.system.run(world, resources, delta_time);
+        
+        // プロファイラーがあれば実行時間を記録
+        if let Some(profiler) = resources.get_mut::<SystemProfiler>() {
+            profiler.record_system(self.system.name(), now_ms() - start);
+        }
+        
+        // 次のシステムの変更と区別できるようにティックを進める
+        self.last_run_tick = world.increment_change_tick();
+        
+        result
+    }
+    
+    /// システムを実行し、エラーはエラーポリシーに従って処理する
+    /// エラーはErrorLogリソースがあれば記録し、Haltのときだけ呼び出し元に返す
+    fn run_with_policy(&mut self, world: &mut World, resources: &mut ResourceManager, delta_time: f32) -> GameResult<()> {
+        let err = match self.run(world, resources, delta_time) {
+            Ok(()) => return Ok(()),
+            Err(err) => err,
+        };
+        
+        // 1. 失敗回数を数え、上限に達したら無効にする
+        self.failures += 1;
+        if let ErrorPolicy::DisableAfter(limit) = self.error_policy {
+            self.disabled = self.failures >= limit;
+        }
+        
+        // 2. エラーログに記録
+        let name = self.system.name();
+        let frame = resources.get::<TimeInfo>().map_or(0, |time| time.frame_count);
+        if let Some(error_log) = resources.get_mut::<ErrorLog>() {
+            error_log.record(name, &err, frame, self.disabled);
+        }
+        
+        if self.disabled {
+            warn!("🚫 システム {} は{}回失敗したため無効にしました: {}", name, self.failures, err);
+        } else {
+            error!("❌ システム {} でエラーが発生しました: {}", name, err);
+        }
+        
+        // 3. Haltならフレームを中断する
+        match self.error_policy {
+            ErrorPolicy::Halt => Err(err),
+            ErrorPolicy::Skip | ErrorPolicy::DisableAfter(_) => Ok(()),
+        }
+    }
+}
+
+/// システム設定
+/// `SystemManager::add_system`の戻り値で、追加したシステムに実行条件などを設定する
+pub struct SystemConfig<'a> {
+    entry: &'a mut SystemEntry,
+}
+
+impl<'a> SystemConfig<'a> {
+    /// 実行条件を追加する
+    /// 複数追加した場合は全ての条件を満たすときだけ実行される
+    pub fn run_if<F>(self, condition: F) -> Self
+    where
+        F: Fn(&World, &ResourceManager) -> bool + 'static,
+    {
+        self.entry.conditions.push(Box::new(condition));
+        self
+    }
+    
+    /// エラーを返したときの扱いを設定する（デフォルトはSkip）
+    pub fn on_error(self, policy: ErrorPolicy) -> Self {
+        self.entry.error_policy = policy;
+        self
+    }
+    
+    /// 指定したラベルのシステムより先に実行する
+    pub fn before(self, label: SystemLabel) -> Self {
+        self.entry.before.push(label);
+        self
+    }
+    
+    /// 指定したラベルのシステムより後に実行する
+    pub fn after(self, label: SystemLabel) -> Self {
+        self.entry.after.push(label);
+        self
+    }
+}
+
+/// システムの情報
+/// `SystemManager::list_systems`の戻り値で、デバッグ用コンソールに表示する
+#[derive(Debug, Clone, Serialize)]
+pub struct SystemInfo {
+    pub name: &'static str,
+    pub phase: String,
+    pub labels: Vec<SystemLabel>,
+    pub enabled: bool,
+    pub failures: u32,
+}
+
+/// システムを挿入する位置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SystemPlacement<'a> {
+    /// 指定したラベルのシステムより先
+    Before(&'a str),
+    /// 指定したラベルのシステムより後
+    After(&'a str),
+}
+
+/// システム同士のデータアクセスの競合
+#[derive(Debug, Clone)]
+pub struct AccessConflict {
+    pub first: &'static str,
+    pub second: &'static str,
+    
+    // 競合しているリソース・コンポーネント
+    pub items: Vec<AccessItem>,
+}
+
+impl AccessConflict {
+    /// 人が読める形式で返す（デバッグ用）
+    pub fn describe(&self) -> String {
+        let items: Vec<String> = self.items.iter().map(|item| item.describe()).collect();
+        format!("{} と {}: {}", self.first, self.second, items.join(", "))
+    }
 }
 
 /// システムマネージャー
 /// 複数のシステムを管理し、適切な順序で実行する
 pub struct SystemManager {
-    systems: Vec<Box<dyn System>>,
+    systems: Vec<SystemEntry>,
+    
+    // 実行順序（systemsのインデックス）
+    // システムが追加されたときだけ再構築する
+    schedule: Vec<usize>,
+    
+    // 実行順序の再構築が必要かどうか
+    schedule_dirty: bool,
+    
+    // ゲーム状態に入ったときに一度だけ実行するシステム
+    on_enter: Vec<(GameState, SystemEntry)>,
+    
+    // ゲーム状態から出るときに一度だけ実行するシステム
+    on_exit: Vec<(GameState, SystemEntry)>,
+    
+    // 前回の遷移チェック時のゲーム状態
+    current_state: Option<GameState>,
 }
 
 impl SystemManager {
@@ -75,29 +329,409 @@ impl SystemManager {
     pub fn new() -> Self {
         Self {
             systems: Vec::new(),
+            schedule: Vec::new(),
+            schedule_dirty: false,
+            on_enter: Vec::new(),
+            on_exit: Vec::new(),
+            current_state: None,
         }
     }
     
     /// システムを追加
-    pub fn add_system<S: System + 'static>(&mut self, system: S) {
-        self.systems.push(Box::new(system));
+    /// Systemを実装した構造体か、引数がRes/ResMut/Queryの関数を渡せる
+    /// 戻り値のSystemConfigで実行条件を設定できる
+    pub fn add_system<M, S: IntoSystem<M>>(&mut self, system: S) -> SystemConfig<'_> {
+        self.systems.push(SystemEntry::new(Box::new(system.into_system())));
+        self.schedule_dirty = true;
+        
+        let entry = self.systems.last_mut().unwrap();
+        SystemConfig { entry }
+    }
+    
+    /// ゲーム状態に入ったときに一度だけ実行するシステムを追加
+    pub fn add_on_enter<M, S: IntoSystem<M>>(&mut self, state: GameState, system: S) {
+        self.on_enter.push((state, SystemEntry::new(Box::new(system.into_system()))));
+    }
+    
+    /// ゲーム状態から出るときに一度だけ実行するシステムを追加
+    pub fn add_on_exit<M, S: IntoSystem<M>>(&mut self, state: GameState, system: S) {
+        self.on_exit.push((state, SystemEntry::new(Box::new(system.into_system()))));
+    }
+    
+    /// ゲーム状態の遷移を検出し、on_exit/on_enterのシステムを実行する
+    /// 遷移1回につき、それぞれのシステムは一度だけ実行される
+    pub fn apply_state_transitions(
+        &mut self,
+        world: &mut World,
+        resources: &mut ResourceManager,
+        delta_time: f32,
+    ) -> GameResult<()> {
+        let new_state = match resources.get::<GameState>() {
+            Some(state) => *state,
+            None => return Ok(()),
+        };
+        
+        if self.current_state == Some(new_state) {
+            return Ok(());
+        }
+        
+        let old_state = self.current_state.replace(new_state);
+        debug!("🔀 ゲーム状態の遷移を検出: {:?} -> {:?}", old_state, new_state);
+        
+        // 1. 前の状態から出るときのシステムを実行
+        if let Some(old_state) = old_state {
+            for (state, entry) in &mut self.on_exit {
+                if *state == old_state {
+                    entry.run_with_policy(world, resources, delta_time)?;
+                }
+            }
+        }
+        
+        // 2. 新しい状態に入るときのシステムを実行
+        for (state, entry) in &mut self.on_enter {
+            if *state == new_state {
+                entry.run_with_policy(world, resources, delta_time)?;
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// 実行順序を構築する
+    /// フェーズ順を守りつつ、before/after制約をトポロジカルソートで解決する
+    /// 制約に循環がある場合やフェーズ順と矛盾する場合はエラーを返す
+    pub fn build_schedule(&mut self) -> GameResult<()> {
+        let count = self.systems.len();
+        
+        // 引数同士でアクセスが競合しているシステムは実行できない
+        self.check_self_conflicts()?;
+        
+        // 1. ラベルからシステムのインデックスへのマップを作成
+        let mut label_map: HashMap<SystemLabel, Vec<usize>> = HashMap::new();
+        for (index, entry) in self.systems.iter().enumerate() {
+            for label in entry.system.labels() {
+                label_map.entry(label).or_default().push(index);
+            }
+        }
+        
+        // 2. before/after制約から依存関係のエッジ（先 -> 後）を作成
+        let mut edges: Vec<Vec<usize>> = vec![Vec::new(); count];
+        for (index, entry) in self.systems.iter().enumerate() {
+            for label in entry.after_labels() {
+                for &target in label_map.get(label).into_iter().flatten() {
+                    if target != index {
+                        edges[target].push(index);
+                    }
+                }
+            }
+            
+            for label in entry.before_labels() {
+                for &target in label_map.get(label).into_iter().flatten() {
+                    if target != index {
+                        edges[index].push(target);
+                    }
+                }
+            }
+        }
+        
+        // 対象のシステムがないラベルの制約は無視する（描画なしのビルドなどでは起こりうる）
+        for (name, label) in self.unknown_labels() {
+            warn!("⚠️ {} の制約のラベル '{}' を持つシステムがありません", name, label);
+        }
+        
+        // 3. 制約がフェーズ順と矛盾していないかチェック
+        for (from, targets) in edges.iter().enumerate() {
+            for &to in targets {
+                let from_system = &self.systems[from].system;
+                let to_system = &self.systems[to].system;
+                if from_system.phase() > to_system.phase() {
+                    return Err(GameError::Schedule(format!(
+                        "システムの実行順序の制約がフェーズ順と矛盾しています: {}（{:?}）は {}（{:?}）より先に実行できません",
+                        from_system.name(), from_system.phase(),
+                        to_system.name(), to_system.phase()
+                    )));
+                }
+            }
+        }
+        
+        // 4. トポロジカルソート（実行可能なものから、フェーズ・優先度・追加順が小さいものを選ぶ）
+        let mut in_degree = vec![0usize; count];
+        for targets in &edges {
+            for &to in targets {
+                in_degree[to] += 1;
+            }
+        }
+        
+        let mut ready: Vec<usize> = (0..count).filter(|&i| in_degree[i] == 0).collect();
+        let mut schedule = Vec::with_capacity(count);
+        
+        while !ready.is_empty() {
+            // 一番先に実行すべきシステムを選ぶ
+            let (position, _) = ready
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, &i)| {
+                    let system = &self.systems[i].system;
+                    (system.phase(), system.priority(), i)
+                })
+                .unwrap();
+            let index = ready.swap_remove(position);
+            schedule.push(index);
+            
+            for &to in &edges[index] {
+                in_degree[to] -= 1;
+                if in_degree[to] == 0 {
+                    ready.push(to);
+                }
+            }
+        }
+        
+        // 5. 全てのシステムを並べられなかった場合は循環がある
+        if schedule.len() < count {
+            let cycle = Self::find_cycle(&edges, &in_degree);
+            let names: Vec<&str> = cycle
+                .iter()
+                .map(|&i| self.systems[i].system.name())
+                .collect();
+            return Err(GameError::Schedule(format!(
+                "システムの実行順序に循環があります: {}",
+                names.join(" -> ")
+            )));
+        }
+        
+        self.schedule = schedule;
+        self.schedule_dirty = false;
+        debug!("📋 システムの実行順序を構築しました:\n{}", self.describe_schedule());
+        for conflict in self.access_conflicts() {
+            debug!("🔒 同じフェーズでデータアクセスが競合しています: {}", conflict.describe());
+        }
+        
+        Ok(())
+    }
+    
+    /// before/after制約に使われているが、どのシステムも持っていないラベルを返す
+    /// （制約を書いたシステムの名前, ラベル）の組で、追加順に並ぶ
+    pub fn unknown_labels(&self) -> Vec<(&'static str, SystemLabel)> {
+        let known: Vec<SystemLabel> = self.systems.iter()
+            .flat_map(|entry| entry.system.labels())
+            .collect();
+        
+        self.systems.iter()
+            .flat_map(|entry| {
+                let name = entry.system.name();
+                entry.after_labels().into_iter()
+                    .chain(entry.before_labels())
+                    .map(move |label| (name, label))
+            })
+            .filter(|(_, label)| !known.contains(label))
+            .collect()
+    }
+    
+    /// システムの引数同士のアクセス競合をチェックする
+    /// 例：同じリソースをResMutとResの両方で借りている関数システム
+    fn check_self_conflicts(&self) -> GameResult<()> {
+        let entries = self.systems.iter()
+            .chain(self.on_enter.iter().map(|(_, entry)| entry))
+            .chain(self.on_exit.iter().map(|(_, entry)| entry));
+        
+        for entry in entries {
+            if let Some(access) = entry.system.access() {
+                if !access.self_conflicts().is_empty() {
+                    return Err(GameError::Schedule(format!(
+                        "システム {} の引数のアクセスが競合しています: {}",
+                        entry.system.name(),
+                        access.self_conflicts().join(", ")
+                    )));
+                }
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// 同じフェーズのシステム同士のデータアクセスの競合を返す
+    /// アクセスを宣言しているシステム（関数システム）同士だけを比較する
+    pub fn access_conflicts(&self) -> Vec<AccessConflict> {
+        let mut conflicts = Vec::new();
+        
+        for (index, entry) in self.systems.iter().enumerate() {
+            let access = match entry.system.access() {
+                Some(access) => access,
+                None => continue,
+            };
+            
+            for other in &self.systems[index + 1..] {
+                if other.system.phase() != entry.system.phase() {
+                    continue;
+                }
+                if let Some(other_access) = other.system.access() {
+                    let items = access.conflicts_with(other_access);
+                    if !items.is_empty() {
+                        conflicts.push(AccessConflict {
+                            first: entry.system.name(),
+                            second: other.system.name(),
+                            items,
+                        });
+                    }
+                }
+            }
+        }
+        
+        conflicts
+    }
+    
+    /// 循環している経路を探す
+    /// ソートで残ったシステム（入次数が0にならなかったもの）は必ず残ったシステムを
+    /// 先行に持つため、先行を辿り続けるといずれ同じシステムに戻る
+    fn find_cycle(edges: &[Vec<usize>], in_degree: &[usize]) -> Vec<usize> {
+        let remaining = |i: usize| in_degree[i] > 0;
+        
+        let start = match (0..in_degree.len()).find(|&i| remaining(i)) {
+            Some(start) => start,
+            None => return Vec::new(),
+        };
+        
+        // 1. 先行（自分より先に実行されるべきシステム）を辿る
+        let mut path = vec![start];
+        let mut current = start;
+        loop {
+            let previous = (0..edges.len())
+                .find(|&from| remaining(from) && edges[from].contains(&current));
+            let previous = match previous {
+                Some(previous) => previous,
+                None => return path,
+            };
+            
+            // 2. 一度通ったシステムに戻ったら、その区間が循環
+            if let Some(position) = path.iter().position(|&i| i == previous) {
+                let mut cycle = path[position..].to_vec();
+                cycle.push(previous);
+                // 先行を辿ったので、実行順の向きに並べ替える
+                cycle.reverse();
+                return cycle;
+            }
+            
+            path.push(previous);
+            current = previous;
+        }
+    }
+    
+    /// 実行順序を人が読める形式で返す（デバッグ用）
+    pub fn describe_schedule(&self) -> String {
+        let order: Vec<usize> = if self.schedule_dirty {
+            (0..self.systems.len()).collect()
+        } else {
+            self.schedule.clone()
+        };
+        
+        let mut lines = Vec::with_capacity(order.len() + 1);
+        if self.schedule_dirty {
+            lines.push("（未構築：追加順で表示）".to_string());
+        }
+        
+        for (step, &index) in order.iter().enumerate() {
+            let entry = &self.systems[index];
+            let system = &entry.system;
+            let before = entry.before_labels();
+            let after = entry.after_labels();
+            lines.push(format!(
+                "{:>2}. [{:?}] {} (labels: {}; after: {}; before: {})",
+                step + 1,
+                system.phase(),
+                system.name(),
+                system.labels().join(", "),
+                if after.is_empty() { "-".to_string() } else { after.join(", ") },
+                if before.is_empty() { "-".to_string() } else { before.join(", ") },
+            ));
+        }
+        
+        lines.join("\n")
     }
     
-    /// 全てのシステムをソートして適切な順序で実行
-    pub fn run_systems(&mut self, world: &mut World, resources: &mut ResourceManager, delta_time: f32) -> Result<(), JsValue> {
-        // フェーズと優先度でシステムをソート
-        self.systems.sort_by(|a, b| {
-            let phase_cmp = a.phase().cmp(&b.phase());
-            if phase_cmp == Ordering::Equal {
-                a.priority().cmp(&b.priority())
-            } else {
-                phase_cmp
+    /// 全てのシステムを実行順序に従って実行
+    /// エラーを返したシステムはエラーポリシーに従って扱い、Haltのときだけ残りを実行せずにエラーを返す
+    pub fn run_systems(&mut self, world: &mut World, resources: &mut ResourceManager, delta_time: f32) -> GameResult<()> {
+        // システムが追加されていれば実行順序を再構築
+        if self.schedule_dirty {
+            self.build_schedule()?;
+        }
+        
+        // ゲーム状態の遷移があればon_exit/on_enterのシステムを先に実行
+        self.apply_state_transitions(world, resources, delta_time)?;
+        
+        // 実行条件を満たすシステムを実行
+        for &index in &self.schedule {
+            let entry = &mut self.systems[index];
+            if entry.should_run(world, resources) {
+                entry.run_with_policy(world, resources, delta_time)?;
             }
-        });
+        }
         
-        // 全てのシステムを実行
-        for system in &mut self.systems {
-            system.run(world, resources, delta_time)?;
+        Ok(())
+    }
+    
+    /// 1フレーム分のシステムを実行
+    /// Input〜PostUpdateは固定タイムステップで0回以上実行し、Renderは毎フレーム可変の経過時間で1回実行する
+    /// FixedTimeリソースがなければ、全てのシステムを可変の経過時間で1回実行する
+    pub fn run_frame(&mut self, world: &mut World, resources: &mut ResourceManager, delta_time: f32) -> GameResult<()> {
+        // フレーム間隔を記録
+        if let Some(profiler) = resources.get_mut::<SystemProfiler>() {
+            profiler.record_frame(delta_time);
+        }
+        
+        let step = match resources.get_mut::<FixedTime>() {
+            Some(fixed_time) => {
+                fixed_time.accumulate(delta_time);
+                fixed_time.step
+            },
+            None => {
+                self.run_systems(world, resources, delta_time)?;
+                world.update();
+                return Ok(());
+            }
+        };
+        
+        // システムが追加されていれば実行順序を再構築
+        if self.schedule_dirty {
+            self.build_schedule()?;
+        }
+        
+        // 1. 溜まった時間の分だけロジックを固定間隔で更新
+        let mut steps = 0;
+        loop {
+            let fixed_time = resources.get_mut::<FixedTime>().unwrap();
+            if steps >= fixed_time.max_steps_per_frame {
+                fixed_time.discard_overflow();
+                break;
+            }
+            if !fixed_time.expend() {
+                break;
+            }
+            
+            // 削除の記録はこのフレームの描画フェーズまで残すため、ここではエンティティの削除だけを反映する
+            self.run_fixed_step(world, resources, step)?;
+            world.apply_removals();
+            steps += 1;
+        }
+        
+        // 2. 描画フェーズは毎フレーム実行（ステップ間の補間はせず、最後のステップの状態を描く）
+        self.run_systems_for_phase(SystemPhase::Render, world, resources, delta_time)?;
+        
+        // 3. フレームの終わりに削除の記録を整理する
+        world.update();
+        Ok(())
+    }
+    
+    /// 固定タイムステップ1回分のシステム（Render以外）を実行
+    fn run_fixed_step(&mut self, world: &mut World, resources: &mut ResourceManager, step: f32) -> GameResult<()> {
+        // ゲーム状態の遷移があればon_exit/on_enterのシステムを先に実行
+        self.apply_state_transitions(world, resources, step)?;
+        
+        for &index in &self.schedule {
+            let entry = &mut self.systems[index];
+            if entry.system.phase() != SystemPhase::Render && entry.should_run(world, resources) {
+                entry.run_with_policy(world, resources, step)?;
+            }
         }
         
         Ok(())
@@ -110,29 +744,453 @@ impl SystemManager {
         world: &mut World,
         resources: &mut ResourceManager,
         delta_time: f32,
-    ) -> Result<(), JsValue> {
-        // フェーズと優先度でシステムをソート
-        self.systems.sort_by(|a, b| {
-            let phase_cmp = a.phase().cmp(&b.phase());
-            if phase_cmp == Ordering::Equal {
-                a.priority().cmp(&b.priority())
-            } else {
-                phase_cmp
-            }
-        });
+    ) -> GameResult<()> {
+        // システムが追加されていれば実行順序を再構築
+        if self.schedule_dirty {
+            self.build_schedule()?;
+        }
         
         // 指定されたフェーズのシステムのみを実行
-        for system in &mut self.systems {
-            if system.phase() == phase {
-                system.run(world, resources, delta_time)?;
+        for &index in &self.schedule {
+            let entry = &mut self.systems[index];
+            if entry.system.phase() == phase && entry.should_run(world, resources) {
+                entry.run_with_policy(world, resources, delta_time)?;
             }
         }
         
         Ok(())
     }
     
+    /// システムの一覧を実行順に返す（実行順序が未構築なら追加順）
+    pub fn list_systems(&self) -> Vec<SystemInfo> {
+        let order: Vec<usize> = if self.schedule_dirty {
+            (0..self.systems.len()).collect()
+        } else {
+            self.schedule.clone()
+        };
+        
+        order
+            .into_iter()
+            .map(|index| {
+                let entry = &self.systems[index];
+                SystemInfo {
+                    name: entry.system.name(),
+                    phase: format!("{:?}", entry.system.phase()),
+                    labels: entry.system.labels(),
+                    enabled: !entry.disabled,
+                    failures: entry.failures,
+                }
+            })
+            .collect()
+    }
+    
+    /// 名前からシステムのインデックスを探す（同じ名前が複数あれば最初に追加されたもの）
+    fn index_of(&self, name: &str) -> GameResult<usize> {
+        self.systems
+            .iter()
+            .position(|entry| entry.system.name() == name)
+            .ok_or_else(|| GameError::SystemNotFound(name.to_string()))
+    }
+    
+    /// 名前からラベルを探す（文字列で指定されたラベルを&'static strに変換する）
+    fn find_label(&self, label: &str) -> GameResult<SystemLabel> {
+        self.systems
+            .iter()
+            .flat_map(|entry| entry.system.labels())
+            .find(|&existing| existing == label)
+            .ok_or_else(|| GameError::Schedule(format!("ラベル '{}' を持つシステムがありません", label)))
+    }
+    
+    /// システムを名前で有効・無効にする
+    /// 有効に戻したときは失敗回数もリセットする（エラーポリシーで無効にされたシステムの再開にも使う）
+    pub fn set_system_enabled(&mut self, name: &str, enabled: bool) -> GameResult<()> {
+        let index = self.index_of(name)?;
+        let entry = &mut self.systems[index];
+        entry.disabled = !enabled;
+        if enabled {
+            entry.failures = 0;
+        }
+        
+        info!("🔧 システム {} を{}にしました", name, if enabled { "有効" } else { "無効" });
+        Ok(())
+    }
+    
+    /// システムが有効かどうか
+    pub fn is_system_enabled(&self, name: &str) -> GameResult<bool> {
+        let index = self.index_of(name)?;
+        Ok(!self.systems[index].disabled)
+    }
+    
+    /// システムを名前で取り除き、取り除いたシステムを返す
+    /// 実行順序を構築できなければ、取り除いたシステムを元の位置に戻してエラーを返す
+    pub fn remove_system(&mut self, name: &str) -> GameResult<Box<dyn System>> {
+        // 1. システムを取り除く
+        let index = self.index_of(name)?;
+        let entry = self.systems.remove(index);
+        
+        // 2. 実行順序を構築し直す（失敗したら元に戻す）
+        // 取り除いたシステムのラベルを参照していた制約は、構築時に警告して無視される
+        self.schedule_dirty = true;
+        if let Err(e) = self.build_schedule() {
+            self.systems.insert(index, entry);
+            self.restore_schedule();
+            return Err(e);
+        }
+        
+        info!("🗑️ システム {} を取り除きました", name);
+        Ok(entry.system)
+    }
+    
+    /// 変更を取り消したあとに実行順序を構築し直す
+    /// 変更前から構築できない状態だった場合は、再構築が必要な印を残して次の実行時にエラーにする
+    fn restore_schedule(&mut self) {
+        self.schedule_dirty = true;
+        if let Err(e) = self.build_schedule() {
+            warn!("⚠️ 変更は取り消しましたが、実行順序を構築できません: {}", e);
+        }
+    }
+    
+    /// 指定したラベルのシステムの前後にシステムを挿入する
+    /// 実行順序をすぐに構築し直し、制約に問題があれば挿入を取り消してエラーを返す
+    pub fn insert_system<M, S: IntoSystem<M>>(&mut self, placement: SystemPlacement<'_>, system: S) -> GameResult<()> {
+        self.insert_boxed_system(placement, Box::new(system.into_system()))
+    }
+    
+    /// ボックス化されたシステムを挿入する（SystemRegistryで作ったシステム用）
+    pub fn insert_boxed_system(&mut self, placement: SystemPlacement<'_>, system: Box<dyn System>) -> GameResult<()> {
+        // 1. 制約を付けてシステムを追加
+        let mut entry = SystemEntry::new(system);
+        match placement {
+            SystemPlacement::Before(label) => entry.before.push(self.find_label(label)?),
+            SystemPlacement::After(label) => entry.after.push(self.find_label(label)?),
+        }
+        let name = entry.system.name();
+        self.systems.push(entry);
+        self.schedule_dirty = true;
+        
+        // 2. 実行順序を構築し直す（失敗したら元に戻す）
+        if let Err(e) = self.build_schedule() {
+            self.systems.pop();
+            self.restore_schedule();
+            return Err(e);
+        }
+        
+        info!("➕ システム {} を挿入しました（{:?}）", name, placement);
+        Ok(())
+    }
+    
+    /// システムを名前で差し替え、差し替え前のシステムを返す
+    /// 実行条件・エラーポリシー・追加時の制約は引き継ぎ、失敗回数と変更検出の記録はリセットする
+    pub fn replace_system<M, S: IntoSystem<M>>(&mut self, name: &str, system: S) -> GameResult<Box<dyn System>> {
+        self.replace_boxed_system(name, Box::new(system.into_system()))
+    }
+    
+    /// ボックス化されたシステムで差し替える（SystemRegistryで作ったシステム用）
+    pub fn replace_boxed_system(&mut self, name: &str, system: Box<dyn System>) -> GameResult<Box<dyn System>> {
+        // 1. システム本体だけを入れ替える
+        let index = self.index_of(name)?;
+        let entry = &mut self.systems[index];
+        let old_system = std::mem::replace(&mut entry.system, system);
+        let (old_last_run_tick, old_failures) = (entry.last_run_tick, entry.failures);
+        entry.last_run_tick = 0;
+        entry.failures = 0;
+        self.schedule_dirty = true;
+        
+        // 2. 実行順序を構築し直す（失敗したら元に戻す）
+        if let Err(e) = self.build_schedule() {
+            let entry = &mut self.systems[index];
+            entry.system = old_system;
+            entry.last_run_tick = old_last_run_tick;
+            entry.failures = old_failures;
+            self.restore_schedule();
+            return Err(e);
+        }
+        
+        info!("🔁 システム {} を {} に差し替えました", name, self.systems[index].system.name());
+        Ok(old_system)
+    }
+    
+    /// ゲームのやり直しに備えて全てのシステムの状態を初期化する
+    /// 実行順序・実行条件・有効かどうかはそのままにして、System::resetを呼び、変更検出の記録を消す
+    /// 現在のゲーム状態は覚えたままにして、やり直しによる遷移でon_exit/on_enterが実行されるようにする
+    pub fn reset_systems(&mut self) {
+        let entries = self.systems.iter_mut()
+            .chain(self.on_enter.iter_mut().map(|(_, entry)| entry))
+            .chain(self.on_exit.iter_mut().map(|(_, entry)| entry));
+        for entry in entries {
+            entry.system.reset();
+            entry.last_run_tick = 0;
+        }
+        
+        debug!("🔄 システムの状態を初期化しました");
+    }
+    
     /// 全てのシステムをクリア
     pub fn clear(&mut self) {
         self.systems.clear();
+        self.schedule.clear();
+        self.schedule_dirty = false;
+        self.on_enter.clear();
+        self.on_exit.clear();
+        self.current_state = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use crate::ecs::component::Transform;
+    use crate::ecs::condition::in_state;
+    
+    // 制約だけを持つ何もしないシステム
+    struct TestSystem {
+        name: &'static str,
+        labels: Vec<SystemLabel>,
+        before: Vec<SystemLabel>,
+    }
+    
+    impl TestSystem {
+        fn new(name: &'static str) -> Self {
+            Self { name, labels: Vec::new(), before: Vec::new() }
+        }
+        
+        fn with_label(mut self, label: SystemLabel) -> Self {
+            self.labels.push(label);
+            self
+        }
+        
+        fn before(mut self, label: SystemLabel) -> Self {
+            self.before.push(label);
+            self
+        }
+    }
+    
+    impl System for TestSystem {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+        
+        fn labels(&self) -> Vec<SystemLabel> {
+            let mut labels = vec![self.name];
+            labels.extend(self.labels.iter().copied());
+            labels
+        }
+        
+        fn before(&self) -> Vec<SystemLabel> {
+            self.before.clone()
+        }
+        
+        fn run(&mut self, _world: &mut World, _resources: &mut ResourceManager, _delta_time: f32) -> GameResult<()> {
+            Ok(())
+        }
+    }
+    
+    // 実行されるたびに処理を呼ぶシステム
+    struct ProbeSystem {
+        name: &'static str,
+        phase: SystemPhase,
+        on_run: Box<dyn FnMut(&mut World)>,
+    }
+    
+    impl ProbeSystem {
+        fn new(name: &'static str, phase: SystemPhase, on_run: impl FnMut(&mut World) + 'static) -> Self {
+            Self { name, phase, on_run: Box::new(on_run) }
+        }
+    }
+    
+    impl System for ProbeSystem {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+        
+        fn phase(&self) -> SystemPhase {
+            self.phase
+        }
+        
+        fn run(&mut self, world: &mut World, _resources: &mut ResourceManager, _delta_time: f32) -> GameResult<()> {
+            (self.on_run)(world);
+            Ok(())
+        }
+    }
+    
+    fn names(manager: &SystemManager) -> Vec<&'static str> {
+        manager.list_systems().into_iter().map(|info| info.name).collect()
+    }
+    
+    #[test]
+    fn remove_system_rebuilds_schedule() {
+        let mut manager = SystemManager::new();
+        manager.add_system(TestSystem::new("A").before("B"));
+        manager.add_system(TestSystem::new("B"));
+        manager.add_system(TestSystem::new("C"));
+        manager.build_schedule().unwrap();
+        
+        let removed = manager.remove_system("B").unwrap();
+        assert_eq!(removed.name(), "B");
+        assert_eq!(names(&manager), vec!["A", "C"]);
     }
-} 
\ No newline at end of file
+    
+    #[test]
+    fn remove_system_keeps_system_when_schedule_fails() {
+        // AとBの制約が循環しているので、Cを取り除いても実行順序は構築できない
+        let mut manager = SystemManager::new();
+        manager.add_system(TestSystem::new("A").before("B"));
+        manager.add_system(TestSystem::new("B").before("A"));
+        manager.add_system(TestSystem::new("C"));
+        
+        assert!(manager.remove_system("C").is_err());
+        assert_eq!(names(&manager), vec!["A", "B", "C"]);
+        assert!(manager.build_schedule().is_err());
+    }
+    
+    #[test]
+    fn before_and_after_order_systems_across_labels() {
+        // 追加順はC, B, Aだが、ラベルの制約でA -> B -> Cの順になる
+        let mut manager = SystemManager::new();
+        manager.add_system(TestSystem::new("C").with_label("Late"));
+        manager.add_system(TestSystem::new("B")).after("Early").before("Late");
+        manager.add_system(TestSystem::new("A").with_label("Early"));
+        manager.build_schedule().unwrap();
+        
+        assert_eq!(names(&manager), vec!["A", "B", "C"]);
+        assert!(manager.unknown_labels().is_empty());
+    }
+    
+    #[test]
+    fn two_system_cycle_names_both_systems() {
+        let mut manager = SystemManager::new();
+        manager.add_system(TestSystem::new("First").before("Second"));
+        manager.add_system(TestSystem::new("Second").before("First"));
+        
+        let message = manager.build_schedule().unwrap_err().to_string();
+        assert!(message.contains("First"), "{}", message);
+        assert!(message.contains("Second"), "{}", message);
+    }
+    
+    #[test]
+    fn unknown_labels_are_reported() {
+        let mut manager = SystemManager::new();
+        manager.add_system(TestSystem::new("A").before("Missing"));
+        manager.add_system(TestSystem::new("B")).after("A").after("AlsoMissing");
+        
+        // 対象のないラベルの制約は無視して実行順序を構築できる
+        manager.build_schedule().unwrap();
+        assert_eq!(names(&manager), vec!["A", "B"]);
+        assert_eq!(manager.unknown_labels(), vec![("A", "Missing"), ("B", "AlsoMissing")]);
+    }
+    
+    #[test]
+    fn insert_system_is_rolled_back_when_schedule_fails() {
+        let mut manager = SystemManager::new();
+        manager.add_system(TestSystem::new("A"));
+        manager.add_system(TestSystem::new("B").before("A"));
+        manager.build_schedule().unwrap();
+        
+        // Aより後でBより前には置けない（A -> X -> B -> A の循環）
+        let result = manager.insert_system(SystemPlacement::After("A"), TestSystem::new("X").before("B"));
+        assert!(result.is_err());
+        assert_eq!(names(&manager), vec!["B", "A"]);
+    }
+    
+    #[test]
+    fn render_phase_sees_removals_from_every_fixed_step_of_the_frame() {
+        let mut world = World::new();
+        let entity_id = world.create_entity().unwrap();
+        world.add_component(entity_id, Transform::new(0.0, 0.0)).unwrap();
+        let mut resources = ResourceManager::new();
+        resources.add(FixedTime::new(1.0 / 60.0, 5));
+        
+        // 1ステップ目でコンポーネントを外し、描画フェーズで削除が見えたかを記録する
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        let seen_in_render = Rc::clone(&seen);
+        let mut manager = SystemManager::new();
+        manager.add_system(ProbeSystem::new("Remove", SystemPhase::Update, move |world| {
+            world.remove_component::<Transform>(entity_id);
+        }));
+        manager.add_system(ProbeSystem::new("Render", SystemPhase::Render, move |world| {
+            seen_in_render.borrow_mut().push(world.removed_components::<Transform>().len());
+        }));
+        
+        // 2ステップ分と少しの時間で1フレーム
+        manager.run_frame(&mut world, &mut resources, 2.5 / 60.0).unwrap();
+        assert_eq!(resources.get::<FixedTime>().unwrap().step_count, 2);
+        assert_eq!(*seen.borrow(), vec![1]);
+        
+        // 次のフレームの描画フェーズからは、もう新しい削除として見えない
+        manager.run_frame(&mut world, &mut resources, 1.0 / 60.0).unwrap();
+        assert_eq!(*seen.borrow(), vec![1, 0]);
+    }
+    
+    #[test]
+    fn run_frame_clamps_fixed_steps_per_frame() {
+        let mut world = World::new();
+        let mut resources = ResourceManager::new();
+        resources.add(FixedTime::new(1.0 / 60.0, 5));
+        let mut manager = SystemManager::new();
+        manager.add_system(TestSystem::new("Step"));
+        
+        // 処理落ちで10ステップ分溜まっても、実行するのは上限の5ステップで残りは切り捨てる
+        manager.run_frame(&mut world, &mut resources, 10.0 / 60.0).unwrap();
+        let fixed_time = resources.get::<FixedTime>().unwrap();
+        assert_eq!(fixed_time.step_count, 5);
+        assert!((0.0..fixed_time.step).contains(&fixed_time.accumulator));
+    }
+    
+    // 実行されたら名前を記録するシステム
+    fn logging_system(name: &'static str, log: &Rc<RefCell<Vec<&'static str>>>) -> ProbeSystem {
+        let log = Rc::clone(log);
+        ProbeSystem::new(name, SystemPhase::Update, move |_world| log.borrow_mut().push(name))
+    }
+    
+    fn set_state(resources: &mut ResourceManager, state: GameState) {
+        *resources.get_mut::<GameState>().unwrap() = state;
+    }
+    
+    #[test]
+    fn run_if_in_state_skips_system_in_other_states() {
+        let mut world = World::new();
+        let mut resources = ResourceManager::new();
+        resources.add(GameState::Paused);
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mut manager = SystemManager::new();
+        manager.add_system(logging_system("PlayingOnly", &log))
+            .run_if(in_state(GameState::Playing));
+        
+        manager.run_systems(&mut world, &mut resources, 1.0 / 60.0).unwrap();
+        assert!(log.borrow().is_empty());
+        
+        set_state(&mut resources, GameState::Playing);
+        manager.run_systems(&mut world, &mut resources, 1.0 / 60.0).unwrap();
+        assert_eq!(*log.borrow(), vec!["PlayingOnly"]);
+    }
+    
+    #[test]
+    fn on_exit_and_on_enter_run_once_per_transition_in_order() {
+        let mut world = World::new();
+        let mut resources = ResourceManager::new();
+        resources.add(GameState::Playing);
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mut manager = SystemManager::new();
+        manager.add_on_enter(GameState::Playing, logging_system("EnterPlaying", &log));
+        manager.add_on_exit(GameState::Playing, logging_system("ExitPlaying", &log));
+        manager.add_on_enter(GameState::Paused, logging_system("EnterPaused", &log));
+        manager.add_on_exit(GameState::Paused, logging_system("ExitPaused", &log));
+        
+        // 最初のフレームで今の状態に入る
+        manager.run_systems(&mut world, &mut resources, 1.0 / 60.0).unwrap();
+        manager.run_systems(&mut world, &mut resources, 1.0 / 60.0).unwrap();
+        assert_eq!(*log.borrow(), vec!["EnterPlaying"]);
+        
+        // 遷移1回につき、前の状態から出てから新しい状態に入る
+        set_state(&mut resources, GameState::Paused);
+        manager.run_systems(&mut world, &mut resources, 1.0 / 60.0).unwrap();
+        manager.run_systems(&mut world, &mut resources, 1.0 / 60.0).unwrap();
+        assert_eq!(*log.borrow(), vec!["EnterPlaying", "ExitPlaying", "EnterPaused"]);
+        
+        set_state(&mut resources, GameState::Playing);
+        manager.run_systems(&mut world, &mut resources, 1.0 / 60.0).unwrap();
+        assert_eq!(*log.borrow(), vec!["EnterPlaying", "ExitPlaying", "EnterPaused", "ExitPaused", "EnterPlaying"]);
+    }
+}
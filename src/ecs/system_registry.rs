@@ -0,0 +1,50 @@
+use std::collections::BTreeMap;
+use crate::error::{GameError, GameResult};
+use crate::ecs::system::System;
+
+// システムを作る関数の型
+type SystemFactory = Box<dyn Fn() -> Box<dyn System>>;
+
+/// システムの登録簿
+/// 名前からシステムを作れるようにしておくリソース
+/// デバッグ用コンソールからシステムを挿入・差し替えするときに使う
+#[derive(Default)]
+pub struct SystemRegistry {
+    factories: BTreeMap<&'static str, SystemFactory>,
+}
+
+impl SystemRegistry {
+    /// 空の登録簿を作成
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// システムを作る関数を登録する
+    /// 名前は作ったシステムのSystem::nameを使う（同じ名前なら上書き）
+    pub fn register<S, F>(&mut self, factory: F)
+    where
+        S: System + 'static,
+        F: Fn() -> S + 'static,
+    {
+        let name = factory().name();
+        self.factories.insert(name, Box::new(move || Box::new(factory())));
+    }
+    
+    /// 名前からシステムを作る
+    pub fn create(&self, name: &str) -> GameResult<Box<dyn System>> {
+        self.factories
+            .get(name)
+            .map(|factory| factory())
+            .ok_or_else(|| GameError::SystemNotFound(name.to_string()))
+    }
+    
+    /// 登録されているか
+    pub fn contains(&self, name: &str) -> bool {
+        self.factories.contains_key(name)
+    }
+    
+    /// 登録されているシステムの名前一覧（名前順）
+    pub fn names(&self) -> Vec<&'static str> {
+        self.factories.keys().copied().collect()
+    }
+}
@@ -1,8 +1,11 @@
-use wasm_bindgen::prelude::*;
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use crate::error::{GameError, GameResult};
 use crate::ecs::entity::{EntityId, EntityManager};
-use crate::ecs::component::{Component, ComponentManager};
+use crate::ecs::component::{Component, ComponentManager, ComponentStorage, RemovedComponents, Name};
 use crate::ecs::system::{System, SystemManager};
 use crate::ecs::resources::ResourceManager;
+use log::warn;
 
 /// World構造体
 /// エンティティ、コンポーネント、システム、リソースを統合管理する
@@ -15,6 +18,22 @@ pub struct World {
     
     // 新しく作成されたエンティティのID
     created_entities: Vec<EntityId>,
+    
+    // 名前からエンティティIDへの索引
+    // Nameコンポーネントの追加・削除に合わせて更新される
+    names: HashMap<String, EntityId>,
+    
+    // 変更検出用の現在のティック
+    // システムが1つ実行されるたびに進む
+    change_tick: u32,
+    
+    // 現在実行中のシステムが前回実行されたときのティック
+    // 「前回の実行以降に変更されたか」の判定に使う
+    last_change_tick: u32,
+    
+    // 前回のフレーム更新時のティック
+    // 削除されたコンポーネントの記録を1フレーム分保持するために使う
+    last_frame_tick: u32,
 }
 
 impl World {
@@ -24,6 +43,11 @@ impl World {
             entity_manager: EntityManager::new(),
             component_manager: ComponentManager::new(),
             created_entities: Vec::new(),
+            names: HashMap::new(),
+            // ティック0は「一度も実行されていない」ことを表すため、1から開始する
+            change_tick: 1,
+            last_change_tick: 0,
+            last_frame_tick: 0,
         }
     }
     
@@ -32,7 +56,7 @@ impl World {
     //
     
     /// 新しいエンティティを作成し、IDを返す
-    pub fn create_entity(&mut self) -> Result<EntityId, JsValue> {
+    pub fn create_entity(&mut self) -> GameResult<EntityId> {
         let entity_id = self.entity_manager.create_entity()?;
         self.created_entities.push(entity_id);
         Ok(entity_id)
@@ -40,11 +64,14 @@ impl World {
     
     /// エンティティを削除
     pub fn remove_entity(&mut self, entity_id: EntityId) {
+        // 名前の索引から外す
+        self.unindex_name(entity_id);
+        
         // エンティティを削除予定としてマーク
         self.entity_manager.mark_entity_for_removal(entity_id);
         
         // 関連するコンポーネントを全て削除
-        self.component_manager.remove_entity(&entity_id);
+        self.component_manager.remove_entity(&entity_id, self.change_tick);
     }
     
     /// エンティティが存在するかチェック
@@ -58,6 +85,7 @@ impl World {
     }
     
     /// アクティブなエンティティのIDのベクターを取得
+    /// IDの小さい順（作成順）に並んでいることを保証する
     pub fn get_all_entities(&self) -> Vec<EntityId> {
         self.entity_manager.active_entities().copied().collect()
     }
@@ -67,15 +95,22 @@ impl World {
     //
     
     /// エンティティにコンポーネントを追加
-    pub fn add_component<T: Component>(&mut self, entity_id: EntityId, component: T) -> Result<(), JsValue> {
+    pub fn add_component<T: Component>(&mut self, entity_id: EntityId, component: T) -> GameResult<()> {
         if !self.entity_exists(entity_id) {
-            return Err(JsValue::from_str(&format!(
-                "エンティティID: {} は存在しません！",
-                entity_id
-            )));
+            return Err(GameError::EntityNotFound(entity_id));
         }
         
-        self.component_manager.add_component(entity_id, component);
+        // 名前コンポーネントなら索引を更新
+        if let Some(name) = (&component as &dyn Any).downcast_ref::<Name>() {
+            self.unindex_name(entity_id);
+            if let Some(previous) = self.names.insert(name.0.clone(), entity_id) {
+                if previous != entity_id {
+                    warn!("⚠️ 名前「{}」はエンティティ {} から {} に付け替えられました", name.0, previous, entity_id);
+                }
+            }
+        }
+        
+        self.component_manager.add_component(entity_id, component, self.change_tick);
         Ok(())
     }
     
@@ -94,7 +129,7 @@ impl World {
             return None;
         }
         
-        self.component_manager.get_component_mut(&entity_id)
+        self.component_manager.get_component_mut(&entity_id, self.change_tick)
     }
     
     /// エンティティからコンポーネントを削除
@@ -103,7 +138,37 @@ impl World {
             return None;
         }
         
-        self.component_manager.remove_component(&entity_id)
+        if TypeId::of::<T>() == TypeId::of::<Name>() {
+            self.unindex_name(entity_id);
+        }
+        
+        self.component_manager.remove_component(&entity_id, self.change_tick)
+    }
+    
+    //
+    // 名前関連のメソッド
+    //
+    
+    /// 名前からエンティティを検索
+    pub fn find_by_name(&self, name: &str) -> Option<EntityId> {
+        self.names
+            .get(name)
+            .copied()
+            .filter(|&entity_id| self.entity_exists(entity_id))
+    }
+    
+    /// エンティティの名前を取得
+    pub fn get_name(&self, entity_id: EntityId) -> Option<&str> {
+        self.get_component::<Name>(entity_id).map(|name| name.as_str())
+    }
+    
+    /// エンティティの現在の名前を索引から外す
+    fn unindex_name(&mut self, entity_id: EntityId) {
+        if let Some(name) = self.component_manager.get_component::<Name>(&entity_id) {
+            if self.names.get(&name.0) == Some(&entity_id) {
+                self.names.remove(&name.0);
+            }
+        }
     }
     
     /// エンティティが特定のコンポーネントを持っているかチェック
@@ -115,42 +180,247 @@ impl World {
         self.component_manager.has_component::<T>(&entity_id)
     }
     
-    /// 特定のコンポーネントを持つ全てのエンティティIDを取得
+    /// 特定のコンポーネントを持つ全てのエンティティIDを取得（ID順）
     pub fn get_entities_with_component<T: Component>(&self) -> Vec<EntityId> {
         self.component_manager.entities_with_component::<T>()
     }
     
+    /// 特定の型のコンポーネントストレージを一時的に取り出す
+    /// 関数システムのQueryが使う（取り出している間、Worldからはその型のコンポーネントが見えない）
+    pub fn take_component_storage<T: Component>(&mut self) -> ComponentStorage<T> {
+        self.component_manager.take_storage::<T>()
+    }
+    
+    /// 取り出したコンポーネントストレージを戻す
+    pub fn restore_component_storage<T: Component>(&mut self, storage: ComponentStorage<T>) {
+        self.component_manager.restore_storage(storage);
+    }
+    
+    //
+    // 変更検出関連のメソッド
+    //
+    
+    /// 現在のティックを取得
+    pub fn change_tick(&self) -> u32 {
+        self.change_tick
+    }
+    
+    /// 現在実行中のシステムが前回実行されたときのティックを取得
+    pub fn last_change_tick(&self) -> u32 {
+        self.last_change_tick
+    }
+    
+    /// 変更検出の基準となるティックを設定
+    /// システムマネージャーが各システムの実行前に呼び出す
+    pub fn set_last_change_tick(&mut self, tick: u32) {
+        self.last_change_tick = tick;
+    }
+    
+    /// ティックを1つ進め、進める前のティックを返す
+    pub fn increment_change_tick(&mut self) -> u32 {
+        let tick = self.change_tick;
+        self.change_tick = self.change_tick.wrapping_add(1);
+        tick
+    }
+    
+    /// 前回のシステム実行以降に型Tのコンポーネントが追加されたエンティティIDを取得（ID順）
+    pub fn get_entities_with_added<T: Component>(&self) -> Vec<EntityId> {
+        self.get_entities_with_added_since::<T>(self.last_change_tick)
+    }
+    
+    /// 指定したティックより後に型Tのコンポーネントが追加されたエンティティIDを取得
+    pub fn get_entities_with_added_since<T: Component>(&self, since_tick: u32) -> Vec<EntityId> {
+        self.component_manager
+            .entities_with_added::<T>(since_tick)
+            .into_iter()
+            .filter(|&entity_id| self.entity_exists(entity_id))
+            .collect()
+    }
+    
+    /// 前回のシステム実行以降に型Tのコンポーネントが変更されたエンティティIDを取得（ID順）
+    /// 追加されたコンポーネントも変更されたものとして扱う
+    pub fn get_entities_with_changed<T: Component>(&self) -> Vec<EntityId> {
+        self.get_entities_with_changed_since::<T>(self.last_change_tick)
+    }
+    
+    /// 指定したティックより後に型Tのコンポーネントが変更されたエンティティIDを取得
+    pub fn get_entities_with_changed_since<T: Component>(&self, since_tick: u32) -> Vec<EntityId> {
+        self.component_manager
+            .entities_with_changed::<T>(since_tick)
+            .into_iter()
+            .filter(|&entity_id| self.entity_exists(entity_id))
+            .collect()
+    }
+    
+    /// エンティティの型Tのコンポーネントが前回のシステム実行以降に追加されたかチェック
+    pub fn is_component_added<T: Component>(&self, entity_id: EntityId) -> bool {
+        self.component_manager
+            .get_component_ticks::<T>(&entity_id)
+            .is_some_and(|ticks| ticks.is_added_since(self.last_change_tick))
+    }
+    
+    /// エンティティの型Tのコンポーネントが前回のシステム実行以降に変更されたかチェック
+    pub fn is_component_changed<T: Component>(&self, entity_id: EntityId) -> bool {
+        self.component_manager
+            .get_component_ticks::<T>(&entity_id)
+            .is_some_and(|ticks| ticks.is_changed_since(self.last_change_tick))
+    }
+    
+    /// 前回のシステム実行以降に型Tのコンポーネントを失ったエンティティを取得
+    /// `remove_component`と`remove_entity`の両方が対象になる
+    pub fn removed_components<T: Component>(&self) -> RemovedComponents<T> {
+        self.removed_components_since::<T>(self.last_change_tick)
+    }
+    
+    /// 指定したティックより後に型Tのコンポーネントを失ったエンティティを取得
+    pub fn removed_components_since<T: Component>(&self, since_tick: u32) -> RemovedComponents<T> {
+        RemovedComponents::new(self.component_manager.removed_since::<T>(since_tick))
+    }
+    
     //
     // 世界の更新
     //
     
-    /// ワールドの状態を更新
     /// 削除予定のエンティティを実際に削除する
-    pub fn update(&mut self) {
+    /// 削除されたコンポーネントの記録は残すので、1フレームに何回呼んでも後のシステムから削除が見える
+    pub fn apply_removals(&mut self) {
         self.entity_manager.update();
         self.created_entities.clear();
     }
     
+    /// ワールドの状態を更新
+    /// 削除予定のエンティティを実際に削除し、古い削除の記録を捨てる
+    /// 描画フェーズまで終わったフレームの最後に1回だけ呼ぶ
+    pub fn update(&mut self) {
+        self.apply_removals();
+        
+        // 削除されたコンポーネントの記録は次のフレームの終わりまで保持する
+        self.component_manager.prune_removed(self.last_frame_tick);
+        self.last_frame_tick = self.change_tick;
+    }
+    
     /// システムを実行
     pub fn run_systems(
         &mut self,
         system_manager: &mut SystemManager,
         resource_manager: &mut ResourceManager,
         delta_time: f32,
-    ) -> Result<(), JsValue> {
-        // 全てのシステムを実行
-        system_manager.run_systems(self, resource_manager, delta_time)?;
-        
-        // ワールドの状態を更新
-        self.update();
-        
-        Ok(())
+    ) -> GameResult<()> {
+        // 1フレーム分のシステムを実行（ロジックは固定タイムステップ、描画は毎フレーム）
+        // ワールドの状態更新はロジックのステップごとに行われる
+        system_manager.run_frame(self, resource_manager, delta_time)
     }
     
     /// 全てのエンティティとコンポーネントをクリア
     pub fn clear(&mut self) {
         self.entity_manager.clear_all_entities();
-        self.component_manager.clear();
+        self.component_manager.clear(self.change_tick);
         self.created_entities.clear();
+        self.names.clear();
     }
-} 
\ No newline at end of file
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ecs::component::Transform;
+    
+    #[test]
+    fn entities_are_listed_in_creation_order_after_remove_and_recreate() {
+        let mut world = World::new();
+        let ids: Vec<EntityId> = (0..5).map(|_| world.create_entity().unwrap()).collect();
+        assert_eq!(world.get_all_entities(), ids);
+        
+        world.remove_entity(ids[1]);
+        world.remove_entity(ids[3]);
+        world.update();
+        assert_eq!(world.get_all_entities(), vec![ids[0], ids[2], ids[4]]);
+        
+        // IDは使い回さないので、作り直したエンティティは最後に並ぶ
+        let recreated = world.create_entity().unwrap();
+        assert!(recreated > ids[4]);
+        assert_eq!(world.get_all_entities(), vec![ids[0], ids[2], ids[4], recreated]);
+    }
+    
+    #[test]
+    fn entities_with_component_are_listed_in_id_order() {
+        let mut world = World::new();
+        let ids: Vec<EntityId> = (0..4).map(|_| world.create_entity().unwrap()).collect();
+        
+        // 追加した順番ではなくID順に並ぶ
+        for &i in &[3, 0, 2] {
+            world.add_component(ids[i], Transform::new(0.0, 0.0)).unwrap();
+        }
+        assert_eq!(world.get_entities_with_component::<Transform>(), vec![ids[0], ids[2], ids[3]]);
+        
+        // 外して付け直しても、エンティティを削除して作り直しても順番は変わらない
+        world.remove_component::<Transform>(ids[0]);
+        world.add_component(ids[1], Transform::new(0.0, 0.0)).unwrap();
+        world.add_component(ids[0], Transform::new(0.0, 0.0)).unwrap();
+        assert_eq!(world.get_entities_with_component::<Transform>(), vec![ids[0], ids[1], ids[2], ids[3]]);
+        
+        world.remove_entity(ids[2]);
+        world.update();
+        let recreated = world.create_entity().unwrap();
+        world.add_component(recreated, Transform::new(0.0, 0.0)).unwrap();
+        assert_eq!(world.get_entities_with_component::<Transform>(), vec![ids[0], ids[1], ids[3], recreated]);
+    }
+    
+    #[test]
+    fn added_is_seen_only_until_the_next_tick() {
+        let mut world = World::new();
+        let entity_id = world.create_entity().unwrap();
+        let tick = world.change_tick();
+        world.add_component(entity_id, Transform::new(0.0, 0.0)).unwrap();
+        
+        // 追加したティックより前を基準にすると追加として見える
+        world.set_last_change_tick(tick - 1);
+        assert!(world.is_component_added::<Transform>(entity_id));
+        assert!(world.is_component_changed::<Transform>(entity_id));
+        assert_eq!(world.get_entities_with_added::<Transform>(), vec![entity_id]);
+        
+        // 追加したティックを基準にすると（次に実行されるシステムからは）もう見えない
+        world.increment_change_tick();
+        world.set_last_change_tick(tick);
+        assert!(!world.is_component_added::<Transform>(entity_id));
+        assert!(world.get_entities_with_added::<Transform>().is_empty());
+    }
+    
+    #[test]
+    fn get_component_mut_marks_changed() {
+        let mut world = World::new();
+        let entity_id = world.create_entity().unwrap();
+        world.add_component(entity_id, Transform::new(0.0, 0.0)).unwrap();
+        let added_tick = world.increment_change_tick();
+        world.set_last_change_tick(added_tick);
+        assert!(!world.is_component_changed::<Transform>(entity_id));
+        
+        world.get_component_mut::<Transform>(entity_id).unwrap().position.x = 1.0;
+        assert!(world.is_component_changed::<Transform>(entity_id));
+        assert!(!world.is_component_added::<Transform>(entity_id));
+        assert_eq!(world.get_entities_with_changed::<Transform>(), vec![entity_id]);
+    }
+    
+    #[test]
+    fn removals_stay_in_the_log_for_one_update() {
+        let mut world = World::new();
+        let a = world.create_entity().unwrap();
+        let b = world.create_entity().unwrap();
+        world.add_component(a, Transform::new(0.0, 0.0)).unwrap();
+        world.add_component(b, Transform::new(0.0, 0.0)).unwrap();
+        world.update();
+        
+        world.remove_component::<Transform>(a);
+        world.remove_entity(b);
+        assert_eq!(world.removed_components_since::<Transform>(0).len(), 2);
+        
+        // 削除したフレームの終わりの更新では残し、次のフレームの終わりに捨てる
+        // （フレームの間にはシステムが実行されてティックが進む）
+        world.increment_change_tick();
+        world.update();
+        let removed = world.removed_components_since::<Transform>(0);
+        assert!(removed.contains(a) && removed.contains(b));
+        world.increment_change_tick();
+        world.update();
+        assert!(world.removed_components_since::<Transform>(0).is_empty());
+    }
+}
@@ -0,0 +1,74 @@
+// エラーモジュール
+//
+// ECSとゲームのルールで使うエラー型を定義します。
+// JsValueへの変換は#[wasm_bindgen]の境界（web機能）でだけ行うので、
+// ECSとルールの部分はブラウザなしでも動かせます。
+
+use std::fmt;
+use crate::ecs::entity::EntityId;
+
+/// ゲームのエラー
+#[derive(Debug, Clone, PartialEq)]
+pub enum GameError {
+    EntityNotFound(EntityId),             // エンティティが存在しない
+    ComponentMissing {                    // エンティティが必要なコンポーネントを持っていない
+        entity: EntityId,
+        component: &'static str,
+    },
+    ResourceMissing(&'static str),        // リソースが存在しない
+    ResourceBorrowed(String),             // リソースが借用中で取得できない
+    StackNotFound(String),                // スタック（ストック、ウェイストなど）が見つからない
+    IllegalMove(String),                  // ルール上できない操作
+    Capacity(String),                     // 上限を超えた
+    Schedule(String),                     // システムの実行順序の問題
+    SystemNotFound(String),               // 指定した名前のシステムが見つからない
+    Serialization(String),                // シリアライズ・デシリアライズの失敗
+    Reflection(String),                   // 型レジストリを通した読み書きの失敗
+    InvalidOperation(String),             // その他の不正な操作
+    #[cfg(feature = "web")]
+    Web(String),                          // ブラウザAPIのエラー
+}
+
+/// ゲームのResult型
+pub type GameResult<T> = Result<T, GameError>;
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GameError::EntityNotFound(entity) => write!(f, "エンティティID: {} は存在しません！", entity),
+            GameError::ComponentMissing { entity, component } => {
+                write!(f, "エンティティ {} は {} を持っていません", entity, component)
+            },
+            GameError::ResourceMissing(name) => write!(f, "リソース {} が見つかりません", name),
+            GameError::ResourceBorrowed(message) => write!(f, "{}", message),
+            GameError::StackNotFound(name) => write!(f, "{}が見つかりません", name),
+            GameError::IllegalMove(message) => write!(f, "その操作はできません: {}", message),
+            GameError::Capacity(message) => write!(f, "上限を超えました: {}", message),
+            GameError::Schedule(message) => write!(f, "{}", message),
+            GameError::SystemNotFound(name) => write!(f, "システム {} が見つかりません", name),
+            GameError::Serialization(message) => write!(f, "{}", message),
+            GameError::Reflection(message) => write!(f, "{}", message),
+            GameError::InvalidOperation(message) => write!(f, "{}", message),
+            #[cfg(feature = "web")]
+            GameError::Web(message) => write!(f, "ブラウザAPIのエラー: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for GameError {}
+
+// #[wasm_bindgen]の境界でJavaScriptのエラーに変換する
+#[cfg(feature = "web")]
+impl From<GameError> for wasm_bindgen::JsValue {
+    fn from(error: GameError) -> Self {
+        wasm_bindgen::JsValue::from_str(&error.to_string())
+    }
+}
+
+// ブラウザAPIのエラーをゲームのエラーとして扱う
+#[cfg(feature = "web")]
+impl From<wasm_bindgen::JsValue> for GameError {
+    fn from(value: wasm_bindgen::JsValue) -> Self {
+        GameError::Web(value.as_string().unwrap_or_else(|| format!("{:?}", value)))
+    }
+}
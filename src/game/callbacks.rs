@@ -0,0 +1,55 @@
+use wasm_bindgen::prelude::*;
+use js_sys::Function;
+use crate::game::events::GameEvent;
+use crate::utils::to_js_value;
+use log::error;
+
+/// ページが登録したイベントのコールバック
+/// Events<GameEvent>から取り出したイベントを、種類ごとのJavaScriptの関数に渡す
+#[derive(Default)]
+pub struct EventCallbacks {
+    pub on_move: Option<Function>,
+    pub on_state_change: Option<Function>,
+    pub on_score: Option<Function>,
+    pub on_clear: Option<Function>,
+    pub on_error: Option<Function>,
+}
+
+impl EventCallbacks {
+    /// コールバックのない状態で作成
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// イベントの種類に対応するコールバックを取得
+    pub fn callback_for(&self, event: &GameEvent) -> Option<&Function> {
+        match event {
+            GameEvent::Move { .. } => self.on_move.as_ref(),
+            GameEvent::StateChange { .. } => self.on_state_change.as_ref(),
+            GameEvent::Score { .. } => self.on_score.as_ref(),
+            GameEvent::Clear { .. } => self.on_clear.as_ref(),
+            GameEvent::Error { .. } => self.on_error.as_ref(),
+        }
+    }
+    
+    /// 全てのコールバックを外す
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+}
+
+/// イベントをJavaScriptのオブジェクト（{ type: "move", from: "waste", ... }）にしてコールバックに渡す
+/// コールバックの中からコールバックを登録し直せるように、呼び出す間はEventCallbacksを借用しない
+pub fn dispatch_events(callbacks: &std::cell::RefCell<EventCallbacks>, events: &[GameEvent]) {
+    for event in events {
+        let callback = match callbacks.borrow().callback_for(event) {
+            Some(callback) => callback.clone(),
+            None => continue,
+        };
+        
+        let result = to_js_value(event).and_then(|payload| callback.call1(&JsValue::NULL, &payload));
+        if let Err(e) = result {
+            error!("イベントのコールバックでエラーが発生しました: {:?}", e);
+        }
+    }
+}
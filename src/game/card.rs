@@ -1,8 +1,8 @@
-use wasm_bindgen::prelude::*;
+use crate::error::{GameError, GameResult};
 use crate::ecs::world::World;
 use crate::ecs::entity::EntityId;
-use crate::ecs::component::{Transform, CardInfo, Renderable, Draggable, Clickable, ClickHandlerType};
-use crate::constants::{CARD_WIDTH, CARD_HEIGHT, CARD_BORDER_RADIUS};
+use crate::ecs::component::{Transform, CardInfo, Draggable};
+use crate::ecs::bundle::CardBundle;
 
 /// カードを作成する関数
 /// プレイヤーが操作するトランプカードのエンティティを作成
@@ -14,38 +14,13 @@ pub fn create_card(
     y: f64,
     face_up: bool,
     z_index: i32,
-) -> Result<EntityId, JsValue> {
-    // 新しいエンティティを作成
-    let entity_id = world.create_entity()?;
-    
-    // トランスフォームコンポーネントを追加
-    let transform = Transform::new(x, y).with_z_index(z_index);
-    world.add_component(entity_id, transform)?;
-    
-    // カード情報コンポーネントを追加
-    let mut card_info = CardInfo::new(suit, rank);
-    card_info.face_up = face_up;
-    world.add_component(entity_id, card_info)?;
-    
-    // レンダラブルコンポーネントを追加
-    let renderable = Renderable::card(CARD_WIDTH, CARD_HEIGHT);
-    world.add_component(entity_id, renderable)?;
-    
-    // ドラッグ可能コンポーネントを追加（面が表の場合のみ）
-    if face_up {
-        let draggable = Draggable::new();
-        world.add_component(entity_id, draggable)?;
-    }
-    
-    // クリック可能コンポーネントを追加
-    let clickable = Clickable::new(ClickHandlerType::FlipCard);
-    world.add_component(entity_id, clickable)?;
-    
-    Ok(entity_id)
+) -> GameResult<EntityId> {
+    // カードのコンポーネントをまとめて持つエンティティを作成
+    world.spawn(CardBundle::new(suit, rank, x, y, face_up, z_index))
 }
 
 /// カードの表面と裏面を切り替える関数
-pub fn flip_card(world: &mut World, card_id: EntityId) -> Result<(), JsValue> {
+pub fn flip_card(world: &mut World, card_id: EntityId) -> GameResult<()> {
     // カード情報コンポーネントを取得
     if let Some(card_info) = world.get_component_mut::<CardInfo>(card_id) {
         // 表裏を反転
@@ -54,7 +29,7 @@ pub fn flip_card(world: &mut World, card_id: EntityId) -> Result<(), JsValue> {
         // 表向きになった場合、ドラッグ可能にする
         if card_info.face_up {
             if !world.has_component::<Draggable>(card_id) {
-                world.add_component(card_id, Draggable::new())?;
+                world.add_component(card_id, Draggable::new().with_drag_children())?;
             }
         } else {
             // 裏向きになった場合、ドラッグ不可にする（必要に応じて）
@@ -63,10 +38,10 @@ pub fn flip_card(world: &mut World, card_id: EntityId) -> Result<(), JsValue> {
         
         Ok(())
     } else {
-        Err(JsValue::from_str(&format!(
-            "エンティティID: {} にCardInfoコンポーネントが見つかりません",
-            card_id
-        )))
+        Err(GameError::ComponentMissing {
+            entity: card_id,
+            component: "CardInfo",
+        })
     }
 }
 
@@ -131,7 +106,7 @@ pub fn can_stack_on_existing_foundation(
 }
 
 /// 完全なカードデッキ（52枚）を作成
-pub fn create_deck(world: &mut World, x: f64, y: f64) -> Result<Vec<EntityId>, JsValue> {
+pub fn create_deck(world: &mut World, x: f64, y: f64) -> GameResult<Vec<EntityId>> {
     let mut deck = Vec::with_capacity(52);
     
     // 各スート（0-3）、各ランク（0-12）のカードを作成
@@ -156,26 +131,35 @@ pub fn shuffle_deck(deck: &mut Vec<EntityId>) {
     deck.shuffle(&mut rng);
 }
 
+/// シード値を指定してカードデッキをシャッフル
+/// 同じシード値なら必ず同じ並びになる（シミュレーションや回帰テストで配り方を再現するため）
+pub fn shuffle_deck_with_seed(deck: &mut Vec<EntityId>, seed: u64) {
+    use rand::seq::SliceRandom;
+    use rand::SeedableRng;
+    let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+    deck.shuffle(&mut rng);
+}
+
 /// カードの位置を設定
-pub fn set_card_position(world: &mut World, card_id: EntityId, x: f64, y: f64, z_index: i32) -> Result<(), JsValue> {
+pub fn set_card_position(world: &mut World, card_id: EntityId, x: f64, y: f64, z_index: i32) -> GameResult<()> {
     if let Some(transform) = world.get_component_mut::<Transform>(card_id) {
         transform.position.x = x;
         transform.position.y = y;
         transform.z_index = z_index;
         Ok(())
     } else {
-        Err(JsValue::from_str(&format!(
-            "エンティティID: {} にTransformコンポーネントが見つかりません",
-            card_id
-        )))
+        Err(GameError::ComponentMissing {
+            entity: card_id,
+            component: "Transform",
+        })
     }
 }
 
 /// カードがドラッグ可能かどうかを設定
-pub fn set_card_draggable(world: &mut World, card_id: EntityId, draggable: bool) -> Result<(), JsValue> {
+pub fn set_card_draggable(world: &mut World, card_id: EntityId, draggable: bool) -> GameResult<()> {
     if draggable {
         if !world.has_component::<Draggable>(card_id) {
-            world.add_component(card_id, Draggable::new())?;
+            world.add_component(card_id, Draggable::new().with_drag_children())?;
         }
     } else {
         world.remove_component::<Draggable>(card_id);
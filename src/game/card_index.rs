@@ -0,0 +1,337 @@
+use std::collections::{BTreeMap, HashMap};
+use crate::error::GameResult;
+use crate::ecs::world::World;
+use crate::ecs::entity::EntityId;
+use crate::ecs::system::{System, SystemPhase, SystemLabel};
+use crate::ecs::resources::ResourceManager;
+use crate::ecs::component::{StackContainer, CardInfo};
+use crate::constants::{LABEL_INPUT, LABEL_STACK_REGISTRY, LABEL_CARD_INDEX};
+
+/// カードの置き場所
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CardLocation {
+    pub stack: EntityId,   // カードを持っているスタック
+    pub index: usize,      // スタック内の位置（0が一番下）
+}
+
+/// カードの索引リソース
+/// カードがどのスタックの何番目にあるか、スートとランクからどのカードかをO(1)で引けるようにする
+/// スタックの変更は変更検出で拾うので、StackContainerを書き換える側は何もしなくてよい
+/// 索引の中身は最後にsyncした時点のもので、CardIndexSystemがステップごとにInputフェーズで同期する
+/// 同じステップの中でスタックを変えたあとに引くシステムは、先にsyncを呼ぶ（DragSystemがそうしている）
+/// 前回のsync以降の変更は何回あっても次のsyncで全て反映されるので、遅れが次のステップまで残ることはない
+#[derive(Default)]
+pub struct CardIndex {
+    // カード → 置き場所
+    locations: HashMap<EntityId, CardLocation>,
+    
+    // スタック → 前回索引を作ったときのカード一覧
+    stack_cards: HashMap<EntityId, Vec<EntityId>>,
+    
+    // (スート, ランク) → カード
+    cards_by_face: HashMap<(u8, u8), EntityId>,
+    
+    // 前回同期したときのティック
+    last_sync_tick: u32,
+}
+
+impl CardIndex {
+    /// 新しい索引を作成
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// カードを持っているスタックを取得
+    pub fn stack_of(&self, card: EntityId) -> Option<EntityId> {
+        self.locations.get(&card).map(|location| location.stack)
+    }
+    
+    /// カードのスタック内の位置を取得
+    pub fn index_in_stack(&self, card: EntityId) -> Option<usize> {
+        self.locations.get(&card).map(|location| location.index)
+    }
+    
+    /// カードの置き場所を取得
+    pub fn location_of(&self, card: EntityId) -> Option<CardLocation> {
+        self.locations.get(&card).copied()
+    }
+    
+    /// スートとランクからカードを取得
+    pub fn card_by(&self, suit: u8, rank: u8) -> Option<EntityId> {
+        self.cards_by_face.get(&(suit, rank)).copied()
+    }
+    
+    /// 索引に載っているカードの数
+    pub fn len(&self) -> usize {
+        self.locations.len()
+    }
+    
+    /// 索引が空かどうか
+    pub fn is_empty(&self) -> bool {
+        self.locations.is_empty()
+    }
+    
+    /// 前回の同期以降の変更を索引に反映する
+    pub fn sync(&mut self, world: &World) {
+        let since = self.last_sync_tick;
+        
+        // 1. 削除されたスタックとカードを索引から外す
+        for &stack_id in world.removed_components_since::<StackContainer>(since).iter() {
+            self.unindex_stack(stack_id);
+        }
+        for &card_id in world.removed_components_since::<CardInfo>(since).iter() {
+            self.cards_by_face.retain(|_, &mut id| id != card_id);
+        }
+        
+        // 2. 変更されたスタックの古い記録を先に全て外してから、新しい並びを登録する
+        //    （AからBへ移動したカードを、Aの後始末で消してしまわないようにするため）
+        let changed_stacks = world.get_entities_with_changed_since::<StackContainer>(since);
+        for &stack_id in &changed_stacks {
+            self.unindex_stack(stack_id);
+        }
+        for &stack_id in &changed_stacks {
+            if let Some(stack) = world.get_component::<StackContainer>(stack_id) {
+                for (index, &card_id) in stack.cards.iter().enumerate() {
+                    self.locations.insert(card_id, CardLocation { stack: stack_id, index });
+                }
+                self.stack_cards.insert(stack_id, stack.cards.clone());
+            }
+        }
+        
+        // 3. 追加されたカードのスートとランクを登録
+        for card_id in world.get_entities_with_added_since::<CardInfo>(since) {
+            if let Some(card_info) = world.get_component::<CardInfo>(card_id) {
+                self.cards_by_face.insert((card_info.suit, card_info.rank), card_id);
+            }
+        }
+        
+        // 同期中のシステムが同じティックで続けて変更しても拾えるように、1つ前のティックを記録する
+        self.last_sync_tick = world.change_tick().saturating_sub(1);
+    }
+    
+    /// スタックに載っていたカードの記録を外す
+    fn unindex_stack(&mut self, stack_id: EntityId) {
+        if let Some(cards) = self.stack_cards.remove(&stack_id) {
+            for card_id in cards {
+                if self.stack_of(card_id) == Some(stack_id) {
+                    self.locations.remove(&card_id);
+                }
+            }
+        }
+    }
+}
+
+/// カードとスタックの不変条件をチェックする
+/// - 全てのカードがちょうど1つのスタックに入っている
+/// - スタックが参照するカードは全て存在する
+/// - 索引がスタックの実際の中身と一致している
+pub fn check_card_invariants(world: &World, index: &CardIndex) -> Result<(), String> {
+    // 1. スタックを全て走査して、カードの実際の置き場所を集める
+    let mut actual: BTreeMap<EntityId, CardLocation> = BTreeMap::new();
+    let mut stack_ids = world.get_entities_with_component::<StackContainer>();
+    stack_ids.sort_unstable();
+    
+    for stack_id in stack_ids {
+        let stack = match world.get_component::<StackContainer>(stack_id) {
+            Some(stack) => stack,
+            None => continue,
+        };
+        for (i, &card_id) in stack.cards.iter().enumerate() {
+            if !world.has_component::<CardInfo>(card_id) {
+                return Err(format!("スタック {} の{}番目が存在しないカード {} を参照しています", stack_id, i, card_id));
+            }
+            let location = CardLocation { stack: stack_id, index: i };
+            if let Some(previous) = actual.insert(card_id, location) {
+                return Err(format!(
+                    "カード {} が複数の場所にあります: スタック {}[{}] とスタック {}[{}]",
+                    card_id, previous.stack, previous.index, stack_id, i
+                ));
+            }
+        }
+    }
+    
+    // 2. 全てのカードがどこかのスタックに入っているか
+    let mut card_ids = world.get_entities_with_component::<CardInfo>();
+    card_ids.sort_unstable();
+    for &card_id in &card_ids {
+        if !actual.contains_key(&card_id) {
+            return Err(format!("カード {} がどのスタックにも入っていません", card_id));
+        }
+    }
+    
+    // 3. 索引が実際の置き場所と一致しているか
+    if index.len() != actual.len() {
+        return Err(format!("索引のカード数 {} が実際のカード数 {} と一致しません", index.len(), actual.len()));
+    }
+    for (&card_id, &location) in &actual {
+        if index.location_of(card_id) != Some(location) {
+            return Err(format!(
+                "カード {} の索引 {:?} が実際の置き場所 {:?} と一致しません",
+                card_id, index.location_of(card_id), location
+            ));
+        }
+    }
+    for &card_id in &card_ids {
+        if let Some(card_info) = world.get_component::<CardInfo>(card_id) {
+            if index.card_by(card_info.suit, card_info.rank) != Some(card_id) {
+                return Err(format!("カード {} がスートとランクの索引に登録されていません", card_id));
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+/// カード索引を最新に保つシステム
+/// デバッグビルドでは毎回不変条件もチェックする
+pub struct CardIndexSystem;
+
+impl System for CardIndexSystem {
+    fn name(&self) -> &'static str {
+        "CardIndexSystem"
+    }
+    
+    fn phase(&self) -> SystemPhase {
+        SystemPhase::Input
+    }
+    
+    fn labels(&self) -> Vec<SystemLabel> {
+        vec![self.name(), LABEL_CARD_INDEX]
+    }
+    
+    fn after(&self) -> Vec<SystemLabel> {
+        vec![LABEL_STACK_REGISTRY]
+    }
+    
+    fn before(&self) -> Vec<SystemLabel> {
+        vec![LABEL_INPUT]  // 入力処理がカードの場所を引く前に更新する
+    }
+    
+    fn run(&mut self, world: &mut World, resources: &mut ResourceManager, _delta_time: f32) -> GameResult<()> {
+        if !resources.has::<CardIndex>() {
+            resources.add(CardIndex::new());
+        }
+        
+        if let Some(index) = resources.get_mut::<CardIndex>() {
+            index.sync(world);
+            
+            #[cfg(debug_assertions)]
+            if let Err(message) = check_card_invariants(world, index) {
+                log::error!("❌ カードの不変条件が崩れています: {}", message);
+            }
+        }
+        
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ecs::component::StackType;
+    use crate::game::registry::StackRegistry;
+    use crate::game::setup::{build_app, HeadlessPlugins};
+    use crate::game::solitaire::{self, check_move, move_cards};
+    
+    // シード値を指定して配り、索引を作る
+    fn dealt_world(seed: u64) -> (World, StackRegistry, CardIndex) {
+        let mut world = World::new();
+        solitaire::setup_solitaire_board_with_seed(&mut world, seed).unwrap();
+        let registry = StackRegistry::from_world(&world);
+        let mut index = CardIndex::new();
+        index.sync(&world);
+        (world, registry, index)
+    }
+    
+    // ウェイストか場札の表向きのカードを、場札のどこかへ動かせる手を探す
+    fn find_tableau_move(world: &World, registry: &StackRegistry) -> Option<(EntityId, EntityId)> {
+        let mut sources = registry.tableaus();
+        sources.extend(registry.waste());
+        for source_id in sources {
+            let stack = world.get_component::<StackContainer>(source_id)?;
+            for &card_id in &stack.cards {
+                for target_id in registry.tableaus() {
+                    if target_id != source_id && check_move(world, card_id, target_id).is_ok() {
+                        return Some((card_id, target_id));
+                    }
+                }
+            }
+        }
+        None
+    }
+    
+    #[test]
+    fn invariants_hold_after_deal() {
+        let (world, _registry, index) = dealt_world(1);
+        assert_eq!(index.len(), 52);
+        assert_eq!(check_card_invariants(&world, &index), Ok(()));
+    }
+    
+    #[test]
+    fn invariants_hold_after_draw_and_recycle() {
+        let (mut world, registry, mut index) = dealt_world(1);
+        let stock_id = registry.stock().unwrap();
+        let waste_id = registry.waste().unwrap();
+        
+        // 山札を全て引いてから、捨て札を山札に戻す
+        while world.get_component::<StackContainer>(stock_id).unwrap().card_count() > 0 {
+            solitaire::draw_from_stock(&mut world, stock_id, waste_id).unwrap();
+            index.sync(&world);
+            assert_eq!(check_card_invariants(&world, &index), Ok(()));
+        }
+        assert!(solitaire::reset_stock_from_waste(&mut world, stock_id, waste_id).unwrap());
+        index.sync(&world);
+        assert_eq!(check_card_invariants(&world, &index), Ok(()));
+        assert_eq!(index.stack_of(world.get_component::<StackContainer>(stock_id).unwrap().cards[0]), Some(stock_id));
+    }
+    
+    #[test]
+    fn invariants_hold_after_tableau_move() {
+        let (mut world, mut index, card_id, target_id) = (1..100)
+            .find_map(|seed| {
+                let (world, registry, index) = dealt_world(seed);
+                let (card_id, target_id) = find_tableau_move(&world, &registry)?;
+                Some((world, index, card_id, target_id))
+            })
+            .expect("場札へ動かせる配り方が見つかりません");
+        
+        let game_move = move_cards(&mut world, card_id, target_id).unwrap();
+        assert!(matches!(game_move.to, StackType::Tableau { .. }));
+        index.sync(&world);
+        assert_eq!(check_card_invariants(&world, &index), Ok(()));
+        assert_eq!(index.stack_of(card_id), Some(target_id));
+    }
+    
+    #[test]
+    fn stale_index_catches_up_on_next_sync() {
+        let (mut world, registry, mut index) = dealt_world(1);
+        let stock_id = registry.stock().unwrap();
+        let waste_id = registry.waste().unwrap();
+        
+        // 同期せずに何回か引くと、索引は古いまま
+        for _ in 0..3 {
+            solitaire::draw_from_stock(&mut world, stock_id, waste_id).unwrap();
+        }
+        assert!(check_card_invariants(&world, &index).is_err());
+        
+        // 次の1回の同期で全ての変更に追いつく
+        index.sync(&world);
+        assert_eq!(check_card_invariants(&world, &index), Ok(()));
+    }
+    
+    #[test]
+    fn index_is_current_after_each_step() {
+        let mut app = build_app(HeadlessPlugins).unwrap();
+        app.update(1.0 / 60.0).unwrap();
+        
+        // ステップの外で手を打っても、次のステップのあとには索引が追いついている
+        let registry = StackRegistry::from_world(app.world());
+        let stock_id = registry.stock().unwrap();
+        let waste_id = registry.waste().unwrap();
+        solitaire::draw_from_stock(app.world_mut(), stock_id, waste_id).unwrap();
+        app.update(1.0 / 60.0).unwrap();
+        
+        let index = app.resources().get::<CardIndex>().unwrap();
+        assert_eq!(check_card_invariants(app.world(), &index), Ok(()));
+    }
+}
@@ -0,0 +1,79 @@
+use wasm_bindgen::prelude::*;
+use crate::constants::{CANVAS_WIDTH, CANVAS_HEIGHT};
+
+/// ゲームごとの設定
+/// 1つのページに複数のゲーム（対戦相手の小さな盤面やリプレイなど）を並べるときに、ゲームごとに指定する
+/// JavaScriptからは`new GameConfig().with_size(400, 300).with_interactive(false)`のように組み立てる
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GameConfig {
+    pub width: u32,         // キャンバスの幅（ピクセル）
+    pub height: u32,        // キャンバスの高さ（ピクセル）
+    pub interactive: bool,  // マウスとキーボードの入力を受け付けるか
+    pub activate: bool,     // lib.rsのトップレベル関数（new_gameなど）の操作対象にするか
+}
+
+#[wasm_bindgen]
+impl GameConfig {
+    /// 既定の設定（800x600、入力あり、操作対象にする）を作成
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self {
+            width: CANVAS_WIDTH,
+            height: CANVAS_HEIGHT,
+            interactive: true,
+            activate: true,
+        }
+    }
+    
+    /// キャンバスの大きさを指定
+    /// 盤面は縦横比を保ったまま、キャンバスに収まるように拡大・縮小して描画する
+    pub fn with_size(mut self, width: u32, height: u32) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+    
+    /// 入力を受け付けるかを指定（観戦用の盤面やリプレイではfalse）
+    pub fn with_interactive(mut self, interactive: bool) -> Self {
+        self.interactive = interactive;
+        self
+    }
+    
+    /// lib.rsのトップレベル関数の操作対象にするかを指定
+    pub fn with_activate(mut self, activate: bool) -> Self {
+        self.activate = activate;
+        self
+    }
+}
+
+impl GameConfig {
+    /// 盤面（CANVAS_WIDTH x CANVAS_HEIGHT）をキャンバスに描画するときの倍率
+    pub fn scale(&self) -> f64 {
+        let scale_x = self.width as f64 / CANVAS_WIDTH as f64;
+        let scale_y = self.height as f64 / CANVAS_HEIGHT as f64;
+        scale_x.min(scale_y)
+    }
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn scale_fits_the_board_inside_the_canvas() {
+        assert_eq!(GameConfig::new().scale(), 1.0);
+        assert_eq!(GameConfig::new().with_size(CANVAS_WIDTH / 2, CANVAS_HEIGHT / 2).scale(), 0.5);
+        assert_eq!(GameConfig::new().with_size(CANVAS_WIDTH * 2, CANVAS_HEIGHT * 2).scale(), 2.0);
+        
+        // 縦横比が違う場合は、はみ出さないように小さい方の倍率に合わせる
+        assert_eq!(GameConfig::new().with_size(CANVAS_WIDTH, CANVAS_HEIGHT / 4).scale(), 0.25);
+        assert_eq!(GameConfig::new().with_size(CANVAS_WIDTH / 4, CANVAS_HEIGHT * 3).scale(), 0.25);
+    }
+}
@@ -0,0 +1,259 @@
+use serde::Serialize;
+use crate::error::GameResult;
+use crate::ecs::world::World;
+use crate::ecs::system::{System, SystemPhase, SystemLabel};
+use crate::ecs::resources::{ResourceManager, GameState};
+use crate::ecs::component::StackType;
+use crate::ecs::event::Events;
+use crate::ecs::error_log::ErrorLog;
+use crate::ecs::snapshot::WorldSnapshot;
+use crate::game::solitaire::GameMove;
+use crate::game::score::Score;
+use crate::game::history::MoveHistory;
+use crate::constants::{LABEL_GAME_RULES, LABEL_GAME_EVENTS};
+use log::debug;
+
+/// ゲームのイベント
+/// ECSの中で起きたことをページに通知するためにEvents<GameEvent>に送る
+/// JavaScriptには"type"をキーにしたオブジェクトとして渡す
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum GameEvent {
+    /// カードを動かした
+    Move {
+        from: String,     // 移動元のスタック（"waste"、"tableau:3"など）
+        to: String,       // 移動先のスタック
+        cards: usize,     // 動かしたカードの枚数
+        revealed: bool,   // タブローの裏向きのカードを表にしたかどうか
+        moves: u32,       // この手を含めた手数
+    },
+    /// ゲーム状態が変わった
+    StateChange {
+        from: Option<GameState>,
+        to: GameState,
+    },
+    /// 得点が変わった（元に戻す・やり直すでも送る）
+    Score {
+        points: i32,
+        moves: u32,
+        delta: i32,
+    },
+    /// ゲームをクリアした
+    Clear {
+        points: i32,
+        moves: u32,
+    },
+    /// システムがエラーを返した
+    Error {
+        system: &'static str,
+        message: String,
+        frame: u64,
+        disabled: bool,
+    },
+}
+
+/// スタックの種類をイベント用の名前にする
+pub fn stack_name(stack_type: StackType) -> String {
+    match stack_type {
+        StackType::Stock => "stock".to_string(),
+        StackType::Waste => "waste".to_string(),
+        StackType::Tableau { column } => format!("tableau:{}", column),
+        StackType::Foundation { suit } => format!("foundation:{}", suit),
+        StackType::Hand => "hand".to_string(),
+    }
+}
+
+/// 1手を記録する
+/// 元に戻せるように指す直前の盤面を履歴に積み、得点を数えて、MoveとScoreのイベントを送る
+/// システムの中からも呼べるように、リソースは借用ガードを通して書き換える
+pub fn record_move(resources: &ResourceManager, before: WorldSnapshot, game_move: GameMove) {
+    // 1. 得点を数える（得点のリソースがなければ、この手だけで数える）
+    let (score_before, score, delta) = match resources.try_borrow_mut::<Score>() {
+        Ok(mut score) => {
+            let score_before = *score;
+            let delta = score.apply(&game_move);
+            (score_before, *score, delta)
+        },
+        Err(e) => {
+            debug!("得点を記録できません: {}", e);
+            let mut score = Score::new();
+            let delta = score.apply(&game_move);
+            (Score::new(), score, delta)
+        },
+    };
+    
+    // 2. 指す直前の盤面と得点を履歴に積む
+    if let Ok(mut history) = resources.try_borrow_mut::<MoveHistory>() {
+        history.record(before, score_before);
+    }
+    
+    // 3. イベントを送る
+    if let Ok(mut events) = resources.try_borrow_mut::<Events<GameEvent>>() {
+        events.send(GameEvent::Move {
+            from: stack_name(game_move.from),
+            to: stack_name(game_move.to),
+            cards: game_move.cards,
+            revealed: game_move.revealed,
+            moves: score.moves,
+        });
+        if delta != 0 {
+            events.send(GameEvent::Score {
+                points: score.points,
+                moves: score.moves,
+                delta,
+            });
+        }
+    }
+}
+
+/// 手として数えない盤面の変更（カードをめくるなど）を、元に戻せるように記録する
+pub fn record_change(resources: &ResourceManager, before: WorldSnapshot) {
+    let score = resources.try_borrow::<Score>().map(|score| *score).unwrap_or_default();
+    if let Ok(mut history) = resources.try_borrow_mut::<MoveHistory>() {
+        history.record(before, score);
+    }
+}
+
+/// ゲーム状態の変化とシステムのエラーを見張り、イベントを送るシステム
+/// 手のイベントは手を指した場所で送るので、ここでは扱わない
+/// PostUpdateで実行するので、Renderフェーズのシステムのエラーは次に固定ステップが実行されるフレームで通知される
+pub struct GameEventSystem {
+    // 前回見たゲーム状態
+    last_state: Option<GameState>,
+    
+    // 前回見たときまでにエラーログに記録されたエラーの総数
+    last_error_total: u64,
+}
+
+impl GameEventSystem {
+    /// 新しいイベントシステムを作成
+    pub fn new() -> Self {
+        Self {
+            last_state: None,
+            last_error_total: 0,
+        }
+    }
+}
+
+impl Default for GameEventSystem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl System for GameEventSystem {
+    fn name(&self) -> &'static str {
+        "GameEventSystem"
+    }
+    
+    fn phase(&self) -> SystemPhase {
+        SystemPhase::PostUpdate
+    }
+    
+    fn labels(&self) -> Vec<SystemLabel> {
+        vec![self.name(), LABEL_GAME_EVENTS]
+    }
+    
+    fn after(&self) -> Vec<SystemLabel> {
+        vec![LABEL_GAME_RULES]  // クリア判定の結果を同じフレームで通知する
+    }
+    
+    fn run(&mut self, _world: &mut World, resources: &mut ResourceManager, _delta_time: f32) -> GameResult<()> {
+        let mut new_events = Vec::new();
+        
+        // 1. ゲーム状態の変化
+        if let Some(state) = resources.get::<GameState>().map(|state| *state) {
+            if self.last_state != Some(state) {
+                new_events.push(GameEvent::StateChange { from: self.last_state, to: state });
+                if state == GameState::Clear {
+                    let score = resources.get::<Score>().map(|score| *score).unwrap_or_default();
+                    new_events.push(GameEvent::Clear { points: score.points, moves: score.moves });
+                }
+                self.last_state = Some(state);
+            }
+        }
+        
+        // 2. 前回から増えたシステムのエラー（ログから溢れた分は通知しない）
+        if let Some(error_log) = resources.get::<ErrorLog>() {
+            let new_count = error_log.total.saturating_sub(self.last_error_total) as usize;
+            let skip = error_log.entries.len().saturating_sub(new_count);
+            for entry in error_log.entries.iter().skip(skip) {
+                new_events.push(GameEvent::Error {
+                    system: entry.system,
+                    message: entry.message.clone(),
+                    frame: entry.frame,
+                    disabled: entry.disabled,
+                });
+            }
+            self.last_error_total = error_log.total;
+        }
+        
+        // 3. イベントを送る
+        if let Some(events) = resources.get_mut::<Events<GameEvent>>() {
+            for event in new_events {
+                events.send(event);
+            }
+        }
+        
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::{SCORE_TO_FOUNDATION, SCORE_REVEAL_CARD};
+    
+    fn resources_for_events() -> ResourceManager {
+        let mut resources = ResourceManager::new();
+        resources.add(Score::new());
+        resources.add(MoveHistory::new());
+        resources.add(Events::<GameEvent>::new());
+        resources
+    }
+    
+    fn drain_events(resources: &mut ResourceManager) -> Vec<GameEvent> {
+        resources.get_mut::<Events<GameEvent>>().unwrap().drain()
+    }
+    
+    #[test]
+    fn record_move_sends_move_and_score_events() {
+        let world = World::new();
+        let mut resources = resources_for_events();
+        let game_move = GameMove {
+            from: StackType::Tableau { column: 2 },
+            to: StackType::Foundation { suit: 0 },
+            cards: 1,
+            revealed: true,
+        };
+        
+        record_move(&resources, world.snapshot(), game_move);
+        
+        let points = SCORE_TO_FOUNDATION + SCORE_REVEAL_CARD;
+        assert_eq!(drain_events(&mut resources), vec![
+            GameEvent::Move { from: "tableau:2".to_string(), to: "foundation:0".to_string(), cards: 1, revealed: true, moves: 1 },
+            GameEvent::Score { points, moves: 1, delta: points },
+        ]);
+        assert_eq!(resources.get::<MoveHistory>().map(|history| history.len()), Some(1));
+    }
+    
+    #[test]
+    fn state_changes_are_sent_once() {
+        let mut world = World::new();
+        let mut resources = resources_for_events();
+        resources.add(GameState::Playing);
+        let mut system = GameEventSystem::new();
+        
+        system.run(&mut world, &mut resources, 1.0 / 60.0).unwrap();
+        system.run(&mut world, &mut resources, 1.0 / 60.0).unwrap();
+        assert_eq!(drain_events(&mut resources), vec![GameEvent::StateChange { from: None, to: GameState::Playing }]);
+        
+        // クリアしたら状態の変化に続けてクリアのイベントを送る
+        *resources.get_mut::<GameState>().unwrap() = GameState::Clear;
+        system.run(&mut world, &mut resources, 1.0 / 60.0).unwrap();
+        assert_eq!(drain_events(&mut resources), vec![
+            GameEvent::StateChange { from: Some(GameState::Playing), to: GameState::Clear },
+            GameEvent::Clear { points: 0, moves: 0 },
+        ]);
+    }
+}
@@ -0,0 +1,126 @@
+use wasm_bindgen::prelude::*;
+use std::cell::{Cell, RefCell};
+use std::rc::{Rc, Weak};
+use log::error;
+
+// ゲームのメインループを処理するクロージャの型
+type GameLoopCallback = Closure<dyn FnMut(f64)>;
+
+/// requestAnimationFrameで毎フレーム処理を呼び出すループ
+/// ゲームごとに1つ持つので、1つのページで複数のゲームがそれぞれのループを回せる
+/// 停止するとリクエスト済みのフレームも取り消すので、停止後に処理が呼ばれることはない
+pub struct GameLoop {
+    // フレームごとに呼ばれるクロージャ（クロージャ自身は弱参照で持つので循環参照にならない）
+    callback: Rc<RefCell<Option<GameLoopCallback>>>,
+    
+    // ループが動いているか
+    running: Rc<Cell<bool>>,
+    
+    // リクエスト済みのアニメーションフレームのID
+    frame_id: Rc<Cell<Option<i32>>>,
+}
+
+impl GameLoop {
+    /// 停止した状態で作成
+    pub fn new() -> Self {
+        Self {
+            callback: Rc::new(RefCell::new(None)),
+            running: Rc::new(Cell::new(false)),
+            frame_id: Rc::new(Cell::new(None)),
+        }
+    }
+    
+    /// ループが動いているか
+    pub fn is_running(&self) -> bool {
+        self.running.get()
+    }
+    
+    /// ループを開始し、毎フレームframeを呼び出す（既に動いていれば何もしない）
+    /// 一度作ったクロージャは停止しても捨てないので、再開するときは最初に渡したframeを使い続ける
+    pub fn start<F>(&self, frame: F) -> Result<(), JsValue>
+    where
+        F: FnMut(f64) + 'static,
+    {
+        if self.running.get() {
+            return Ok(());
+        }
+        
+        // 1. 初めて開始するときだけクロージャを作る
+        if self.callback.borrow().is_none() {
+            let closure = self.create_callback(frame);
+            *self.callback.borrow_mut() = Some(closure);
+        }
+        
+        // 2. 最初のフレームをリクエスト
+        self.running.set(true);
+        Self::request_frame(&self.callback, &self.frame_id)
+    }
+    
+    /// ループを停止し、リクエスト済みのフレームを取り消す
+    /// フレームの処理中（コールバックの中など）から呼んでも安全なように、クロージャ自体は解放しない
+    pub fn stop(&self) {
+        self.running.set(false);
+        if let Some(frame_id) = self.frame_id.take() {
+            if let Some(window) = web_sys::window() {
+                if let Err(e) = window.cancel_animation_frame(frame_id) {
+                    error!("アニメーションフレームを取り消せませんでした: {:?}", e);
+                }
+            }
+        }
+    }
+    
+    /// フレームごとに呼ばれるクロージャを作る
+    fn create_callback<F>(&self, mut frame: F) -> GameLoopCallback
+    where
+        F: FnMut(f64) + 'static,
+    {
+        let callback: Weak<RefCell<Option<GameLoopCallback>>> = Rc::downgrade(&self.callback);
+        let running = Rc::clone(&self.running);
+        let frame_id = Rc::clone(&self.frame_id);
+        
+        Closure::wrap(Box::new(move |timestamp: f64| {
+            // このフレームのリクエストは消化した
+            frame_id.set(None);
+            if !running.get() {
+                return;
+            }
+            
+            frame(timestamp);
+            
+            // フレームの処理中に停止されたか、停止してすぐ再開されて次のフレームがリクエスト済みなら何もしない
+            if !running.get() || frame_id.get().is_some() {
+                return;
+            }
+            
+            // 次のフレームをリクエスト（GameLoopが破棄されていれば終わる）
+            if let Some(callback) = callback.upgrade() {
+                if let Err(e) = Self::request_frame(&callback, &frame_id) {
+                    error!("次のフレームをリクエストできませんでした: {:?}", e);
+                    running.set(false);
+                }
+            }
+        }) as Box<dyn FnMut(f64)>)
+    }
+    
+    /// アニメーションフレームをリクエストしてIDを覚えておく
+    fn request_frame(callback: &RefCell<Option<GameLoopCallback>>, frame_id: &Cell<Option<i32>>) -> Result<(), JsValue> {
+        let window = web_sys::window().ok_or_else(|| JsValue::from_str("ウィンドウが見つかりません"))?;
+        if let Some(ref closure) = *callback.borrow() {
+            frame_id.set(Some(window.request_animation_frame(closure.as_ref().unchecked_ref())?));
+        }
+        Ok(())
+    }
+}
+
+impl Default for GameLoop {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// 破棄したあとにフレームが呼ばれないように、リクエスト済みのフレームを取り消す
+impl Drop for GameLoop {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
@@ -0,0 +1,256 @@
+use std::collections::VecDeque;
+use crate::error::{GameError, GameResult};
+use crate::ecs::app::App;
+use crate::ecs::world::World;
+use crate::ecs::resources::{ResourceManager, GameState};
+use crate::ecs::event::Events;
+use crate::ecs::snapshot::WorldSnapshot;
+use crate::game::score::Score;
+use crate::game::events::GameEvent;
+use crate::game::state::{self, ClearModal};
+use crate::constants::MOVE_HISTORY_CAPACITY;
+use log::{info, debug};
+
+/// 履歴の1件
+/// 盤面と、そのときの得点・手数
+#[derive(Clone)]
+pub struct HistoryEntry {
+    pub world: WorldSnapshot,
+    pub score: Score,
+}
+
+/// 手の履歴リソース
+/// 盤面を変える操作の直前のスナップショットを積んでおき、元に戻す・やり直すに使う
+#[derive(Default)]
+pub struct MoveHistory {
+    undo_stack: VecDeque<HistoryEntry>,  // 元に戻す先（古い順）
+    redo_stack: Vec<HistoryEntry>,       // やり直す先（新しい順に取り出す）
+}
+
+impl MoveHistory {
+    /// 空の履歴を作成
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// 手を指した後に、指す直前の盤面と得点を記録する
+    /// 新しい手を指したので、やり直しの履歴は捨てる
+    pub fn record(&mut self, world: WorldSnapshot, score: Score) {
+        self.undo_stack.push_back(HistoryEntry { world, score });
+        if self.undo_stack.len() > MOVE_HISTORY_CAPACITY {
+            self.undo_stack.pop_front();
+        }
+        self.redo_stack.clear();
+        debug!("📝 手を記録しました（元に戻せる手: {}）", self.undo_stack.len());
+    }
+    
+    /// 元に戻せるかどうか
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+    
+    /// やり直せるかどうか
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+    
+    /// 記録した手の数
+    pub fn len(&self) -> usize {
+        self.undo_stack.len()
+    }
+    
+    /// 記録した手がないかどうか
+    pub fn is_empty(&self) -> bool {
+        self.undo_stack.is_empty()
+    }
+    
+    /// 履歴を消去
+    pub fn clear(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+    }
+}
+
+/// 盤面のスナップショットを取る
+/// クリアモーダルは盤面ではなく、復元したあとにゲーム状態に合わせて出し直すので含めない
+pub fn board_snapshot(world: &World, resources: &ResourceManager) -> WorldSnapshot {
+    let mut snapshot = world.snapshot();
+    if let Some(modal) = resources.get::<ClearModal>() {
+        snapshot.entities.retain(|entity| !modal.entities.contains(&entity.id));
+    }
+    snapshot
+}
+
+/// 盤面をスナップショットの状態にする
+/// ワールドを作り直すので、古いエンティティIDを覚えているシステムとクリアモーダルの状態も初期化する
+/// ゲーム状態は盤面に合わせて決め直し、クリアモーダルは次のステップの状態遷移で必要なら出し直す
+pub fn restore_state(app: &mut App, snapshot: &WorldSnapshot) -> GameResult<()> {
+    app.world_mut().restore(snapshot)?;
+    app.system_manager_mut().reset_systems();
+    if let Some(modal) = app.resources_mut().get_mut::<ClearModal>() {
+        modal.entities.clear();
+    }
+    
+    if let Some(current) = app.resources().get::<GameState>().map(|state| *state) {
+        let new_state = state::game_state_for_board(app.world(), current);
+        state::change_game_state(app.resources_mut(), new_state);
+    }
+    Ok(())
+}
+
+/// 今の盤面と得点を履歴の1件にする
+fn current_entry(app: &App) -> HistoryEntry {
+    HistoryEntry {
+        world: board_snapshot(app.world(), app.resources()),
+        score: app.resources().get::<Score>().map(|score| *score).unwrap_or_default(),
+    }
+}
+
+/// 履歴の1件の状態に戻し、得点が変わったことを通知する
+fn apply_entry(app: &mut App, entry: HistoryEntry) -> GameResult<()> {
+    restore_state(app, &entry.world)?;
+    app.insert_resource(entry.score);
+    if let Some(events) = app.resources_mut().get_mut::<Events<GameEvent>>() {
+        events.send(GameEvent::Score {
+            points: entry.score.points,
+            moves: entry.score.moves,
+            delta: 0,
+        });
+    }
+    Ok(())
+}
+
+/// 1手元に戻す
+/// 戻せる手がなければfalseを返す
+pub fn undo_move(app: &mut App) -> GameResult<bool> {
+    // 1. 戻す先を取り出し、今の状態をやり直し用に積む
+    let current = current_entry(app);
+    let entry = {
+        let history = app.resources_mut().get_mut::<MoveHistory>()
+            .ok_or(GameError::ResourceMissing("MoveHistory"))?;
+        match history.undo_stack.pop_back() {
+            Some(entry) => {
+                history.redo_stack.push(current);
+                entry
+            },
+            None => return Ok(false),
+        }
+    };
+    
+    // 2. 盤面と得点を戻す
+    apply_entry(app, entry)?;
+    info!("↩️ 1手元に戻しました");
+    Ok(true)
+}
+
+/// 元に戻した手をやり直す
+/// やり直せる手がなければfalseを返す
+pub fn redo_move(app: &mut App) -> GameResult<bool> {
+    // 1. やり直す先を取り出し、今の状態を元に戻す用に積む
+    let current = current_entry(app);
+    let entry = {
+        let history = app.resources_mut().get_mut::<MoveHistory>()
+            .ok_or(GameError::ResourceMissing("MoveHistory"))?;
+        match history.redo_stack.pop() {
+            Some(entry) => {
+                history.undo_stack.push_back(current);
+                entry
+            },
+            None => return Ok(false),
+        }
+    };
+    
+    // 2. 盤面と得点を進める
+    apply_entry(app, entry)?;
+    info!("↪️ 1手やり直しました");
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ecs::entity::EntityId;
+    use crate::ecs::component::{CardInfo, StackContainer, StackType};
+    use crate::game::setup::{build_app, HeadlessPlugins};
+    
+    const DT: f32 = 1.0 / 60.0;
+    
+    fn game_state(app: &App) -> GameState {
+        app.resources().get::<GameState>().map(|state| *state).unwrap()
+    }
+    
+    fn modal_entities(app: &App) -> Vec<EntityId> {
+        app.resources().get::<ClearModal>().map(|modal| modal.entities.clone()).unwrap_or_default()
+    }
+    
+    // 全てのカードを組み札に並べ、最後の1手としてその直前の盤面を履歴に積む
+    fn clear_board(app: &mut App) {
+        let before = board_snapshot(app.world(), app.resources());
+        
+        let world = app.world_mut();
+        let mut cards: Vec<(EntityId, CardInfo)> = world.get_entities_with_component::<CardInfo>()
+            .into_iter()
+            .filter_map(|card_id| world.get_component::<CardInfo>(card_id).map(|card| (card_id, card.clone())))
+            .collect();
+        cards.sort_by_key(|(_, card)| card.rank);
+        for stack_id in world.get_entities_with_component::<StackContainer>() {
+            let stack = world.get_component_mut::<StackContainer>(stack_id).unwrap();
+            stack.cards = match stack.stack_type {
+                StackType::Foundation { suit } => cards.iter()
+                    .filter(|(_, card)| card.suit as usize == suit)
+                    .map(|&(card_id, _)| card_id)
+                    .collect(),
+                _ => Vec::new(),
+            };
+        }
+        
+        let score = app.resources().get::<Score>().map(|score| *score).unwrap_or_default();
+        app.resources_mut().get_mut::<MoveHistory>().unwrap().record(before, score);
+    }
+    
+    #[test]
+    fn undo_and_redo_follow_the_clear_state() {
+        let mut app = build_app(HeadlessPlugins).unwrap();
+        app.update(DT).unwrap();
+        assert_eq!(game_state(&app), GameState::Playing);
+        
+        // 1. クリアするとモーダルが出る
+        clear_board(&mut app);
+        app.update(DT).unwrap();
+        app.update(DT).unwrap();
+        assert_eq!(game_state(&app), GameState::Clear);
+        assert_eq!(modal_entities(&app).len(), 2);
+        
+        // 2. 元に戻すとプレイ中に戻り、モーダルは盤面と一緒に消える
+        assert!(undo_move(&mut app).unwrap());
+        assert_eq!(game_state(&app), GameState::Playing);
+        app.update(DT).unwrap();
+        assert_eq!(game_state(&app), GameState::Playing);
+        assert!(modal_entities(&app).is_empty());
+        let entity_count = app.world().get_all_entities().len();
+        
+        // 3. やり直すとクリアに戻り、モーダルは1つだけ出し直される
+        assert!(redo_move(&mut app).unwrap());
+        assert_eq!(game_state(&app), GameState::Clear);
+        app.update(DT).unwrap();
+        let modal = modal_entities(&app);
+        assert_eq!(modal.len(), 2);
+        assert!(modal.iter().all(|&entity_id| app.world().entity_exists(entity_id)));
+        assert_eq!(app.world().get_all_entities().len(), entity_count + 2);
+    }
+    
+    #[test]
+    fn board_snapshot_leaves_out_the_clear_modal() {
+        let mut app = build_app(HeadlessPlugins).unwrap();
+        app.update(DT).unwrap();
+        clear_board(&mut app);
+        app.update(DT).unwrap();
+        app.update(DT).unwrap();
+        
+        let modal = modal_entities(&app);
+        let snapshot = board_snapshot(app.world(), app.resources());
+        assert!(!modal.is_empty());
+        assert!(snapshot.entities.iter().all(|entity| !modal.contains(&entity.id)));
+        assert_eq!(snapshot.entities.len() + modal.len(), app.world().get_all_entities().len());
+    }
+}
@@ -8,41 +8,79 @@ pub mod card;        // カード関連
 pub mod solitaire;   // ソリティアゲームのルール
 pub mod setup;       // ゲーム初期化
 pub mod state;       // ゲーム状態管理
+pub mod registry;    // スタックの登録簿
+pub mod card_index;  // カードの置き場所の索引
+pub mod history;     // 元に戻す・やり直すための手の履歴
+pub mod score;       // 得点と手数
+pub mod events;      // ページに通知するゲームのイベント
+pub mod status;      // ページが読み取るゲームの状況のまとめ
+#[cfg(feature = "web")]
+pub mod callbacks;   // ページが登録したイベントのコールバック
+#[cfg(feature = "web")]
+pub mod config;      // ゲームごとの設定（キャンバスの大きさ・入力の有無）
+#[cfg(feature = "web")]
+pub mod game_loop;   // ゲームごとのrequestAnimationFrameのループ
 
-// 他のモジュールからのインポート
+// 他のモジュールからのインポート（Game構造体はブラウザ上でだけ使うのでweb機能が有効なときだけ）
+#[cfg(feature = "web")]
 use wasm_bindgen::prelude::*;
+#[cfg(feature = "web")]
 use std::cell::RefCell;
-use std::rc::Rc;
+#[cfg(feature = "web")]
+use std::rc::{Rc, Weak};
+#[cfg(feature = "web")]
 use web_sys::{HtmlCanvasElement, CanvasRenderingContext2d};
-use crate::ecs::world::World;
-use crate::ecs::system::SystemManager;
-use crate::ecs::resources::ResourceManager;
+#[cfg(feature = "web")]
+use crate::error::GameError;
+#[cfg(feature = "web")]
+use crate::ecs::app::App;
+#[cfg(feature = "web")]
+use crate::ecs::profiler::SystemProfiler;
+#[cfg(feature = "web")]
+use crate::ecs::error_log::ErrorLog;
+#[cfg(feature = "web")]
+use crate::ecs::snapshot::WorldSnapshot;
+#[cfg(feature = "web")]
+use crate::ecs::resources::InputState;
+#[cfg(feature = "web")]
+use crate::ecs::event::Events;
+#[cfg(feature = "web")]
+use crate::game::events::GameEvent;
+#[cfg(feature = "web")]
+use crate::game::callbacks::{EventCallbacks, dispatch_events};
+#[cfg(feature = "web")]
+use crate::game::status::{game_status, stack_summaries};
+#[cfg(feature = "web")]
+use crate::game::config::GameConfig;
+#[cfg(feature = "web")]
+use crate::game::game_loop::GameLoop;
+#[cfg(feature = "web")]
+use crate::ecs::reflect::TypeRegistry;
+#[cfg(feature = "web")]
+use crate::ecs::system::{System, SystemPlacement};
+#[cfg(feature = "web")]
+use crate::ecs::system_registry::SystemRegistry;
+#[cfg(feature = "web")]
 use crate::render::renderer::Renderer;
+#[cfg(feature = "web")]
 use crate::input::input_handler::InputHandler;
-use crate::game::setup::setup_game;
-use log::{info, error};
-
-// ゲームのメインループを処理するクロージャの型
-type GameLoopCallback = Closure<dyn FnMut(f64)>;
+#[cfg(feature = "web")]
+use crate::game::solitaire::DealSeed;
+#[cfg(feature = "web")]
+use crate::game::setup::{build_app, DefaultPlugins, NewGameMode};
+#[cfg(feature = "web")]
+use crate::utils::{now_ms, to_js_value};
+#[cfg(feature = "web")]
+use log::{info, warn, error};
 
 /// ゲームを管理する構造体
 /// WebAssemblyからJavaScriptへエクスポートされる
+/// アプリケーション・描画・入力・ループはゲームごとに持つので、1つのページに複数のゲームを並べられる
+#[cfg(feature = "web")]
 #[wasm_bindgen]
 pub struct Game {
-    // キャンバス要素
-    canvas: HtmlCanvasElement,
-    
-    // 描画コンテキスト
-    context: CanvasRenderingContext2d,
-    
-    // ECSのワールド
-    world: Rc<RefCell<World>>,
-    
-    // システムマネージャー
-    system_manager: Rc<RefCell<SystemManager>>,
-    
-    // リソースマネージャー
-    resource_manager: Rc<RefCell<ResourceManager>>,
+    // ワールド・システム・リソースを所有するアプリケーション
+    app: Rc<RefCell<App>>,
     
     // レンダラー
     renderer: Renderer,
@@ -50,19 +88,27 @@ pub struct Game {
     // 入力ハンドラー
     input_handler: InputHandler,
     
-    // ゲームループコールバック
-    // Closureをドロップするとコールバックが停止するため、保持しておく
-    _game_loop: Option<GameLoopCallback>,
+    // ページが登録したイベントのコールバック
+    callbacks: Rc<RefCell<EventCallbacks>>,
     
-    // ゲームが実行中かどうか
-    is_running: bool,
+    // ゲームループ
+    game_loop: GameLoop,
+    
+    // ゲームごとの設定
+    config: GameConfig,
 }
 
+#[cfg(feature = "web")]
 #[wasm_bindgen]
 impl Game {
-    /// 新しいゲームを作成
+    /// 新しいゲームを既定の設定で作成
     pub fn new(canvas_id: &str) -> Result<Game, JsValue> {
-        info!("🎮 新しいゲームを作成中... canvas_id: {}", canvas_id);
+        Self::with_config(canvas_id, GameConfig::new())
+    }
+    
+    /// 新しいゲームを設定を指定して作成
+    pub fn with_config(canvas_id: &str, config: GameConfig) -> Result<Game, JsValue> {
+        info!("🎮 新しいゲームを作成中... canvas_id: {}, 設定: {:?}", canvas_id, config);
         
         // DOMからキャンバス要素を取得
         let window = web_sys::window()
@@ -100,9 +146,9 @@ impl Game {
             
         info!("キャンバス要素を取得しました: {}x{}", canvas.width(), canvas.height());
         
-        // キャンバスサイズを設定
-        canvas.set_width(crate::constants::CANVAS_WIDTH);
-        canvas.set_height(crate::constants::CANVAS_HEIGHT);
+        // キャンバスサイズを設定（盤面は倍率をかけて描画する）
+        canvas.set_width(config.width);
+        canvas.set_height(config.height);
         
         // 2Dコンテキストを取得
         let context = canvas
@@ -119,145 +165,419 @@ impl Game {
                 JsValue::from_str(err_msg)
             })?;
             
-        // ECSコンポーネントを初期化
-        let world = Rc::new(RefCell::new(World::new()));
-        let system_manager = Rc::new(RefCell::new(SystemManager::new()));
-        let resource_manager = Rc::new(RefCell::new(ResourceManager::new()));
+        // プラグインからアプリケーションを組み立ててゲームを初期化
+        let app = Rc::new(RefCell::new(build_app(DefaultPlugins)?));
         
-        // レンダラーと入力ハンドラーを初期化
-        let renderer = Renderer::new(canvas.clone(), context.clone());
-        let input_handler = InputHandler::new(canvas.clone(), Rc::clone(&world), Rc::clone(&resource_manager))?;
+        // lib.rsのトップレベル関数から操作できるように、操作対象のゲームとして覚えておく
+        if config.activate {
+            set_active_app(&app);
+        }
         
-        // ゲームを初期化
-        setup_game(
-            &mut world.borrow_mut(),
-            &mut system_manager.borrow_mut(),
-            &mut resource_manager.borrow_mut(),
-        )?;
+        // レンダラーと入力ハンドラーを初期化
+        let scale = config.scale();
+        let renderer = Renderer::new(canvas.clone(), context).with_scale(scale);
+        let input_handler = InputHandler::new(canvas, Rc::clone(&app))?.with_scale(scale);
         
         info!("✨ ゲームの初期化が完了しました！");
         
         Ok(Game {
-            canvas,
-            context,
-            world,
-            system_manager,
-            resource_manager,
+            app,
             renderer,
             input_handler,
-            _game_loop: None,
-            is_running: false,
+            callbacks: Rc::new(RefCell::new(EventCallbacks::new())),
+            game_loop: GameLoop::new(),
+            config,
         })
     }
     
     /// ゲームを開始
+    /// 停止中のゲームは同じループで再開する
     pub fn start(&mut self) -> Result<(), JsValue> {
-        if self.is_running {
+        if self.game_loop.is_running() {
             return Ok(());  // 既に実行中の場合は何もしない
         }
         
         info!("🚀 ゲームを開始します！");
-        self.is_running = true;
-        
-        // JavaScriptのウィンドウオブジェクトを取得
-        let window = web_sys::window().ok_or_else(|| JsValue::from_str("ウィンドウが見つかりません"))?;
         
         // ゲームループのクロージャを作成
-        let world_clone = Rc::clone(&self.world);
-        let system_manager_clone = Rc::clone(&self.system_manager);
-        let resource_manager_clone = Rc::clone(&self.resource_manager);
+        let app_clone = Rc::clone(&self.app);
         let renderer_clone = self.renderer.clone();
+        let callbacks_clone = Rc::clone(&self.callbacks);
         
-        // レンダリングコールバックを作成
-        let f = Rc::new(RefCell::new(None::<Closure<dyn FnMut(f64)>>));
-        let g = Rc::clone(&f);
-        
-        // ゲームループのクロージャを定義
-        *g.borrow_mut() = Some(Closure::wrap(Box::new(move |timestamp: f64| {
+        self.game_loop.start(move |timestamp: f64| {
             // ゲームの更新とレンダリングを行う
-            let mut world = world_clone.borrow_mut();
-            let mut system_manager = system_manager_clone.borrow_mut();
-            let mut resource_manager = resource_manager_clone.borrow_mut();
+            // ページのコードがこのゲームを操作している最中なら、このフレームは飛ばす
+            let mut app = match app_clone.try_borrow_mut() {
+                Ok(app) => app,
+                Err(_) => {
+                    warn!("⚠️ ゲームを操作中のため、このフレームの更新を飛ばしました");
+                    return;
+                },
+            };
             
             // 時間情報を更新
-            if let Some(time_info) = resource_manager.get_mut::<crate::ecs::resources::TimeInfo>() {
-                time_info.update(timestamp);
-                let delta_time = time_info.delta_time;
-                
-                // システムを実行（ゲームの更新）
-                if let Err(e) = world.run_systems(&mut system_manager, &mut resource_manager, delta_time) {
-                    error!("システムの実行中にエラーが発生しました: {:?}", e);
+            let delta_time = app.resources_mut()
+                .get_mut::<crate::ecs::resources::TimeInfo>()
+                .map(|time_info| {
+                    time_info.update(timestamp);
+                    time_info.delta_time
+                });
+            
+            // システムを実行（ゲームの更新）
+            // エラーポリシーがHaltのシステムが失敗したときだけエラーが返り、このフレームの残りのシステムは実行されない
+            if let Some(delta_time) = delta_time {
+                if let Err(e) = app.update(delta_time) {
+                    error!("システムの実行を中断しました: {}", e);
                 }
             }
             
-            // レンダリング
-            if let Err(e) = renderer_clone.render(&world, &resource_manager) {
+            // レンダリング（実行時間をプロファイラーに記録）
+            let render_start = now_ms();
+            if let Err(e) = renderer_clone.render(app.world(), app.resources()) {
                 error!("レンダリング中にエラーが発生しました: {:?}", e);
             }
-            
-            // 次のフレームをリクエスト
-            if let Some(ref callback) = *f.borrow() {
-                window.request_animation_frame(callback.as_ref().unchecked_ref()).unwrap();
+            if let Some(profiler) = app.resources_mut().get_mut::<SystemProfiler>() {
+                profiler.record_render(now_ms() - render_start);
             }
-        }) as Box<dyn FnMut(f64)>));
-        
-        // 最初のアニメーションフレームをリクエストするために新しいウィンドウインスタンスを取得
-        let window_for_first_request = web_sys::window().ok_or_else(|| JsValue::from_str("最初のリクエスト用ウィンドウが見つかりません"))?;
-        
-        // アニメーションフレームをリクエスト
-        window_for_first_request.request_animation_frame(g.borrow().as_ref().unwrap().as_ref().unchecked_ref())?;
-        
-        // クロージャをドロップされないように保持
-        self._game_loop = Some(g.borrow_mut().take().unwrap());
-        
-        Ok(())
+            
+            // このフレームまでに溜まったイベントをページのコールバックに渡す
+            // コールバックからゲームを操作できるように、アプリケーションの借用を先に返す
+            let events = app.resources_mut()
+                .get_mut::<Events<GameEvent>>()
+                .map(|events| events.drain())
+                .unwrap_or_default();
+            drop(app);
+            dispatch_events(&callbacks_clone, &events);
+        })
     }
     
     /// ゲームを停止
     pub fn stop(&mut self) {
         info!("⏹️ ゲームを停止します");
-        self.is_running = false;
-        self._game_loop = None;  // クロージャをドロップしてゲームループを停止
+        self.game_loop.stop();  // リクエスト済みのフレームを取り消してゲームループを停止
+    }
+    
+    /// ゲームループが動いているか
+    #[wasm_bindgen(getter)]
+    pub fn is_running(&self) -> bool {
+        self.game_loop.is_running()
+    }
+    
+    /// このゲームをlib.rsのトップレベル関数（new_gameやhandle_clickなど）の操作対象にする
+    pub fn activate(&self) {
+        set_active_app(&self.app);
     }
     
-    /// ゲームをリセット
+    /// ゲームをリセット（ランダムに配り直す）
+    /// システムの実行順序はそのままなので、ゲームループは止めずに次のフレームから新しい盤面になる
     pub fn reset(&mut self) -> Result<(), JsValue> {
-        info!("🔄 ゲームをリセットします");
-        
-        // ゲームを一時停止
-        let was_running = self.is_running;
-        self.stop();
-        
-        // ワールドとリソースをクリア
-        self.world.borrow_mut().clear();
-        
-        // ゲームを再初期化
-        setup_game(
-            &mut self.world.borrow_mut(),
-            &mut self.system_manager.borrow_mut(),
-            &mut self.resource_manager.borrow_mut(),
-        )?;
-        
-        // 実行中だった場合は再開
-        if was_running {
-            self.start()?;
-        }
-        
+        setup::new_game(&mut self.app.borrow_mut(), NewGameMode::NewDeal)?;
+        Ok(())
+    }
+    
+    /// 同じ配り方でゲームをやり直す
+    pub fn restart(&mut self) -> Result<(), JsValue> {
+        setup::new_game(&mut self.app.borrow_mut(), NewGameMode::SameDeal)?;
+        Ok(())
+    }
+    
+    /// シード値を指定して配り直す
+    pub fn new_game_with_seed(&mut self, seed: u64) -> Result<(), JsValue> {
+        setup::new_game(&mut self.app.borrow_mut(), NewGameMode::Seeded(seed))?;
         Ok(())
     }
     
-    /// 入力イベントを登録
-    pub fn setup_input_handlers(&self) -> Result<(), JsValue> {
+    /// 入力イベントをこのゲームのキャンバスに登録（設定で入力を受け付けない場合は何もしない）
+    pub fn setup_input_handlers(&mut self) -> Result<(), JsValue> {
+        if !self.config.interactive {
+            info!("🚫 入力を受け付けない設定なので、入力イベントは登録しません");
+            return Ok(());
+        }
         self.input_handler.register_event_handlers()
     }
+    
+    /// 入力イベントを外す（観戦に切り替えるときなど）
+    pub fn remove_input_handlers(&mut self) {
+        self.input_handler.remove_event_handlers();
+    }
+    
+    /// ワールドの状態をJSON文字列で保存
+    pub fn save_snapshot(&self) -> Result<String, JsValue> {
+        let app = self.app.borrow();
+        history::board_snapshot(app.world(), app.resources()).to_json().map_err(JsValue::from)
+    }
+    
+    /// JSON文字列からワールドの状態を復元
+    pub fn load_snapshot(&mut self, json: &str) -> Result<(), JsValue> {
+        let snapshot = WorldSnapshot::from_json(json)?;
+        history::restore_state(&mut self.app.borrow_mut(), &snapshot)?;
+        info!("📥 スナップショットを読み込みました");
+        Ok(())
+    }
+    
+    /// カードを動かしたときのコールバックを登録（{ type: "move", from, to, cards, revealed, moves }）
+    pub fn on_move(&self, callback: js_sys::Function) {
+        self.callbacks.borrow_mut().on_move = Some(callback);
+    }
+    
+    /// ゲーム状態が変わったときのコールバックを登録（{ type: "state_change", from, to }）
+    pub fn on_state_change(&self, callback: js_sys::Function) {
+        self.callbacks.borrow_mut().on_state_change = Some(callback);
+    }
+    
+    /// 得点が変わったときのコールバックを登録（{ type: "score", points, moves, delta }）
+    pub fn on_score(&self, callback: js_sys::Function) {
+        self.callbacks.borrow_mut().on_score = Some(callback);
+    }
+    
+    /// ゲームをクリアしたときのコールバックを登録（{ type: "clear", points, moves }）
+    pub fn on_clear(&self, callback: js_sys::Function) {
+        self.callbacks.borrow_mut().on_clear = Some(callback);
+    }
+    
+    /// システムがエラーを返したときのコールバックを登録（{ type: "error", system, message, frame, disabled }）
+    pub fn on_error(&self, callback: js_sys::Function) {
+        self.callbacks.borrow_mut().on_error = Some(callback);
+    }
+    
+    /// 登録した全てのコールバックを外す
+    pub fn clear_callbacks(&self) {
+        self.callbacks.borrow_mut().clear();
+    }
+    
+    /// 1手元に戻す（戻せる手がなければfalse）
+    pub fn undo(&mut self) -> Result<bool, JsValue> {
+        history::undo_move(&mut self.app.borrow_mut()).map_err(JsValue::from)
+    }
+    
+    /// 元に戻した手をやり直す（やり直せる手がなければfalse）
+    pub fn redo(&mut self) -> Result<bool, JsValue> {
+        history::redo_move(&mut self.app.borrow_mut()).map_err(JsValue::from)
+    }
+    
+    /// 盤面の座標でのクリックを入力する（次のフレームでInputSystemが処理する）
+    /// キャンバスを縮小していても、既定の大きさ（800x600）での座標で指定する
+    pub fn click(&self, x: f64, y: f64) -> Result<(), JsValue> {
+        let mut app = self.app.borrow_mut();
+        let input_state = app.resources_mut().get_mut::<InputState>()
+            .ok_or(GameError::ResourceMissing("InputState"))?;
+        input_state.click(x, y);
+        Ok(())
+    }
+    
+    /// ゲーム状態（"Title" | "Playing" | "Paused" | "GameOver" | "Clear"）
+    #[wasm_bindgen(getter, unchecked_return_type = "GameStateName")]
+    pub fn state(&self) -> Result<JsValue, JsValue> {
+        to_js_value(&game_status(&self.app.borrow()).state)
+    }
+    
+    /// 指した手の数
+    #[wasm_bindgen(getter)]
+    pub fn move_count(&self) -> u32 {
+        game_status(&self.app.borrow()).moves
+    }
+    
+    /// 配ってからのプレイ時間（秒）
+    #[wasm_bindgen(getter)]
+    pub fn elapsed_seconds(&self) -> f64 {
+        game_status(&self.app.borrow()).elapsed_seconds
+    }
+    
+    /// 得点
+    #[wasm_bindgen(getter)]
+    pub fn score(&self) -> i32 {
+        game_status(&self.app.borrow()).points
+    }
+    
+    /// 配り方のシード値（JavaScriptではbigint、配る前はundefined）
+    #[wasm_bindgen(getter)]
+    pub fn seed(&self) -> Option<u64> {
+        self.app.borrow().resources().get::<DealSeed>().map(|seed| seed.0)
+    }
+    
+    /// 元に戻せる手があるか
+    #[wasm_bindgen(getter)]
+    pub fn can_undo(&self) -> bool {
+        game_status(&self.app.borrow()).can_undo
+    }
+    
+    /// やり直せる手があるか
+    #[wasm_bindgen(getter)]
+    pub fn can_redo(&self) -> bool {
+        game_status(&self.app.borrow()).can_redo
+    }
+    
+    /// ゲームの状況（状態・手数・得点・プレイ時間・シード値・元に戻せるか）をまとめて取得
+    #[wasm_bindgen(unchecked_return_type = "GameStatus")]
+    pub fn status(&self) -> Result<JsValue, JsValue> {
+        to_js_value(&game_status(&self.app.borrow()))
+    }
+    
+    /// 全てのスタックの枚数と一番上のカードを取得（山札・捨て札・組み札・場札の順）
+    #[wasm_bindgen(unchecked_return_type = "StackSummary[]")]
+    pub fn stacks(&self) -> Result<JsValue, JsValue> {
+        to_js_value(&stack_summaries(self.app.borrow().world()))
+    }
+    
+    /// システムごとの実行時間とフレーム時間の統計をJSON文字列で取得
+    pub fn get_profile_stats(&self) -> String {
+        match self.app.borrow().resources().get::<SystemProfiler>() {
+            Some(profiler) => profiler.to_json(),
+            None => "{}".to_string(),
+        }
+    }
+    
+    /// プロファイラーの統計をリセット
+    pub fn reset_profile_stats(&self) {
+        if let Some(profiler) = self.app.borrow_mut().resources_mut().get_mut::<SystemProfiler>() {
+            profiler.reset();
+        }
+    }
+    
+    /// システムが返した直近のエラーを新しい順に最大count件、JSON文字列で取得（バグ報告用）
+    pub fn get_recent_errors(&self, count: usize) -> String {
+        match self.app.borrow().resources().get::<ErrorLog>() {
+            Some(error_log) => error_log.to_json(count),
+            None => "[]".to_string(),
+        }
+    }
+    
+    /// エラーログを消去
+    pub fn clear_errors(&self) {
+        if let Some(error_log) = self.app.borrow_mut().resources_mut().get_mut::<ErrorLog>() {
+            error_log.clear();
+        }
+    }
+    
+    /// システムの一覧（名前・フェーズ・ラベル・有効かどうか・失敗回数）を実行順にJSON文字列で取得
+    pub fn list_systems(&self) -> Result<String, JsValue> {
+        let systems = self.app.borrow().system_manager().list_systems();
+        serde_json::to_string(&systems)
+            .map_err(|e| JsValue::from(GameError::Serialization(e.to_string())))
+    }
+    
+    /// 挿入・差し替えに使えるシステムの名前一覧をJSON文字列で取得
+    pub fn list_registered_systems(&self) -> String {
+        let app = self.app.borrow();
+        let names = app.resources().get::<SystemRegistry>().map(|registry| registry.names()).unwrap_or_default();
+        serde_json::to_string(&names).unwrap_or_else(|_| "[]".to_string())
+    }
+    
+    /// システムを名前で有効・無効にする
+    pub fn set_system_enabled(&self, name: &str, enabled: bool) -> Result<(), JsValue> {
+        self.app.borrow_mut().system_manager_mut().set_system_enabled(name, enabled).map_err(JsValue::from)
+    }
+    
+    /// システムを名前で取り除く
+    pub fn remove_system(&self, name: &str) -> Result<(), JsValue> {
+        self.app.borrow_mut().system_manager_mut().remove_system(name)?;
+        Ok(())
+    }
+    
+    /// 登録簿のシステムを、指定したラベルのシステムより先に実行されるように挿入する
+    pub fn insert_system_before(&self, name: &str, label: &str) -> Result<(), JsValue> {
+        self.insert_registered_system(name, SystemPlacement::Before(label))
+    }
+    
+    /// 登録簿のシステムを、指定したラベルのシステムより後に実行されるように挿入する
+    pub fn insert_system_after(&self, name: &str, label: &str) -> Result<(), JsValue> {
+        self.insert_registered_system(name, SystemPlacement::After(label))
+    }
+    
+    /// 実行中のシステムを登録簿のシステムで差し替える
+    pub fn replace_system(&self, name: &str, with: &str) -> Result<(), JsValue> {
+        let system = self.create_registered_system(with)?;
+        self.app.borrow_mut().system_manager_mut().replace_boxed_system(name, system)?;
+        Ok(())
+    }
+    
+    /// 型レジストリに登録されているコンポーネント名の一覧をJSON文字列で取得
+    pub fn list_component_types(&self) -> String {
+        let app = self.app.borrow();
+        let names = app.resources().get::<TypeRegistry>().map(|registry| registry.names()).unwrap_or_default();
+        serde_json::to_string(&names).unwrap_or_else(|_| "[]".to_string())
+    }
+    
+    /// エンティティのコンポーネントを名前をキーにしたJSON文字列で取得
+    pub fn inspect_entity(&self, entity_id: usize) -> Result<String, JsValue> {
+        let app = self.app.borrow();
+        let registry = app.resources().get::<TypeRegistry>()
+            .ok_or(GameError::ResourceMissing("TypeRegistry"))?;
+        let value = registry.entity_to_json(app.world(), entity_id);
+        serde_json::to_string(&value).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+    
+    /// エンティティのコンポーネントをJSON文字列で設定（持っていなければ追加）
+    pub fn set_component(&self, entity_id: usize, component: &str, json: &str) -> Result<(), JsValue> {
+        let value = serde_json::from_str(json).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        let mut app = self.app.borrow_mut();
+        let registry = app.resources().get::<TypeRegistry>()
+            .ok_or(GameError::ResourceMissing("TypeRegistry"))?;
+        registry.set(app.world_mut(), entity_id, component, value).map_err(JsValue::from)
+    }
+    
+    /// エンティティのコンポーネントのフィールドを1つだけJSON文字列で設定
+    pub fn set_component_field(&self, entity_id: usize, component: &str, field: &str, json: &str) -> Result<(), JsValue> {
+        let value = serde_json::from_str(json).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        let mut app = self.app.borrow_mut();
+        let registry = app.resources().get::<TypeRegistry>()
+            .ok_or(GameError::ResourceMissing("TypeRegistry"))?;
+        registry.set_field(app.world_mut(), entity_id, component, field, value).map_err(JsValue::from)
+    }
+}
+
+// 操作対象のゲームのアプリケーション（既定では最後に作成したゲーム、Game::activateで切り替える）
+// lib.rsのnew_gameなど、Gameを受け取らないトップレベル関数から操作するために使う
+// 弱参照なので、ゲームが破棄されれば自然に使えなくなる
+#[cfg(feature = "web")]
+thread_local! {
+    static ACTIVE_APP: RefCell<Weak<RefCell<App>>> = const { RefCell::new(Weak::new()) };
+}
+
+/// 操作対象のゲームを切り替える
+#[cfg(feature = "web")]
+fn set_active_app(app: &Rc<RefCell<App>>) {
+    ACTIVE_APP.with(|active| *active.borrow_mut() = Rc::downgrade(app));
+}
+
+/// 操作対象のゲームのアプリケーションを操作する
+/// ゲームがまだ作成されていないか、既に破棄されている場合はエラーを返す
+#[cfg(feature = "web")]
+pub(crate) fn with_active_app<T, F>(f: F) -> Result<T, JsValue>
+where
+    F: FnOnce(&mut App) -> Result<T, GameError>,
+{
+    let app = ACTIVE_APP.with(|active| active.borrow().upgrade())
+        .ok_or_else(|| GameError::InvalidOperation("ゲームが作成されていません。先にcreate_gameを呼び出してください".to_string()))?;
+    let mut app = app.try_borrow_mut()
+        .map_err(|_| GameError::InvalidOperation("ゲームは更新中です".to_string()))?;
+    f(&mut app).map_err(JsValue::from)
+}
+
+// JavaScriptに公開しない補助メソッド
+#[cfg(feature = "web")]
+impl Game {
+    /// 登録簿から名前でシステムを作る
+    fn create_registered_system(&self, name: &str) -> Result<Box<dyn System>, GameError> {
+        let app = self.app.borrow();
+        let registry = app.resources().get::<SystemRegistry>()
+            .ok_or(GameError::ResourceMissing("SystemRegistry"))?;
+        registry.create(name)
+    }
+    
+    /// 登録簿のシステムを指定した位置に挿入する
+    fn insert_registered_system(&self, name: &str, placement: SystemPlacement<'_>) -> Result<(), JsValue> {
+        let system = self.create_registered_system(name)?;
+        self.app.borrow_mut().system_manager_mut().insert_boxed_system(placement, system).map_err(JsValue::from)
+    }
 }
 
 // Dropトレイトを実装して、リソースの解放を行う
+// 入力ハンドラーはリスナーを、ゲームループはリクエスト済みのフレームを自分で外すので、同じページの他のゲームには影響しない
+#[cfg(feature = "web")]
 impl Drop for Game {
     fn drop(&mut self) {
         info!("👋 ゲームを終了します");
         self.stop();  // ゲームループを停止
-        
-        // ここで追加のクリーンアップが必要な場合は実装
     }
 } 
\ No newline at end of file
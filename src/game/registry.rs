@@ -0,0 +1,203 @@
+use std::collections::HashMap;
+use crate::error::GameResult;
+use crate::ecs::world::World;
+use crate::ecs::entity::EntityId;
+use crate::ecs::system::{System, SystemPhase, SystemLabel};
+use crate::ecs::resources::ResourceManager;
+use crate::ecs::component::{StackContainer, StackType};
+use crate::constants::{LABEL_INPUT, LABEL_STACK_REGISTRY};
+use log::{debug, warn};
+
+/// スタックの登録簿リソース
+/// スタックの種類からエンティティIDを引けるようにし、毎回StackContainerを走査しなくて済むようにする
+#[derive(Default, Clone)]
+pub struct StackRegistry {
+    stacks: HashMap<StackType, EntityId>,
+}
+
+impl StackRegistry {
+    /// 新しい登録簿を作成
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// ワールド内の全てのスタックから登録簿を作成
+    pub fn from_world(world: &World) -> Self {
+        let mut registry = Self::new();
+        for entity_id in world.get_entities_with_component::<StackContainer>() {
+            if let Some(stack) = world.get_component::<StackContainer>(entity_id) {
+                registry.insert(stack.stack_type, entity_id);
+            }
+        }
+        registry
+    }
+    
+    /// スタックを登録
+    pub fn insert(&mut self, stack_type: StackType, entity_id: EntityId) {
+        if let Some(previous) = self.stacks.insert(stack_type, entity_id) {
+            if previous != entity_id {
+                warn!("⚠️ スタック {:?} の登録がエンティティ {} から {} に置き換えられました", stack_type, previous, entity_id);
+            }
+        }
+    }
+    
+    /// エンティティの登録を解除
+    /// 同じ種類で別のエンティティが登録済みの場合は何もしない
+    pub fn remove_entity(&mut self, entity_id: EntityId) {
+        self.stacks.retain(|_, &mut id| id != entity_id);
+    }
+    
+    /// スタックの種類からエンティティIDを取得
+    pub fn get(&self, stack_type: StackType) -> Option<EntityId> {
+        self.stacks.get(&stack_type).copied()
+    }
+    
+    /// 山札のエンティティIDを取得
+    pub fn stock(&self) -> Option<EntityId> {
+        self.get(StackType::Stock)
+    }
+    
+    /// 捨て札のエンティティIDを取得
+    pub fn waste(&self) -> Option<EntityId> {
+        self.get(StackType::Waste)
+    }
+    
+    /// 場札の列のエンティティIDを取得
+    pub fn tableau(&self, column: usize) -> Option<EntityId> {
+        self.get(StackType::Tableau { column })
+    }
+    
+    /// 組み札のエンティティIDを取得
+    pub fn foundation(&self, suit: usize) -> Option<EntityId> {
+        self.get(StackType::Foundation { suit })
+    }
+    
+    /// 登録されている全ての組み札のエンティティIDをスート順に取得
+    pub fn foundations(&self) -> Vec<EntityId> {
+        let mut foundations: Vec<_> = self.stacks
+            .iter()
+            .filter_map(|(stack_type, &id)| match stack_type {
+                StackType::Foundation { suit } => Some((*suit, id)),
+                _ => None,
+            })
+            .collect();
+        foundations.sort_unstable();
+        foundations.into_iter().map(|(_, id)| id).collect()
+    }
+    
+    /// 登録されている全ての場札のエンティティIDを列順に取得
+    pub fn tableaus(&self) -> Vec<EntityId> {
+        let mut tableaus: Vec<_> = self.stacks
+            .iter()
+            .filter_map(|(stack_type, &id)| match stack_type {
+                StackType::Tableau { column } => Some((*column, id)),
+                _ => None,
+            })
+            .collect();
+        tableaus.sort_unstable();
+        tableaus.into_iter().map(|(_, id)| id).collect()
+    }
+    
+    /// 登録されているスタックの数
+    pub fn len(&self) -> usize {
+        self.stacks.len()
+    }
+    
+    /// 登録が空かどうか
+    pub fn is_empty(&self) -> bool {
+        self.stacks.is_empty()
+    }
+    
+    /// 前回の実行以降に追加・削除されたスタックを反映する
+    pub fn apply_changes(&mut self, world: &World) {
+        // 1. 削除されたスタックの登録を解除
+        for &entity_id in world.removed_components::<StackContainer>().iter() {
+            self.remove_entity(entity_id);
+        }
+        
+        // 2. 追加されたスタックを登録
+        for entity_id in world.get_entities_with_added::<StackContainer>() {
+            if let Some(stack) = world.get_component::<StackContainer>(entity_id) {
+                debug!("📚 スタック {:?} をエンティティ {} として登録", stack.stack_type, entity_id);
+                self.insert(stack.stack_type, entity_id);
+            }
+        }
+    }
+}
+
+/// スタック登録簿を最新に保つシステム
+/// スタックの生成・削除を変更検出で拾い、入力処理より先に反映する
+pub struct StackRegistrySystem;
+
+impl System for StackRegistrySystem {
+    fn name(&self) -> &'static str {
+        "StackRegistrySystem"
+    }
+    
+    fn phase(&self) -> SystemPhase {
+        SystemPhase::Input
+    }
+    
+    fn labels(&self) -> Vec<SystemLabel> {
+        vec![self.name(), LABEL_STACK_REGISTRY]
+    }
+    
+    fn before(&self) -> Vec<SystemLabel> {
+        vec![LABEL_INPUT]  // 入力処理がスタックを引く前に更新する
+    }
+    
+    fn run(&mut self, world: &mut World, resources: &mut ResourceManager, _delta_time: f32) -> GameResult<()> {
+        match resources.get_mut::<StackRegistry>() {
+            Some(registry) => registry.apply_changes(world),
+            None => resources.add(StackRegistry::from_world(world)),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ecs::component::Name;
+    
+    fn add_stack(world: &mut World, name: &str, stack_type: StackType) -> EntityId {
+        let entity_id = world.create_entity().unwrap();
+        world.add_component(entity_id, Name::new(name)).unwrap();
+        world.add_component(entity_id, StackContainer::new(stack_type)).unwrap();
+        entity_id
+    }
+    
+    #[test]
+    fn registry_follows_added_and_removed_stacks() {
+        let mut world = World::new();
+        let stock = add_stack(&mut world, "stock", StackType::Stock);
+        let second = add_stack(&mut world, "tableau_1", StackType::Tableau { column: 1 });
+        let first = add_stack(&mut world, "tableau_0", StackType::Tableau { column: 0 });
+        
+        let mut registry = StackRegistry::new();
+        registry.apply_changes(&world);
+        assert_eq!(registry.stock(), Some(stock));
+        assert_eq!(registry.tableaus(), vec![first, second]);
+        assert_eq!(world.find_by_name("tableau_1"), Some(second));
+        
+        // スタックでなくなったエンティティは登録から外れる
+        world.remove_component::<StackContainer>(stock);
+        registry.apply_changes(&world);
+        assert_eq!(registry.stock(), None);
+        assert_eq!(registry.len(), 2);
+    }
+    
+    #[test]
+    fn name_index_follows_renames() {
+        let mut world = World::new();
+        let waste = add_stack(&mut world, "waste", StackType::Waste);
+        
+        world.add_component(waste, Name::new("discard")).unwrap();
+        assert_eq!(world.find_by_name("waste"), None);
+        assert_eq!(world.find_by_name("discard"), Some(waste));
+        assert_eq!(world.get_name(waste), Some("discard"));
+        
+        world.remove_component::<Name>(waste);
+        assert_eq!(world.find_by_name("discard"), None);
+    }
+}
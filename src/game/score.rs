@@ -0,0 +1,88 @@
+use serde::Serialize;
+use crate::error::GameResult;
+use crate::ecs::world::World;
+use crate::ecs::system::{System, SystemPhase};
+use crate::ecs::resources::ResourceManager;
+use crate::ecs::component::StackType;
+use crate::game::solitaire::GameMove;
+use crate::constants::{
+    SCORE_WASTE_TO_TABLEAU, SCORE_TO_FOUNDATION, SCORE_REVEAL_CARD,
+    SCORE_FOUNDATION_TO_TABLEAU, SCORE_RECYCLE_WASTE,
+};
+
+/// 得点と手数のリソース
+/// 得点はWindows版ソリティアの標準ルールで数え、0未満にはならない
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct Score {
+    pub points: i32,  // 得点
+    pub moves: u32,   // 指した手の数
+}
+
+impl Score {
+    /// 0点・0手から始める
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// 1手を数えて得点を加え、実際に増減した得点を返す
+    pub fn apply(&mut self, game_move: &GameMove) -> i32 {
+        let before = self.points;
+        self.points = (self.points + points_for_move(game_move)).max(0);
+        self.moves += 1;
+        self.points - before
+    }
+}
+
+/// 1手で得られる得点
+pub fn points_for_move(game_move: &GameMove) -> i32 {
+    let points = match (game_move.from, game_move.to) {
+        (StackType::Waste, StackType::Tableau { .. }) => SCORE_WASTE_TO_TABLEAU,
+        (StackType::Waste, StackType::Foundation { .. }) => SCORE_TO_FOUNDATION,
+        (StackType::Tableau { .. }, StackType::Foundation { .. }) => SCORE_TO_FOUNDATION,
+        (StackType::Foundation { .. }, StackType::Tableau { .. }) => SCORE_FOUNDATION_TO_TABLEAU,
+        (StackType::Waste, StackType::Stock) => SCORE_RECYCLE_WASTE,
+        _ => 0,
+    };
+    
+    if game_move.revealed {
+        points + SCORE_REVEAL_CARD
+    } else {
+        points
+    }
+}
+
+/// プレイ時間のリソース
+/// プレイ中の固定ステップの時間だけを数えるので、クリア後やタイトル画面では進まない
+/// 元に戻す・やり直すでは戻さない
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PlayTime {
+    pub seconds: f64,  // 配ってからの経過時間（秒）
+}
+
+impl PlayTime {
+    /// 0秒から始める
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// プレイ時間を進めるシステム
+pub struct PlayTimeSystem;
+
+impl System for PlayTimeSystem {
+    fn name(&self) -> &'static str {
+        "PlayTimeSystem"
+    }
+    
+    fn phase(&self) -> SystemPhase {
+        SystemPhase::Update
+    }
+    
+    fn run(&mut self, _world: &mut World, resources: &mut ResourceManager, delta_time: f32) -> GameResult<()> {
+        // このシステムはPlaying状態のときだけ実行される（KlondikePluginでrun_ifを設定）
+        if let Some(play_time) = resources.get_mut::<PlayTime>() {
+            play_time.seconds += delta_time as f64;
+        }
+        Ok(())
+    }
+}
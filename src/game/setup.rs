@@ -1,78 +1,243 @@
-use wasm_bindgen::prelude::*;
+use crate::error::{GameError, GameResult};
 use crate::ecs::world::World;
-use crate::ecs::system::SystemManager;
-use crate::ecs::resources::{ResourceManager, TimeInfo, InputState, GameState, NetworkState};
-use crate::constants::TARGET_FPS;
-use crate::game::solitaire;
-use crate::render::systems::RenderSystem;
-use crate::input::systems::{InputSystem, DragSystem};
-use crate::game::state::GameStateSystem;
-use log::{info, error};
-
-/// ゲームの初期化を行う関数
-pub fn setup_game(
-    world: &mut World,
-    system_manager: &mut SystemManager,
-    resource_manager: &mut ResourceManager,
-) -> Result<(), JsValue> {
-    info!("🎮 ゲームをセットアップ中...");
+use crate::ecs::app::{App, Plugin};
+use crate::ecs::condition::in_state;
+use crate::ecs::resources::{ResourceManager, TimeInfo, FixedTime, GameState, NetworkState};
+use crate::ecs::profiler::SystemProfiler;
+use crate::ecs::error_log::ErrorLog;
+use crate::ecs::reflect::TypeRegistry;
+use crate::constants::{TARGET_FPS, FIXED_TIME_STEP, MAX_FIXED_STEPS_PER_FRAME};
+use crate::game::solitaire::{self, DealSeed};
+use crate::game::registry::{StackRegistry, StackRegistrySystem};
+use crate::game::card_index::{CardIndex, CardIndexSystem};
+use crate::game::history::MoveHistory;
+use crate::game::score::{Score, PlayTime, PlayTimeSystem};
+use crate::game::events::{GameEvent, GameEventSystem};
+use crate::game::state::{self, GameStateSystem, ShowClearModalSystem, HideClearModalSystem};
+use crate::input::plugin::InputPlugin;
+use crate::render::plugin::RenderPlugin;
+use log::info;
+
+/// 基本プラグイン
+/// 時間・固定タイムステップ・ゲーム状態・エラーログなど、全てのビルドで必要なリソースを追加する
+pub struct CorePlugin;
+
+impl Plugin for CorePlugin {
+    fn build(&self, app: &mut App) {
+        // 時間情報を初期化
+        app.insert_resource(TimeInfo::new(TARGET_FPS));
+        
+        // 固定タイムステップを初期化
+        app.insert_resource(FixedTime::new(FIXED_TIME_STEP, MAX_FIXED_STEPS_PER_FRAME));
+        
+        // ゲーム状態を初期化
+        app.insert_resource(GameState::Title);
+        
+        // システムが返したエラーの記録
+        app.insert_resource(ErrorLog::new());
+    }
+}
+
+/// クロンダイク（ソリティア）のルールのプラグイン
+/// スタック登録簿・カード索引・クリア判定・プレイ時間・ゲームのイベントと、盤面の初期化（手の履歴と得点を含む）を追加する
+pub struct KlondikePlugin;
+
+impl Plugin for KlondikePlugin {
+    fn build(&self, app: &mut App) {
+        // スタック登録簿の更新システムを追加
+        app.add_system(StackRegistrySystem);
+        
+        // カード索引の更新システムを追加
+        app.add_system(CardIndexSystem);
+        
+        // ゲーム状態システムを追加（クリア判定はプレイ中のみ）
+        app.add_system(GameStateSystem::new())
+            .run_if(in_state(GameState::Playing));
+        
+        // プレイ時間を数えるシステムを追加（プレイ中のみ）
+        app.add_system(PlayTimeSystem)
+            .run_if(in_state(GameState::Playing));
+        
+        // ゲーム状態の変化とエラーをイベントとして送るシステムを追加
+        app.add_event::<GameEvent>()
+            .add_system(GameEventSystem::new());
+        
+        // クリアモーダルの表示・非表示は状態遷移ごとに一度だけ実行
+        app.add_on_enter(GameState::Clear, ShowClearModalSystem)
+            .add_on_exit(GameState::Clear, HideClearModalSystem);
+        
+        // デバッグ用コンソールから作り直せるように登録
+        app.register_system(|| StackRegistrySystem)
+            .register_system(|| CardIndexSystem)
+            .register_system(GameStateSystem::new)
+            .register_system(|| PlayTimeSystem)
+            .register_system(GameEventSystem::new);
+        
+        // 盤面を配ってプレイ状態に移行（リセット時にも実行される）
+        app.add_startup(start_klondike);
+    }
+}
+
+/// クロンダイクの盤面を準備してプレイ状態に移行する
+fn start_klondike(world: &mut World, resources: &mut ResourceManager) -> GameResult<()> {
+    info!("🌍 ゲーム世界を初期化中...");
     
-    // リソースを初期化
-    setup_resources(resource_manager);
+    // スタック登録簿とカード索引を空にする（中身は各システムが反映する）
+    resources.add(StackRegistry::new());
+    resources.add(CardIndex::new());
     
-    // システムを初期化
-    setup_systems(system_manager);
+    // 前のゲームの手の履歴・得点・プレイ時間を捨てる
+    resources.add(MoveHistory::new());
+    resources.add(Score::new());
+    resources.add(PlayTime::new());
     
-    // ゲーム世界を初期化
-    setup_world(world)?;
+    // ソリティアボードをセットアップ（シード値が無ければランダムに決めて、やり直し用に記録する）
+    let seed = resources.get::<DealSeed>().map(|seed| seed.0).unwrap_or_else(rand::random);
+    resources.add(DealSeed(seed));
+    solitaire::setup_solitaire_board_with_seed(world, seed)?;
+    info!("🃏 シード値 {} で配りました", seed);
     
-    info!("✅ ゲームのセットアップが完了しました！");
+    // 盤面の準備ができたのでプレイ状態に移行
+    state::start_game(resources);
     Ok(())
 }
 
-/// リソースのセットアップ
-fn setup_resources(resource_manager: &mut ResourceManager) {
-    info!("📦 リソースを初期化中...");
-    
-    // 時間情報を初期化
-    let time_info = TimeInfo::new(TARGET_FPS);
-    resource_manager.add(time_info);
+/// 新しいゲームの始め方
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NewGameMode {
+    /// 同じ配り方でやり直す
+    SameDeal,
+    /// ランダムに配り直す
+    NewDeal,
+    /// シード値を指定して配り直す
+    Seeded(u64),
+}
+
+/// 新しいゲームを始める
+/// システムの実行順序はそのままに、盤面とゲームごとの状態だけを作り直す
+/// 戻り値は配り方のシード値
+pub fn new_game(app: &mut App, mode: NewGameMode) -> GameResult<u64> {
+    // 1. 配り方のシード値を決める
+    let seed = match mode {
+        NewGameMode::SameDeal => app.resources().get::<DealSeed>()
+            .map(|seed| seed.0)
+            .ok_or(GameError::ResourceMissing("DealSeed"))?,
+        NewGameMode::NewDeal => rand::random(),
+        NewGameMode::Seeded(seed) => seed,
+    };
+    app.insert_resource(DealSeed(seed));
     
-    // 入力状態を初期化
-    let input_state = InputState::new();
-    resource_manager.add(input_state);
+    // 2. ワールドとシステムの状態を初期化して配り直す
+    info!("🔄 新しいゲームを始めます（{:?}）", mode);
+    app.reset()?;
+    Ok(seed)
+}
+
+/// ネットワークのプラグイン
+pub struct NetworkPlugin;
+
+impl Plugin for NetworkPlugin {
+    fn build(&self, app: &mut App) {
+        // ネットワーク状態を初期化
+        app.insert_resource(NetworkState::new());
+    }
+}
+
+/// デバッグ用のプラグイン
+/// プロファイラーとインスペクター用の型レジストリを追加する
+pub struct DebugPlugin;
+
+impl Plugin for DebugPlugin {
+    fn build(&self, app: &mut App) {
+        // プロファイラーを初期化
+        app.insert_resource(SystemProfiler::new());
+        
+        // 型レジストリを初期化（インスペクターやコンソールから名前でコンポーネントを扱う）
+        app.insert_resource(TypeRegistry::with_game_components());
+    }
+}
+
+/// ブラウザで遊ぶための全てのプラグイン
+pub struct DefaultPlugins;
+
+impl Plugin for DefaultPlugins {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(CorePlugin)
+            .add_plugin(InputPlugin)
+            .add_plugin(KlondikePlugin)
+            .add_plugin(RenderPlugin)
+            .add_plugin(NetworkPlugin)
+            .add_plugin(DebugPlugin);
+    }
+}
+
+/// 描画なしで動かすためのプラグイン（テストやシミュレーション用）
+pub struct HeadlessPlugins;
+
+impl Plugin for HeadlessPlugins {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(CorePlugin)
+            .add_plugin(InputPlugin)
+            .add_plugin(KlondikePlugin)
+            .add_plugin(DebugPlugin);
+    }
+}
+
+/// プラグインからアプリケーションを組み立てて準備する
+pub fn build_app<P: Plugin>(plugins: P) -> GameResult<App> {
+    info!("🎮 ゲームをセットアップ中...");
     
-    // ゲーム状態を初期化
-    resource_manager.add(GameState::Title);
+    let mut app = App::new();
+    app.add_plugin(plugins);
+    app.setup()?;
     
-    // ネットワーク状態を初期化
-    let network_state = NetworkState::new();
-    resource_manager.add(network_state);
+    info!("✅ ゲームのセットアップが完了しました！");
+    Ok(app)
 }
 
-/// システムのセットアップ
-fn setup_systems(system_manager: &mut SystemManager) {
-    info!("⚙️ システムを初期化中...");
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ecs::component::{CardInfo, StackContainer};
     
-    // 入力システムを追加
-    system_manager.add_system(InputSystem::new());
+    // 山札のカードの並び（スート, ランク）
+    fn stock_cards(app: &App) -> Vec<(u8, u8)> {
+        let world = app.world();
+        let stock_id = StackRegistry::from_world(world).stock().unwrap();
+        world.get_component::<StackContainer>(stock_id).unwrap().cards.iter()
+            .map(|&card_id| {
+                let info = world.get_component::<CardInfo>(card_id).unwrap();
+                (info.suit, info.rank)
+            })
+            .collect()
+    }
     
-    // ドラッグシステムを追加
-    system_manager.add_system(DragSystem::new());
+    fn system_names(app: &App) -> Vec<&'static str> {
+        app.system_manager().list_systems().into_iter().map(|info| info.name).collect()
+    }
     
-    // ゲーム状態システムを追加
-    system_manager.add_system(GameStateSystem::new());
+    #[test]
+    fn same_deal_restarts_with_the_same_cards_and_schedule() {
+        let mut app = build_app(HeadlessPlugins).unwrap();
+        assert_eq!(new_game(&mut app, NewGameMode::Seeded(5)).unwrap(), 5);
+        let deal = stock_cards(&app);
+        let systems = system_names(&app);
+        app.update(1.0 / 60.0).unwrap();
+        
+        assert_eq!(new_game(&mut app, NewGameMode::SameDeal).unwrap(), 5);
+        assert_eq!(stock_cards(&app), deal);
+        assert_eq!(system_names(&app), systems);
+        assert_eq!(app.resources().get::<GameState>().map(|state| *state), Some(GameState::Playing));
+    }
     
-    // レンダリングシステムを追加
-    system_manager.add_system(RenderSystem::new());
+    #[test]
+    fn new_deal_records_its_seed_for_a_restart() {
+        let mut app = build_app(HeadlessPlugins).unwrap();
+        let seed = new_game(&mut app, NewGameMode::NewDeal).unwrap();
+        assert_eq!(app.resources().get::<DealSeed>().map(|seed| seed.0), Some(seed));
+        let deal = stock_cards(&app);
+        
+        assert_eq!(new_game(&mut app, NewGameMode::SameDeal).unwrap(), seed);
+        assert_eq!(stock_cards(&app), deal);
+    }
 }
-
-/// ゲーム世界のセットアップ
-fn setup_world(world: &mut World) -> Result<(), JsValue> {
-    info!("🌍 ゲーム世界を初期化中...");
-    
-    // ソリティアボードをセットアップ
-    solitaire::setup_solitaire_board(world)?;
-    
-    Ok(())
-} 
\ No newline at end of file
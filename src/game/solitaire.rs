@@ -1,7 +1,8 @@
-use wasm_bindgen::prelude::*;
+use crate::error::{GameError, GameResult};
 use crate::ecs::world::World;
 use crate::ecs::entity::EntityId;
-use crate::ecs::component::{Transform, CardInfo, StackContainer, StackType, Clickable, ClickHandlerType};
+use crate::ecs::component::{Transform, CardInfo, StackContainer, StackType, ClickHandlerType};
+use crate::ecs::bundle::StackBundle;
 use crate::game::card;
 use crate::constants::{
     STOCK_X, STOCK_Y, WASTE_X, WASTE_Y,
@@ -10,14 +11,47 @@ use crate::constants::{
     CARD_SPACING_X, STACK_OFFSET_Y,
 };
 
+/// 配り方を固定するためのシード値
+/// 現在の配り方のシード値
+/// 起動前に入れておくと毎回同じ配り方になり、無ければ盤面の初期化でランダムに決めて記録する
+/// 記録したシード値を使えば、同じ配り方でゲームをやり直せる
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DealSeed(pub u64);
+
+/// 1手の内容
+/// どのスタックからどのスタックへ何枚動かしたかを表し、得点の計算とページへの通知に使う
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GameMove {
+    pub from: StackType,
+    pub to: StackType,
+    pub cards: usize,     // 動かしたカードの枚数
+    pub revealed: bool,   // 動かした結果、タブローの裏向きのカードを表にしたかどうか
+}
+
 /// ソリティア（クロンダイク）ゲームのボードをセットアップ
-pub fn setup_solitaire_board(world: &mut World) -> Result<(), JsValue> {
+pub fn setup_solitaire_board(world: &mut World) -> GameResult<()> {
     // デッキを作成
     let mut deck = card::create_deck(world, STOCK_X, STOCK_Y)?;
     
     // デッキをシャッフル
     card::shuffle_deck(&mut deck);
     
+    deal_board(world, deck)
+}
+
+/// シード値を指定してソリティアのボードをセットアップ
+pub fn setup_solitaire_board_with_seed(world: &mut World, seed: u64) -> GameResult<()> {
+    // デッキを作成
+    let mut deck = card::create_deck(world, STOCK_X, STOCK_Y)?;
+    
+    // シード値でデッキをシャッフル
+    card::shuffle_deck_with_seed(&mut deck, seed);
+    
+    deal_board(world, deck)
+}
+
+/// シャッフル済みのデッキからスタックを作ってカードを配る
+fn deal_board(world: &mut World, mut deck: Vec<EntityId>) -> GameResult<()> {
     // ストック（山札）を作成
     let stock_id = create_stock(world, deck.clone())?;
     
@@ -40,68 +74,32 @@ pub fn setup_solitaire_board(world: &mut World) -> Result<(), JsValue> {
 }
 
 /// ストック（山札）を作成
-fn create_stock(world: &mut World, _cards: Vec<EntityId>) -> Result<EntityId, JsValue> {
-    // ストックのエンティティを作成
-    let stock_id = world.create_entity()?;
-    
-    // トランスフォームコンポーネントを追加
-    let transform = Transform::new(STOCK_X, STOCK_Y);
-    world.add_component(stock_id, transform)?;
-    
-    // スタックコンテナコンポーネントを追加
-    let stack = StackContainer::new(StackType::Stock);
-    world.add_component(stock_id, stack)?;
-    
-    // クリック可能コンポーネントを追加
-    let clickable = Clickable::new(ClickHandlerType::DrawFromStock);
-    world.add_component(stock_id, clickable)?;
-    
-    Ok(stock_id)
+fn create_stock(world: &mut World, _cards: Vec<EntityId>) -> GameResult<EntityId> {
+    world.spawn(StackBundle::new("stock", StackType::Stock, STOCK_X, STOCK_Y, ClickHandlerType::DrawFromStock))
 }
 
 /// ウェイスト（捨て札）を作成
-fn create_waste(world: &mut World) -> Result<EntityId, JsValue> {
-    // ウェイストのエンティティを作成
-    let waste_id = world.create_entity()?;
-    
-    // トランスフォームコンポーネントを追加
-    let transform = Transform::new(WASTE_X, WASTE_Y);
-    world.add_component(waste_id, transform)?;
-    
-    // スタックコンテナコンポーネントを追加
-    let stack = StackContainer::new(StackType::Waste);
-    world.add_component(waste_id, stack)?;
-    
-    // クリック可能コンポーネントを追加
-    let clickable = Clickable::new(ClickHandlerType::DrawFromWaste);
-    world.add_component(waste_id, clickable)?;
-    
-    Ok(waste_id)
+fn create_waste(world: &mut World) -> GameResult<EntityId> {
+    world.spawn(StackBundle::new("waste", StackType::Waste, WASTE_X, WASTE_Y, ClickHandlerType::DrawFromWaste))
 }
 
 /// タブロー（場札）を作成 - 7列
-fn create_tableau(world: &mut World) -> Result<Vec<EntityId>, JsValue> {
+fn create_tableau(world: &mut World) -> GameResult<Vec<EntityId>> {
     let mut tableau_ids = Vec::with_capacity(7);
     
     for i in 0..7 {
-        // 各列のエンティティを作成
-        let tableau_id = world.create_entity()?;
-        
         // 位置を計算（横に並べる）
         let x = TABLEAU_START_X + (i as f64 * CARD_SPACING_X * 1.5);
         let y = TABLEAU_START_Y;
         
-        // トランスフォームコンポーネントを追加
-        let transform = Transform::new(x, y);
-        world.add_component(tableau_id, transform)?;
-        
-        // スタックコンテナコンポーネントを追加
-        let stack = StackContainer::new(StackType::Tableau { column: i });
-        world.add_component(tableau_id, stack)?;
-        
-        // クリック可能コンポーネントを追加
-        let clickable = Clickable::new(ClickHandlerType::DrawFromTableau { column: i });
-        world.add_component(tableau_id, clickable)?;
+        // 各列のエンティティを作成
+        let tableau_id = world.spawn(StackBundle::new(
+            &format!("tableau_{}", i),
+            StackType::Tableau { column: i },
+            x,
+            y,
+            ClickHandlerType::DrawFromTableau { column: i },
+        ))?;
         
         tableau_ids.push(tableau_id);
     }
@@ -110,28 +108,22 @@ fn create_tableau(world: &mut World) -> Result<Vec<EntityId>, JsValue> {
 }
 
 /// ファウンデーション（組み札）を作成 - 4スート
-fn create_foundations(world: &mut World) -> Result<Vec<EntityId>, JsValue> {
+fn create_foundations(world: &mut World) -> GameResult<Vec<EntityId>> {
     let mut foundation_ids = Vec::with_capacity(4);
     
     for i in 0..4 {
-        // 各スートのエンティティを作成
-        let foundation_id = world.create_entity()?;
-        
         // 位置を計算（横に並べる）
         let x = FOUNDATION_START_X + (i as f64 * CARD_SPACING_X * 1.5);
         let y = FOUNDATION_START_Y;
         
-        // トランスフォームコンポーネントを追加
-        let transform = Transform::new(x, y);
-        world.add_component(foundation_id, transform)?;
-        
-        // スタックコンテナコンポーネントを追加
-        let stack = StackContainer::new(StackType::Foundation { suit: i });
-        world.add_component(foundation_id, stack)?;
-        
-        // クリック可能コンポーネントを追加
-        let clickable = Clickable::new(ClickHandlerType::DrawFromFoundation { stack: i });
-        world.add_component(foundation_id, clickable)?;
+        // 各スートのエンティティを作成
+        let foundation_id = world.spawn(StackBundle::new(
+            &format!("foundation_{}", i),
+            StackType::Foundation { suit: i },
+            x,
+            y,
+            ClickHandlerType::DrawFromFoundation { stack: i },
+        ))?;
         
         foundation_ids.push(foundation_id);
     }
@@ -144,7 +136,7 @@ fn deal_cards_to_tableau(
     world: &mut World,
     deck: &mut Vec<EntityId>,
     tableau_ids: &[EntityId],
-) -> Result<(), JsValue> {
+) -> GameResult<()> {
     // ソリティアのルールに従って、タブローの各列にカードを配る
     // 1列目に1枚、2列目に2枚、...、7列目に7枚
     for (i, &tableau_id) in tableau_ids.iter().enumerate() {
@@ -158,7 +150,10 @@ fn deal_cards_to_tableau(
             base_x = transform.position.x;
             base_y = transform.position.y;
         } else {
-            return Err(JsValue::from_str("タブローのトランスフォームが見つかりません"));
+            return Err(GameError::ComponentMissing {
+                entity: tableau_id,
+                component: "Transform",
+            });
         }
         
         // 各列に必要な枚数のカードを配る
@@ -201,7 +196,7 @@ fn add_cards_to_stock(
     world: &mut World,
     stock_id: EntityId,
     cards: &[EntityId],
-) -> Result<(), JsValue> {
+) -> GameResult<()> {
     // 先にトランスフォーム情報を取得
     let x;
     let y;
@@ -210,7 +205,10 @@ fn add_cards_to_stock(
         x = transform.position.x;
         y = transform.position.y;
     } else {
-        return Err(JsValue::from_str("ストックのトランスフォームが見つかりません"));
+        return Err(GameError::ComponentMissing {
+            entity: stock_id,
+            component: "Transform",
+        });
     }
     
     // 全てのカードをストックに追加
@@ -232,17 +230,18 @@ fn add_cards_to_stock(
     Ok(())
 }
 
-/// ストックからウェイストにカードを移動
+/// ストックからウェイストにカードを移動し、移動した手の内容を返す
+/// ストックが空ならウェイストのカードを全てストックに戻す（どちらも空なら何もせずNone）
 pub fn draw_from_stock(
     world: &mut World,
     stock_id: EntityId,
     waste_id: EntityId,
-) -> Result<bool, JsValue> {
+) -> GameResult<Option<GameMove>> {
     // ストックを確認し、空の場合はウェイストからストックにカードを戻す
     let is_stock_empty = {
         let stock = match world.get_component::<StackContainer>(stock_id) {
             Some(stack) => stack,
-            None => return Err(JsValue::from_str("ストックが見つかりません")),
+            None => return Err(GameError::StackNotFound("ストック".to_string())),
         };
         
         stock.is_empty()
@@ -251,19 +250,26 @@ pub fn draw_from_stock(
     // ストックが空の場合
     if is_stock_empty {
         // ウェイストからストックにカードを戻す
-        return reset_stock_from_waste(world, stock_id, waste_id);
+        let waste_count = world.get_component::<StackContainer>(waste_id).map_or(0, |waste| waste.card_count());
+        let recycled = reset_stock_from_waste(world, stock_id, waste_id)?;
+        return Ok(recycled.then_some(GameMove {
+            from: StackType::Waste,
+            to: StackType::Stock,
+            cards: waste_count,
+            revealed: false,
+        }));
     }
     
     // ストックの一番上のカードを取得
     let card_id = {
         let stock = match world.get_component::<StackContainer>(stock_id) {
             Some(stack) => stack,
-            None => return Err(JsValue::from_str("ストックが見つかりません")),
+            None => return Err(GameError::StackNotFound("ストック".to_string())),
         };
         
         match stock.top_card() {
             Some(id) => id,
-            None => return Err(JsValue::from_str("ストックにカードがありません")),
+            None => return Err(GameError::IllegalMove("ストックにカードがありません".to_string())),
         }
     };
     
@@ -278,12 +284,15 @@ pub fn draw_from_stock(
     let (waste_x, waste_y, waste_card_count) = {
         let waste_transform = match world.get_component::<Transform>(waste_id) {
             Some(transform) => transform,
-            None => return Err(JsValue::from_str("ウェイストのトランスフォームが見つかりません")),
+            None => return Err(GameError::ComponentMissing {
+                entity: waste_id,
+                component: "Transform",
+            }),
         };
         
         let waste = match world.get_component::<StackContainer>(waste_id) {
             Some(stack) => stack,
-            None => return Err(JsValue::from_str("ウェイストが見つかりません")),
+            None => return Err(GameError::StackNotFound("ウェイスト".to_string())),
         };
         
         (waste_transform.position.x, waste_transform.position.y, waste.card_count() as i32)
@@ -302,7 +311,12 @@ pub fn draw_from_stock(
         }
     }
     
-    Ok(true)
+    Ok(Some(GameMove {
+        from: StackType::Stock,
+        to: StackType::Waste,
+        cards: 1,
+        revealed: false,
+    }))
 }
 
 /// ウェイストからストックにカードを戻す
@@ -310,12 +324,12 @@ pub fn reset_stock_from_waste(
     world: &mut World,
     stock_id: EntityId,
     waste_id: EntityId,
-) -> Result<bool, JsValue> {
+) -> GameResult<bool> {
     // ウェイストのコンテナを取得し、カードをコピー
     let waste_cards = {
         let waste = match world.get_component::<StackContainer>(waste_id) {
             Some(stack) => stack.clone(),
-            None => return Err(JsValue::from_str("ウェイストが見つかりません")),
+            None => return Err(GameError::StackNotFound("ウェイスト".to_string())),
         };
         
         // ウェイストが空の場合は何もしない
@@ -330,7 +344,7 @@ pub fn reset_stock_from_waste(
     let (stock_x, stock_y) = {
         let stock_transform = match world.get_component::<Transform>(stock_id) {
             Some(transform) => transform.clone(),
-            None => return Err(JsValue::from_str("ストックが見つかりません")),
+            None => return Err(GameError::StackNotFound("ストック".to_string())),
         };
         
         (stock_transform.position.x, stock_transform.position.y)
@@ -448,4 +462,169 @@ pub fn check_game_clear(world: &World, foundation_ids: &[EntityId]) -> bool {
     }
     
     true
-} 
\ No newline at end of file
+}
+
+/// カードを移動できるかチェックし、移動元のスタックと一緒に移動するカードを返す
+/// - 表向きのカードだけを移動できる
+/// - ウェイストとファウンデーションからは一番上のカードだけ、タブローからはそのカード以降をまとめて移動する
+/// - ファウンデーションには同じスートでランクが1つ上のカードを1枚ずつ、タブローには色が異なりランクが1つ下のカード（空ならK）を置ける
+pub fn check_move(world: &World, card_id: EntityId, target_id: EntityId) -> GameResult<(EntityId, Vec<EntityId>)> {
+    // 1. カードが表向きかチェック
+    let card_info = world.get_component::<CardInfo>(card_id).ok_or(GameError::ComponentMissing {
+        entity: card_id,
+        component: "CardInfo",
+    })?;
+    if !card_info.face_up {
+        return Err(GameError::IllegalMove(format!("カード {} は裏向きです", card_id)));
+    }
+    
+    // 2. カードが入っているスタックと、一緒に移動するカードを調べる
+    let (source_id, cards) = find_stack_of(world, card_id)
+        .ok_or_else(|| GameError::IllegalMove(format!("カード {} はどのスタックにも入っていません", card_id)))?;
+    if source_id == target_id {
+        return Err(GameError::IllegalMove(format!("カード {} は既にスタック {} にあります", card_id, target_id)));
+    }
+    
+    // 3. 移動先のルールをチェック
+    let target = world.get_component::<StackContainer>(target_id)
+        .ok_or_else(|| GameError::StackNotFound(format!("スタック {}", target_id)))?;
+    let allowed = match target.stack_type {
+        StackType::Foundation { .. } => cards.len() == 1 && can_move_to_foundation(world, card_id, target_id),
+        StackType::Tableau { .. } => match target.top_card() {
+            Some(top_card_id) => card::can_stack_card(world, card_id, top_card_id),
+            None => card_info.rank == 12,  // 空のタブローにはKのみ置ける
+        },
+        _ => false,
+    };
+    
+    if allowed {
+        Ok((source_id, cards))
+    } else {
+        Err(GameError::IllegalMove(format!("カード {} はスタック {} に置けません", card_id, target_id)))
+    }
+}
+
+/// カードを別のスタックに移動し、移動した手の内容を返す
+/// タブローから移動した結果、一番上に裏向きのカードが残った場合は表向きにする
+/// 座標はスタックの変更を見てレイアウト側が更新する
+pub fn move_cards(world: &mut World, card_id: EntityId, target_id: EntityId) -> GameResult<GameMove> {
+    // 1. 移動できるかチェック
+    let (source_id, cards) = check_move(world, card_id, target_id)?;
+    
+    // 2. 元のスタックから取り除く
+    let (from, new_top) = match world.get_component_mut::<StackContainer>(source_id) {
+        Some(source) => {
+            let index = source.cards.len() - cards.len();
+            source.remove_cards_from_index(index);
+            (source.stack_type, source.top_card())
+        },
+        None => return Err(GameError::StackNotFound(format!("スタック {}", source_id))),
+    };
+    
+    // 3. 移動先のスタックに追加
+    let to = match world.get_component_mut::<StackContainer>(target_id) {
+        Some(target) => {
+            for &card in &cards {
+                target.add_card(card);
+            }
+            target.stack_type
+        },
+        None => return Err(GameError::StackNotFound(format!("スタック {}", target_id))),
+    };
+    
+    // 4. タブローで裏向きのカードが一番上になったら表向きにする
+    let mut revealed = false;
+    if let Some(top_card_id) = new_top {
+        let is_tableau = matches!(from, StackType::Tableau { .. });
+        let is_face_down = world.get_component::<CardInfo>(top_card_id).is_some_and(|info| !info.face_up);
+        if is_tableau && is_face_down {
+            card::flip_card(world, top_card_id)?;
+            revealed = true;
+        }
+    }
+    
+    Ok(GameMove {
+        from,
+        to,
+        cards: cards.len(),
+        revealed,
+    })
+}
+
+/// カードが入っているスタックと、そのカード以降で一緒に移動するカードを探す
+/// タブロー以外では一番上のカードしか動かせないので、一番上でなければNoneを返す
+fn find_stack_of(world: &World, card_id: EntityId) -> Option<(EntityId, Vec<EntityId>)> {
+    let mut stack_ids = world.get_entities_with_component::<StackContainer>();
+    stack_ids.sort_unstable();
+    
+    for stack_id in stack_ids {
+        let stack = match world.get_component::<StackContainer>(stack_id) {
+            Some(stack) => stack,
+            None => continue,
+        };
+        let index = match stack.cards.iter().position(|&card| card == card_id) {
+            Some(index) => index,
+            None => continue,
+        };
+        
+        return match stack.stack_type {
+            StackType::Tableau { .. } => Some((stack_id, stack.cards_from_index(index))),
+            StackType::Waste | StackType::Foundation { .. } if stack.top_card() == Some(card_id) => {
+                Some((stack_id, vec![card_id]))
+            },
+            _ => None,
+        };
+    }
+    
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::registry::StackRegistry;
+    
+    // スタックごとのカードの並び（スート, ランク, 表向きか）
+    type Layout = Vec<(StackType, Vec<(u8, u8, bool)>)>;
+    
+    fn layout(world: &World) -> Layout {
+        let mut stack_ids = world.get_entities_with_component::<StackContainer>();
+        stack_ids.sort_unstable();
+        stack_ids.into_iter()
+            .map(|stack_id| {
+                let stack = world.get_component::<StackContainer>(stack_id).unwrap();
+                let cards = stack.cards.iter()
+                    .map(|&card_id| {
+                        let info = world.get_component::<CardInfo>(card_id).unwrap();
+                        (info.suit, info.rank, info.face_up)
+                    })
+                    .collect();
+                (stack.stack_type, cards)
+            })
+            .collect()
+    }
+    
+    fn seeded_world(seed: u64) -> World {
+        let mut world = World::new();
+        setup_solitaire_board_with_seed(&mut world, seed).unwrap();
+        world
+    }
+    
+    #[test]
+    fn same_seed_deals_the_same_board() {
+        assert_eq!(layout(&seeded_world(42)), layout(&seeded_world(42)));
+        assert_ne!(layout(&seeded_world(42)), layout(&seeded_world(43)));
+    }
+    
+    #[test]
+    fn face_down_cards_cannot_be_moved() {
+        let world = seeded_world(7);
+        let registry = StackRegistry::from_world(&world);
+        let tableau = world.get_component::<StackContainer>(registry.tableau(6).unwrap()).unwrap();
+        
+        // 7列目は一番上以外が裏向き
+        let face_down = tableau.cards[0];
+        let result = check_move(&world, face_down, registry.tableau(0).unwrap());
+        assert!(matches!(result, Err(GameError::IllegalMove(_))));
+    }
+}
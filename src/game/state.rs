@@ -1,54 +1,33 @@
-use wasm_bindgen::prelude::*;
+use crate::error::GameResult;
 use crate::ecs::world::World;
-use crate::ecs::system::{System, SystemPhase, SystemPriority};
+use crate::ecs::system::{System, SystemPhase, SystemLabel};
 use crate::ecs::resources::{ResourceManager, GameState};
-use crate::ecs::component::{StackContainer, StackType};
+use crate::ecs::entity::EntityId;
+use crate::ecs::component::{Transform, Renderable};
 use crate::game::solitaire;
+use crate::game::registry::StackRegistry;
+use crate::constants::{LABEL_DRAG, LABEL_GAME_RULES, CANVAS_WIDTH, CANVAS_HEIGHT, CLEAR_MODAL_Z_INDEX};
 use log::{info, debug};
 
 /// ゲームの状態を管理するシステム
-pub struct GameStateSystem {
-    foundation_ids: Vec<usize>, // ファウンデーションのエンティティID
-}
+pub struct GameStateSystem;
 
 impl GameStateSystem {
     /// 新しいゲーム状態システムを作成
     pub fn new() -> Self {
-        Self {
-            foundation_ids: Vec::new(),
-        }
+        Self
     }
     
-    /// ファウンデーションのエンティティIDを見つける
-    fn find_foundation_ids(&mut self, world: &World) {
-        if !self.foundation_ids.is_empty() {
-            return;  // 既に見つかっている場合は何もしない
-        }
-        
-        // StackContainerコンポーネントを持つエンティティを取得
-        let entities_with_stack = world.get_entities_with_component::<StackContainer>();
-        
-        // ファウンデーションのエンティティを探す
-        for entity_id in entities_with_stack {
-            if let Some(stack) = world.get_component::<StackContainer>(entity_id) {
-                match stack.stack_type {
-                    StackType::Foundation { .. } => {
-                        self.foundation_ids.push(entity_id);
-                    },
-                    _ => {},
-                }
-            }
-        }
+}
+
+/// ゲームがクリアされたかチェック
+fn is_board_clear(world: &World, registry: &StackRegistry) -> bool {
+    let foundation_ids = registry.foundations();
+    if foundation_ids.len() != 4 {
+        return false;
     }
     
-    /// ゲームがクリアされたかチェック
-    fn check_game_clear(&self, world: &World) -> bool {
-        if self.foundation_ids.is_empty() {
-            return false;
-        }
-        
-        solitaire::check_game_clear(world, &self.foundation_ids)
-    }
+    solitaire::check_game_clear(world, &foundation_ids)
 }
 
 impl System for GameStateSystem {
@@ -60,56 +39,134 @@ impl System for GameStateSystem {
         SystemPhase::Update
     }
     
-    fn priority(&self) -> SystemPriority {
-        SystemPriority::new(100)  // 低い優先度で実行（他のシステムの後）
+    fn labels(&self) -> Vec<SystemLabel> {
+        vec![self.name(), LABEL_GAME_RULES]
     }
     
-    fn run(&mut self, world: &mut World, resources: &mut ResourceManager, _delta_time: f32) -> Result<(), JsValue> {
-        // ファウンデーションのIDを見つける（初回のみ）
-        self.find_foundation_ids(world);
+    fn after(&self) -> Vec<SystemLabel> {
+        vec![LABEL_DRAG]  // カードの移動が終わってからクリア判定する
+    }
+    
+    fn run(&mut self, world: &mut World, resources: &mut ResourceManager, _delta_time: f32) -> GameResult<()> {
+        // このシステムはPlaying状態のときだけ実行される（KlondikePluginでrun_ifを設定）
         
-        // 現在のゲーム状態を取得
-        let game_state = match resources.get::<GameState>() {
-            Some(state) => *state,
-            None => return Ok(()),  // ゲーム状態がなければ何もしない
+        // ゲームクリアのチェック（ファウンデーションは登録簿から引く）
+        let is_clear = match resources.get::<StackRegistry>() {
+            Some(registry) => is_board_clear(world, &registry),
+            None => false,
         };
         
-        // 状態に応じた処理
-        match game_state {
-            GameState::Title => {
-                // タイトル画面の処理
-                // 実際のゲームでは、ここでスタート画面の表示などを行う
-            },
-            GameState::Playing => {
-                // プレイ中の処理
-                
-                // ゲームクリアのチェック
-                if self.check_game_clear(world) {
-                    // ゲームクリア状態に移行
-                    info!("🎉 ゲームクリア！おめでとう！");
-                    if let Some(state) = resources.get_mut::<GameState>() {
-                        *state = GameState::Clear;
-                    }
+        if is_clear {
+            // ゲームクリア状態に移行
+            info!("🎉 ゲームクリア！おめでとう！");
+            change_game_state(resources, GameState::Clear);
+        }
+        
+        Ok(())
+    }
+}
+
+/// クリアモーダルのリソース
+/// 表示中のモーダルを構成するエンティティを保持する
+#[derive(Default)]
+pub struct ClearModal {
+    pub entities: Vec<EntityId>,
+}
+
+/// クリアモーダルを表示するシステム
+/// Clear状態に入ったときに一度だけ実行される
+pub struct ShowClearModalSystem;
+
+impl System for ShowClearModalSystem {
+    fn name(&self) -> &'static str {
+        "ShowClearModalSystem"
+    }
+    
+    fn phase(&self) -> SystemPhase {
+        SystemPhase::Update
+    }
+    
+    fn run(&mut self, world: &mut World, resources: &mut ResourceManager, _delta_time: f32) -> GameResult<()> {
+        let width = CANVAS_WIDTH as f64;
+        let height = CANVAS_HEIGHT as f64;
+        
+        // 1. 画面全体を覆う半透明の背景
+        let overlay = world.create_entity()?;
+        world.add_component(overlay, Transform::new(0.0, 0.0).with_z_index(CLEAR_MODAL_Z_INDEX))?;
+        let mut overlay_renderable = Renderable::rectangle(width, height, "#000000", "#000000", 0.0, 0.0);
+        overlay_renderable.opacity = 0.6;
+        world.add_component(overlay, overlay_renderable)?;
+        
+        // 2. クリアメッセージ
+        let message = world.create_entity()?;
+        world.add_component(message, Transform::new(width / 2.0, height / 2.0).with_z_index(CLEAR_MODAL_Z_INDEX + 1))?;
+        world.add_component(message, Renderable::text(
+            width,
+            60.0,
+            "🎉 ゲームクリア！",
+            "bold 48px sans-serif",
+            "#FFFFFF",
+            "center",
+            "middle",
+        ))?;
+        
+        // 3. 後で消せるようにエンティティを記録
+        let entities = vec![overlay, message];
+        if let Some(modal) = resources.get_mut::<ClearModal>() {
+            modal.entities.extend(entities);
+        } else {
+            resources.add(ClearModal { entities });
+        }
+        
+        debug!("🪟 クリアモーダルを表示しました");
+        Ok(())
+    }
+}
+
+/// クリアモーダルを閉じるシステム
+/// Clear状態から出るときに一度だけ実行される
+pub struct HideClearModalSystem;
+
+impl System for HideClearModalSystem {
+    fn name(&self) -> &'static str {
+        "HideClearModalSystem"
+    }
+    
+    fn phase(&self) -> SystemPhase {
+        SystemPhase::Update
+    }
+    
+    fn run(&mut self, world: &mut World, resources: &mut ResourceManager, _delta_time: f32) -> GameResult<()> {
+        if let Some(modal) = resources.get_mut::<ClearModal>() {
+            for entity in modal.entities.drain(..) {
+                // リセットなどで既に消えている場合もある
+                if world.entity_exists(entity) {
+                    world.remove_entity(entity);
                 }
-            },
-            GameState::Paused => {
-                // 一時停止中の処理
-                // 実際のゲームでは、ここで一時停止画面の表示などを行う
-            },
-            GameState::GameOver => {
-                // ゲームオーバーの処理
-                // 実際のゲームでは、ここでゲームオーバー画面の表示などを行う
-            },
-            GameState::Clear => {
-                // クリア画面の処理
-                // 実際のゲームでは、ここでクリア画面の表示などを行う
-            },
+            }
         }
         
+        debug!("🪟 クリアモーダルを閉じました");
         Ok(())
     }
 }
 
+/// 盤面を復元したあとのゲーム状態を決める
+/// ゲーム中（PlayingかClear）なら、組み札が揃っていればClear、そうでなければPlaying（それ以外の状態はそのまま）
+/// スタックの登録簿は次のステップまで古いままなので、盤面から直接スタックを探す
+pub fn game_state_for_board(world: &World, current: GameState) -> GameState {
+    match current {
+        GameState::Playing | GameState::Clear => {
+            if is_board_clear(world, &StackRegistry::from_world(world)) {
+                GameState::Clear
+            } else {
+                GameState::Playing
+            }
+        },
+        other => other,
+    }
+}
+
 /// ゲーム状態を変更する関数
 pub fn change_game_state(resources: &mut ResourceManager, new_state: GameState) {
     if let Some(state) = resources.get_mut::<GameState>() {
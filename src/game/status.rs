@@ -0,0 +1,183 @@
+use serde::Serialize;
+use crate::ecs::app::App;
+use crate::ecs::world::World;
+use crate::ecs::resources::GameState;
+use crate::ecs::component::{CardInfo, StackContainer, StackType};
+use crate::game::solitaire::DealSeed;
+use crate::game::score::{Score, PlayTime};
+use crate::game::history::MoveHistory;
+use crate::game::events::stack_name;
+
+/// ゲームの状況のまとめ
+/// ページのUI（得点表示や元に戻すボタンなど）が読み取るための値で、ゲームには書き戻さない
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct GameStatus {
+    pub state: GameState,       // ゲーム状態
+    pub moves: u32,             // 指した手の数
+    pub points: i32,            // 得点
+    pub elapsed_seconds: f64,   // 配ってからのプレイ時間（秒）
+    pub seed: Option<String>,   // 配り方のシード値（JavaScriptの数値では64ビットを表せないので文字列）
+    pub can_undo: bool,         // 元に戻せる手があるか
+    pub can_redo: bool,         // やり直せる手があるか
+}
+
+/// スタックの中身のまとめ
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct StackSummary {
+    pub stack: String,                // スタックの名前（"waste"、"tableau:3"など、イベントと同じ）
+    pub cards: usize,                 // カードの枚数
+    pub face_up: usize,               // 表向きのカードの枚数
+    pub top_card: Option<CardSummary>, // 一番上のカード（空か裏向きなら無し）
+}
+
+/// カード1枚のまとめ
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct CardSummary {
+    pub suit: u8,        // 0=ハート, 1=ダイヤ, 2=クラブ, 3=スペード
+    pub rank: u8,        // 0=A, 1=2, ..., 12=K
+    pub label: String,   // 表示用の名前（"A♥"など）
+}
+
+impl CardSummary {
+    /// カードの情報からまとめを作成
+    pub fn from_card(card: &CardInfo) -> Self {
+        Self {
+            suit: card.suit,
+            rank: card.rank,
+            label: format!("{}{}", card.get_symbol(), card.get_suit_symbol()),
+        }
+    }
+}
+
+/// 今のゲームの状況をまとめる
+/// リソースが無い項目（ゲームを配る前など）は初期値にする
+pub fn game_status(app: &App) -> GameStatus {
+    let resources = app.resources();
+    let score = resources.get::<Score>().map(|score| *score).unwrap_or_default();
+    let (can_undo, can_redo) = resources.get::<MoveHistory>()
+        .map(|history| (history.can_undo(), history.can_redo()))
+        .unwrap_or((false, false));
+    
+    GameStatus {
+        state: resources.get::<GameState>().map(|state| *state).unwrap_or(GameState::Title),
+        moves: score.moves,
+        points: score.points,
+        elapsed_seconds: resources.get::<PlayTime>().map_or(0.0, |play_time| play_time.seconds),
+        seed: resources.get::<DealSeed>().map(|seed| seed.0.to_string()),
+        can_undo,
+        can_redo,
+    }
+}
+
+/// 全てのスタックの中身をまとめる
+/// 並び順は山札・捨て札・組み札（スート順）・場札（列順）・手札
+pub fn stack_summaries(world: &World) -> Vec<StackSummary> {
+    let mut stacks: Vec<&StackContainer> = world.get_entities_with_component::<StackContainer>()
+        .into_iter()
+        .filter_map(|entity_id| world.get_component::<StackContainer>(entity_id))
+        .collect();
+    stacks.sort_by_key(|stack| stack_order(stack.stack_type));
+    
+    stacks.into_iter()
+        .map(|stack| {
+            let cards: Vec<&CardInfo> = stack.cards.iter()
+                .filter_map(|&card_id| world.get_component::<CardInfo>(card_id))
+                .collect();
+            StackSummary {
+                stack: stack_name(stack.stack_type),
+                cards: cards.len(),
+                face_up: cards.iter().filter(|card| card.face_up).count(),
+                // 裏向きのカードの中身はページに教えない
+                top_card: cards.last()
+                    .filter(|card| card.face_up)
+                    .map(|card| CardSummary::from_card(card)),
+            }
+        })
+        .collect()
+}
+
+// スタックを並べる順番
+fn stack_order(stack_type: StackType) -> (u8, usize) {
+    match stack_type {
+        StackType::Stock => (0, 0),
+        StackType::Waste => (1, 0),
+        StackType::Foundation { suit } => (2, suit),
+        StackType::Tableau { column } => (3, column),
+        StackType::Hand => (4, 0),
+    }
+}
+
+// JavaScriptに渡すまとめの型定義（フィールド名は上の構造体と揃える）
+#[cfg(feature = "web")]
+#[wasm_bindgen::prelude::wasm_bindgen(typescript_custom_section)]
+const STATUS_TYPES: &str = r#"
+export type GameStateName = "Title" | "Playing" | "Paused" | "GameOver" | "Clear";
+
+export interface GameStatus {
+    state: GameStateName;
+    moves: number;
+    points: number;
+    elapsed_seconds: number;
+    /** 64ビットのシード値を10進数の文字列で表したもの（配る前はnull） */
+    seed: string | null;
+    can_undo: boolean;
+    can_redo: boolean;
+}
+
+export interface CardSummary {
+    /** 0=ハート, 1=ダイヤ, 2=クラブ, 3=スペード */
+    suit: number;
+    /** 0=A, 1=2, ..., 12=K */
+    rank: number;
+    label: string;
+}
+
+export interface StackSummary {
+    /** "stock" | "waste" | "foundation:0"〜"foundation:3" | "tableau:0"〜"tableau:6" | "hand" */
+    stack: string;
+    cards: number;
+    face_up: number;
+    /** 一番上のカード（空か裏向きならnull） */
+    top_card: CardSummary | null;
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::solitaire;
+    use crate::game::setup::{build_app, new_game, HeadlessPlugins, NewGameMode};
+    
+    #[test]
+    fn stack_summaries_are_ordered_and_hide_face_down_cards() {
+        let mut world = World::new();
+        solitaire::setup_solitaire_board_with_seed(&mut world, 1).unwrap();
+        let summaries = stack_summaries(&world);
+        
+        let names: Vec<&str> = summaries.iter().map(|summary| summary.stack.as_str()).collect();
+        assert_eq!(&names[..6], &["stock", "waste", "foundation:0", "foundation:1", "foundation:2", "foundation:3"]);
+        assert_eq!(&names[6..13], &["tableau:0", "tableau:1", "tableau:2", "tableau:3", "tableau:4", "tableau:5", "tableau:6"]);
+        
+        // 山札は全て裏向きなので一番上のカードは教えない
+        let stock = &summaries[0];
+        assert_eq!((stock.cards, stock.face_up), (24, 0));
+        assert_eq!(stock.top_card, None);
+        
+        // 場札の7列目は一番上だけ表向き
+        let last_column = &summaries[12];
+        assert_eq!((last_column.cards, last_column.face_up), (7, 1));
+        assert!(last_column.top_card.is_some());
+    }
+    
+    #[test]
+    fn game_status_reports_the_seed_as_a_string() {
+        let mut app = build_app(HeadlessPlugins).unwrap();
+        new_game(&mut app, NewGameMode::Seeded(u64::MAX)).unwrap();
+        let status = game_status(&app);
+        
+        assert_eq!(status.state, GameState::Playing);
+        assert_eq!(status.seed.as_deref(), Some("18446744073709551615"));
+        assert_eq!((status.moves, status.points), (0, 0));
+        assert!(!status.can_undo && !status.can_redo);
+    }
+}
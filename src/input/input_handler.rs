@@ -2,17 +2,21 @@ use wasm_bindgen::prelude::*;
 use web_sys::{HtmlCanvasElement, MouseEvent, KeyboardEvent};
 use std::cell::RefCell;
 use std::rc::Rc;
-use crate::ecs::world::World;
-use crate::ecs::resources::{ResourceManager, InputState};
-use crate::utils::Vec2;
-use log::{info, debug};
+use crate::ecs::app::App;
+use crate::ecs::resources::InputState;
+use log::{info, debug, warn, error};
 
 /// 入力ハンドラー
 /// ユーザーの入力イベント（マウス、キーボード、タッチ）を処理する
+/// リスナーは全て自分のキャンバスに登録するので、1つのページに複数のゲームがあっても入力は混ざらない
 pub struct InputHandler {
     canvas: HtmlCanvasElement,
-    world: Rc<RefCell<World>>,
-    resources: Rc<RefCell<ResourceManager>>,
+    
+    // 入力状態を書き込むアプリケーション
+    app: Rc<RefCell<App>>,
+    
+    // 盤面の座標からキャンバスのピクセルへの倍率（Rendererと同じ値）
+    scale: f64,
     
     // イベントリスナーのクロージャを保持
     // ドロップされないように保持する必要がある
@@ -27,13 +31,12 @@ impl InputHandler {
     /// 新しい入力ハンドラーを作成
     pub fn new(
         canvas: HtmlCanvasElement,
-        world: Rc<RefCell<World>>,
-        resources: Rc<RefCell<ResourceManager>>,
+        app: Rc<RefCell<App>>,
     ) -> Result<Self, JsValue> {
         Ok(Self {
             canvas,
-            world,
-            resources,
+            app,
+            scale: 1.0,
             _mouse_down_listener: None,
             _mouse_up_listener: None,
             _mouse_move_listener: None,
@@ -42,8 +45,16 @@ impl InputHandler {
         })
     }
     
+    /// 盤面を拡大・縮小して描画している倍率を指定（マウス座標を盤面の座標に戻すのに使う）
+    pub fn with_scale(mut self, scale: f64) -> Self {
+        self.scale = scale;
+        self
+    }
+    
     /// 入力イベントハンドラーを登録
-    pub fn register_event_handlers(&self) -> Result<(), JsValue> {
+    /// 既に登録済みなら、二重に登録しないように外してから登録し直す
+    pub fn register_event_handlers(&mut self) -> Result<(), JsValue> {
+        self.remove_event_handlers();
         self.register_mouse_handlers()?;
         self.register_keyboard_handlers()?;
         
@@ -51,70 +62,117 @@ impl InputHandler {
         Ok(())
     }
     
+    /// 登録したイベントハンドラーを全て外す
+    pub fn remove_event_handlers(&mut self) {
+        let canvas = self.canvas.clone();
+        let remove = |event: &str, listener: &js_sys::Function| {
+            if let Err(e) = canvas.remove_event_listener_with_callback(event, listener) {
+                error!("{}イベントのリスナーを外せませんでした: {:?}", event, e);
+            }
+        };
+        
+        if let Some(listener) = self._mouse_down_listener.take() {
+            remove("mousedown", listener.as_ref().unchecked_ref());
+        }
+        if let Some(listener) = self._mouse_up_listener.take() {
+            remove("mouseup", listener.as_ref().unchecked_ref());
+        }
+        if let Some(listener) = self._mouse_move_listener.take() {
+            remove("mousemove", listener.as_ref().unchecked_ref());
+        }
+        if let Some(listener) = self._key_down_listener.take() {
+            remove("keydown", listener.as_ref().unchecked_ref());
+        }
+        if let Some(listener) = self._key_up_listener.take() {
+            remove("keyup", listener.as_ref().unchecked_ref());
+        }
+    }
+    
+    /// 入力状態を書き換える
+    /// ゲームの更新中やコールバックの中でDOMイベントが届いたときは、パニックせずにログを残してそのイベントを捨てる
+    fn with_input_state<F>(app: &RefCell<App>, event: &str, f: F)
+    where
+        F: FnOnce(&mut InputState),
+    {
+        match app.try_borrow_mut() {
+            Ok(mut app) => {
+                if let Some(input_state) = app.resources_mut().get_mut::<InputState>() {
+                    f(input_state);
+                }
+            },
+            Err(_) => warn!("⚠️ ゲームの更新中に{}イベントが届いたので無視しました", event),
+        }
+    }
+    
+    /// マウスイベントの位置を盤面の座標に変換
+    /// CSSでキャンバスの表示サイズを変えていても、キャンバスのピクセルに合わせてから倍率で割る
+    fn board_position(canvas: &HtmlCanvasElement, scale: f64, event: &MouseEvent) -> (f64, f64) {
+        let rect = canvas.get_bounding_client_rect();
+        let css_scale_x = if rect.width() > 0.0 { canvas.width() as f64 / rect.width() } else { 1.0 };
+        let css_scale_y = if rect.height() > 0.0 { canvas.height() as f64 / rect.height() } else { 1.0 };
+        let x = (event.client_x() as f64 - rect.left()) * css_scale_x / scale;
+        let y = (event.client_y() as f64 - rect.top()) * css_scale_y / scale;
+        (x, y)
+    }
+    
     /// マウスイベントハンドラーを登録
-    fn register_mouse_handlers(&self) -> Result<(), JsValue> {
+    fn register_mouse_handlers(&mut self) -> Result<(), JsValue> {
         // mousedownイベントのハンドラーを作成
-        let _world = Rc::clone(&self.world);
-        let resources = Rc::clone(&self.resources);
+        let app = Rc::clone(&self.app);
         let canvas = self.canvas.clone();
+        let scale = self.scale;
         
         let mouse_down_closure = Closure::wrap(Box::new(move |event: MouseEvent| {
             // イベントのデフォルト動作を防止
+            // デフォルト動作を止めるとフォーカスが移らないので、キー入力を受け取れるように自分でフォーカスする
             event.prevent_default();
+            let _ = canvas.focus();
             
-            // マウス座標を取得（キャンバス座標系に変換）
-            let rect = canvas.get_bounding_client_rect();
-            let x = event.client_x() as f64 - rect.left();
-            let y = event.client_y() as f64 - rect.top();
+            // マウス座標を取得（盤面の座標系に変換）
+            let (x, y) = Self::board_position(&canvas, scale, &event);
             
             // 入力状態を更新
-            if let Some(input_state) = resources.borrow_mut().get_mut::<InputState>() {
+            Self::with_input_state(&app, "mousedown", |input_state| {
                 input_state.update_mouse_position(x, y);
                 input_state.update_mouse_button(0, true);  // 左ボタン
                 input_state.is_mouse_clicked = true;  // クリックフラグを設定
                 input_state.is_mouse_down = true;     // マウスが押されている状態に設定
                 debug!("🖱️ マウスダウン検出: ({}, {}) - クリックフラグ設定", x, y);
-            }
+            });
         }) as Box<dyn FnMut(MouseEvent)>);
         
         // mouseupイベントのハンドラーを作成
-        let _world_up = Rc::clone(&self.world);
-        let resources_up = Rc::clone(&self.resources);
+        let app_up = Rc::clone(&self.app);
         let canvas_up = self.canvas.clone();
         
         let mouse_up_closure = Closure::wrap(Box::new(move |event: MouseEvent| {
             event.prevent_default();
             
-            let rect = canvas_up.get_bounding_client_rect();
-            let x = event.client_x() as f64 - rect.left();
-            let y = event.client_y() as f64 - rect.top();
+            let (x, y) = Self::board_position(&canvas_up, scale, &event);
             
-            if let Some(input_state) = resources_up.borrow_mut().get_mut::<InputState>() {
+            Self::with_input_state(&app_up, "mouseup", |input_state| {
                 input_state.update_mouse_position(x, y);
                 input_state.update_mouse_button(0, false);  // 左ボタン
                 input_state.is_mouse_down = false;  // マウスが離された状態に設定
                 debug!("🖱️ マウスアップ: ({}, {})", x, y);
-            }
+            });
         }) as Box<dyn FnMut(MouseEvent)>);
         
         // mousemoveイベントのハンドラーを作成
-        let _world_move = Rc::clone(&self.world);
-        let resources_move = Rc::clone(&self.resources);
+        let app_move = Rc::clone(&self.app);
         let canvas_move = self.canvas.clone();
         
         let mouse_move_closure = Closure::wrap(Box::new(move |event: MouseEvent| {
             // マウス移動イベントは頻繁に発生するのでpreventDefaultは不要
             
-            let rect = canvas_move.get_bounding_client_rect();
-            let x = event.client_x() as f64 - rect.left();
-            let y = event.client_y() as f64 - rect.top();
+            let (x, y) = Self::board_position(&canvas_move, scale, &event);
             
-            if let Some(input_state) = resources_move.borrow_mut().get_mut::<InputState>() {
+            Self::with_input_state(&app_move, "mousemove", |input_state| {
                 input_state.update_mouse_position(x, y);
                 
                 // マウスボタンが押されているかどうかに関わらず、マウスの動きをログに記録
                 debug!("🖱️ マウス移動: ({}, {}), ドラッグ中: {}", x, y, input_state.is_mouse_down);
-            }
+            });
         }) as Box<dyn FnMut(MouseEvent)>);
         
         // キャンバスにイベントリスナーを追加
@@ -134,121 +192,67 @@ impl InputHandler {
         )?;
         
         // クロージャを保持（ドロップされないように）
-        let this = self as *const _ as *mut InputHandler;
-        unsafe {
-            (*this)._mouse_down_listener = Some(mouse_down_closure);
-            (*this)._mouse_up_listener = Some(mouse_up_closure);
-            (*this)._mouse_move_listener = Some(mouse_move_closure);
-        }
+        self._mouse_down_listener = Some(mouse_down_closure);
+        self._mouse_up_listener = Some(mouse_up_closure);
+        self._mouse_move_listener = Some(mouse_move_closure);
         
         Ok(())
     }
     
     /// キーボードイベントハンドラーを登録
-    fn register_keyboard_handlers(&self) -> Result<(), JsValue> {
-        // キーボードイベントはドキュメント全体に設定
-        let document = web_sys::window()
-            .ok_or_else(|| JsValue::from_str("ウィンドウが見つかりません"))?
-            .document()
-            .ok_or_else(|| JsValue::from_str("ドキュメントが見つかりません"))?;
+    fn register_keyboard_handlers(&mut self) -> Result<(), JsValue> {
+        // キーボードイベントはキャンバスに設定し、フォーカスのあるゲームだけが受け取る
+        // キャンバスはそのままではフォーカスできないので、tabindexが無ければ付ける
+        if !self.canvas.has_attribute("tabindex") {
+            self.canvas.set_attribute("tabindex", "0")?;
+        }
         
         // keydownイベントのハンドラーを作成
-        let resources_down = Rc::clone(&self.resources);
+        let app_down = Rc::clone(&self.app);
         
         let key_down_closure = Closure::wrap(Box::new(move |event: KeyboardEvent| {
             let key = event.key();
             
-            if let Some(input_state) = resources_down.borrow_mut().get_mut::<InputState>() {
+            Self::with_input_state(&app_down, "keydown", |input_state| {
                 input_state.update_key(&key, true);
                 debug!("⌨️ キーダウン: {}", key);
-            }
+            });
         }) as Box<dyn FnMut(KeyboardEvent)>);
         
         // keyupイベントのハンドラーを作成
-        let resources_up = Rc::clone(&self.resources);
+        let app_up = Rc::clone(&self.app);
         
         let key_up_closure = Closure::wrap(Box::new(move |event: KeyboardEvent| {
             let key = event.key();
             
-            if let Some(input_state) = resources_up.borrow_mut().get_mut::<InputState>() {
+            Self::with_input_state(&app_up, "keyup", |input_state| {
                 input_state.update_key(&key, false);
                 debug!("⌨️ キーアップ: {}", key);
-            }
+            });
         }) as Box<dyn FnMut(KeyboardEvent)>);
         
-        // ドキュメントにイベントリスナーを追加
-        document.add_event_listener_with_callback(
+        // キャンバスにイベントリスナーを追加
+        self.canvas.add_event_listener_with_callback(
             "keydown",
             key_down_closure.as_ref().unchecked_ref(),
         )?;
         
-        document.add_event_listener_with_callback(
+        self.canvas.add_event_listener_with_callback(
             "keyup",
             key_up_closure.as_ref().unchecked_ref(),
         )?;
         
         // クロージャを保持（ドロップされないように）
-        let this = self as *const _ as *mut InputHandler;
-        unsafe {
-            (*this)._key_down_listener = Some(key_down_closure);
-            (*this)._key_up_listener = Some(key_up_closure);
-        }
+        self._key_down_listener = Some(key_down_closure);
+        self._key_up_listener = Some(key_up_closure);
         
         Ok(())
     }
-    
-    /// 指定した座標にあるエンティティを取得
-    pub fn get_entity_at_position(
-        world: &World,
-        position: Vec2,
-    ) -> Option<usize> {
-        // レンダラブルコンポーネントを持つエンティティのうち、
-        // Z-indexが大きい（上に表示されている）順にソート
-        let mut entities = world.get_entities_with_component::<crate::ecs::component::Renderable>();
-        
-        if entities.is_empty() {
-            return None;
-        }
-        
-        // Z-indexでソート（大きい順）
-        entities.sort_by(|&a, &b| {
-            let z_a = world
-                .get_component::<crate::ecs::component::Transform>(a)
-                .map(|t| t.z_index)
-                .unwrap_or(0);
-                
-            let z_b = world
-                .get_component::<crate::ecs::component::Transform>(b)
-                .map(|t| t.z_index)
-                .unwrap_or(0);
-                
-            z_b.cmp(&z_a)  // 降順
-        });
-        
-        // 座標が含まれるエンティティを探す
-        for &entity_id in &entities {
-            let transform = match world.get_component::<crate::ecs::component::Transform>(entity_id) {
-                Some(t) => t,
-                None => continue,
-            };
-            
-            let renderable = match world.get_component::<crate::ecs::component::Renderable>(entity_id) {
-                Some(r) => r,
-                None => continue,
-            };
-            
-            // エンティティの領域内にあるかチェック
-            let x = transform.position.x;
-            let y = transform.position.y;
-            let width = renderable.width;
-            let height = renderable.height;
-            
-            if position.x >= x && position.x <= x + width &&
-               position.y >= y && position.y <= y + height {
-                return Some(entity_id);
-            }
-        }
-        
-        None
+}
+
+// ハンドラーを破棄したあとにイベントが届かないように、リスナーを外してからクロージャを解放する
+impl Drop for InputHandler {
+    fn drop(&mut self) {
+        self.remove_event_handlers();
     }
-} 
\ No newline at end of file
+}
@@ -4,5 +4,7 @@
 // ゲームの状態更新やECSシステムに反映させる機能を提供します。
 
 // サブモジュール
-pub mod input_handler;  // 入力ハンドラー
-pub mod systems;        // 入力処理システム 
\ No newline at end of file
+#[cfg(feature = "web")]
+pub mod input_handler;  // 入力ハンドラー（ブラウザのイベントを使うのでweb機能が有効なときだけ）
+pub mod systems;        // 入力処理システム
+pub mod plugin;         // 入力のプラグイン
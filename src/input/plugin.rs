@@ -0,0 +1,26 @@
+use crate::ecs::app::{App, Plugin};
+use crate::ecs::condition::in_state;
+use crate::ecs::resources::{InputState, GameState};
+use crate::input::systems::{InputSystem, DragSystem};
+
+/// 入力のプラグイン
+/// 入力状態のリソースと、クリック・ドラッグのシステムを追加する
+pub struct InputPlugin;
+
+impl Plugin for InputPlugin {
+    fn build(&self, app: &mut App) {
+        // 入力状態を初期化
+        app.insert_resource(InputState::new());
+        
+        // 入力システムを追加
+        app.add_system(InputSystem::new());
+        
+        // ドラッグシステムを追加（プレイ中のみ操作を受け付ける）
+        app.add_system(DragSystem::new())
+            .run_if(in_state(GameState::Playing));
+        
+        // デバッグ用コンソールから作り直せるように登録
+        app.register_system(InputSystem::new)
+            .register_system(DragSystem::new);
+    }
+}
@@ -1,12 +1,15 @@
-use wasm_bindgen::prelude::*;
+use crate::error::{GameError, GameResult};
 use crate::ecs::world::World;
-use crate::ecs::system::{System, SystemPhase, SystemPriority};
+use crate::ecs::system::{System, SystemPhase, SystemLabel};
 use crate::ecs::resources::{ResourceManager, InputState};
-use crate::ecs::component::{Transform, Draggable, Clickable, StackContainer, StackType, Droppable, Renderable};
+use crate::ecs::component::{Transform, Draggable, Clickable, ClickHandlerType, CardInfo, StackContainer, StackType, Droppable, Renderable};
 use crate::ecs::entity::EntityId;
-use crate::input::input_handler::InputHandler;
+use crate::game::registry::StackRegistry;
+use crate::game::card_index::CardIndex;
+use crate::game::events;
 use crate::utils::Vec2;
-use crate::constants::{DRAG_OPACITY};
+use crate::ecs::hierarchy::{LocalTransform, set_parent, remove_parent};
+use crate::constants::{DRAG_OPACITY, DRAG_Z_INDEX, STACK_OFFSET_Y, LABEL_INPUT, LABEL_DRAG};
 use log::debug;
 /// 入力処理システム
 /// マウスやキーボードの入力を処理し、ゲーム状態を更新する
@@ -30,74 +33,83 @@ impl InputSystem {
     fn process_click(
         &mut self,
         world: &mut World,
+        resources: &ResourceManager,
         entity_id: EntityId,
-    ) -> Result<(), JsValue> {
-        // クリック可能コンポーネントを持つかチェック
-        if let Some(clickable) = world.get_component_mut::<Clickable>(entity_id) {
-            // クリックされたことをマーク
-            clickable.was_clicked = true;
-            debug!("🖱️ エンティティ {} がクリックされました", entity_id);
-            
-            // クリックハンドラーのタイプに応じて処理
-            match &clickable.click_handler {
-                crate::ecs::component::ClickHandlerType::FlipCard => {
-                    // カードをめくる
-                    if let Some(card_info) = world.get_component_mut::<crate::ecs::component::CardInfo>(entity_id) {
-                        card_info.face_up = !card_info.face_up;
-                        debug!("🃏 カード {} を{}", entity_id, if card_info.face_up { "表向き" } else { "裏向き" });
-                    }
-                },
-                crate::ecs::component::ClickHandlerType::DrawFromStock => {
-                    // ストックからカードを引く処理
-                    let (stock_id, waste_id) = self.find_stock_and_waste(world)?;
-                    crate::game::solitaire::draw_from_stock(world, stock_id, waste_id)?;
-                },
-                crate::ecs::component::ClickHandlerType::DrawFromWaste => {
-                    // ウェイストからカードを引く処理
-                    // 実際の実装はもっと複雑になるが、ここではシンプルに
-                    debug!("🃏 ウェイストからカードを引く処理");
-                },
-                crate::ecs::component::ClickHandlerType::DrawFromTableau { column } => {
-                    // タブローからカードを引く処理
-                    debug!("🃏 タブロー列 {} からカードを引く処理", column);
-                },
-                crate::ecs::component::ClickHandlerType::DrawFromFoundation { stack } => {
-                    // ファウンデーションからカードを引く処理
-                    debug!("🃏 ファウンデーションスタック {} からカードを引く処理", stack);
-                },
-                crate::ecs::component::ClickHandlerType::Custom => {
-                    // カスタム処理（必要に応じて実装）
-                },
+    ) -> GameResult<()> {
+        // クリック可能コンポーネントを持つかチェックし、クリックされたことをマーク
+        let click_handler = match world.get_component_mut::<Clickable>(entity_id) {
+            Some(clickable) => {
+                clickable.was_clicked = true;
+                clickable.click_handler.clone()
+            },
+            None => return Ok(()),
+        };
+        debug!("🖱️ エンティティ {} がクリックされました", entity_id);
+        
+        // 盤面を変える操作のときだけ、変える直前の盤面を取っておく
+        let mut before = None;
+        let mut game_move = None;
+        
+        // クリックハンドラーのタイプに応じて処理
+        match click_handler {
+            ClickHandlerType::FlipCard => {
+                // カードをめくる
+                // 表向きになったカードをドラッグできるようにするのはflip_cardが行う
+                if world.has_component::<CardInfo>(entity_id) {
+                    before = Some(world.snapshot());
+                    crate::game::card::flip_card(world, entity_id)?;
+                    let face_up = world.get_component::<CardInfo>(entity_id).is_some_and(|card_info| card_info.face_up);
+                    debug!("🃏 カード {} を{}", entity_id, if face_up { "表向き" } else { "裏向き" });
+                }
+            },
+            ClickHandlerType::DrawFromStock => {
+                // ストックからカードを引く処理（ストックもウェイストも空なら何も変わらない）
+                let registry = resources.get::<StackRegistry>();
+                let (stock_id, waste_id) = Self::find_stock_and_waste(registry.as_deref())?;
+                let has_cards = [stock_id, waste_id].iter().any(|&stack_id| {
+                    world.get_component::<StackContainer>(stack_id).is_some_and(|stack| !stack.cards.is_empty())
+                });
+                if has_cards {
+                    let snapshot = world.snapshot();
+                    game_move = crate::game::solitaire::draw_from_stock(world, stock_id, waste_id)?;
+                    before = game_move.as_ref().map(|_| snapshot);
+                }
+            },
+            ClickHandlerType::DrawFromWaste => {
+                // ウェイストからカードを引く処理
+                // 実際の実装はもっと複雑になるが、ここではシンプルに
+                debug!("🃏 ウェイストからカードを引く処理");
+            },
+            ClickHandlerType::DrawFromTableau { column } => {
+                // タブローからカードを引く処理
+                debug!("🃏 タブロー列 {} からカードを引く処理", column);
+            },
+            ClickHandlerType::DrawFromFoundation { stack } => {
+                // ファウンデーションからカードを引く処理
+                debug!("🃏 ファウンデーションスタック {} からカードを引く処理", stack);
+            },
+            ClickHandlerType::Custom => {
+                // カスタム処理（必要に応じて実装）
+            },
+        }
+        
+        // 盤面が変わったら元に戻せるように記録（手として数えるものは得点とイベントも）
+        if let Some(before) = before {
+            match game_move {
+                Some(game_move) => events::record_move(resources, before, game_move),
+                None => events::record_change(resources, before),
             }
         }
         
         Ok(())
     }
     
-    /// ストックとウェイストのエンティティIDを検索
-    fn find_stock_and_waste(&self, world: &World) -> Result<(EntityId, EntityId), JsValue> {
-        let mut stock_id = None;
-        let mut waste_id = None;
-        
-        // StackContainerコンポーネントを持つエンティティを探索
-        let entities_with_stack = world.get_entities_with_component::<StackContainer>();
-        
-        for &entity_id in &entities_with_stack {
-            if let Some(stack) = world.get_component::<StackContainer>(entity_id) {
-                match stack.stack_type {
-                    StackType::Stock => {
-                        stock_id = Some(entity_id);
-                    },
-                    StackType::Waste => {
-                        waste_id = Some(entity_id);
-                    },
-                    _ => {},
-                }
-            }
-        }
+    /// ストックとウェイストのエンティティIDを登録簿から取得
+    fn find_stock_and_waste(registry: Option<&StackRegistry>) -> GameResult<(EntityId, EntityId)> {
+        let registry = registry.ok_or(GameError::ResourceMissing("StackRegistry"))?;
         
-        let stock_id = stock_id.ok_or_else(|| JsValue::from_str("ストックエンティティが見つかりません"))?;
-        let waste_id = waste_id.ok_or_else(|| JsValue::from_str("ウェイストエンティティが見つかりません"))?;
+        let stock_id = registry.stock().ok_or_else(|| GameError::StackNotFound("ストック".to_string()))?;
+        let waste_id = registry.waste().ok_or_else(|| GameError::StackNotFound("ウェイスト".to_string()))?;
         
         Ok((stock_id, waste_id))
     }
@@ -111,8 +123,13 @@ impl System for InputSystem {
         SystemPhase::Input  // 入力フェーズで実行
     }
     
-    fn priority(&self) -> SystemPriority {
-        SystemPriority::new(0)  // 入力フェーズ内で最初に実行
+    fn labels(&self) -> Vec<SystemLabel> {
+        vec![self.name(), LABEL_INPUT]
+    }
+    
+    fn reset(&mut self) {
+        // 前のゲームのエンティティは消えているので、クリックされたエンティティを忘れる
+        self.clicked_entity = None;
     }
     
     fn run(
@@ -120,7 +137,7 @@ impl System for InputSystem {
         world: &mut World,
         _resources: &mut ResourceManager,
         _delta_time: f32,
-    ) -> Result<(), JsValue> {
+    ) -> GameResult<()> {
         // 入力状態を取得
         let input_state = match _resources.get::<InputState>() {
             Some(state) => state,  // 参照を使用
@@ -128,14 +145,16 @@ impl System for InputSystem {
         };
         
         // マウスがクリックされた瞬間を検出
-        if input_state.is_mouse_down && !input_state.mouse_buttons[0] {
+        // （ボタンを押したままにしていない押下は、InputState::clickで入力された1回のクリック）
+        let is_click = input_state.is_mouse_down && !input_state.mouse_buttons[0];
+        if is_click {
             // エンティティを探す
-            if let Some(entity_id) = InputHandler::get_entity_at_position(
+            if let Some(entity_id) = get_entity_at_position(
                 world,
                 input_state.mouse_position,
             ) {
                 self.clicked_entity = Some(entity_id);
-                self.process_click(world, entity_id)?;
+                self.process_click(world, _resources, entity_id)?;
             }
         }
         
@@ -151,6 +170,14 @@ impl System for InputSystem {
         
         // マウス位置を記録
         self.last_mouse_position = input_state.mouse_position;
+        drop(input_state);
+        
+        // 1回のクリックは処理したので離したことにする（次のフレームでクリック状態がリセットされる）
+        if is_click {
+            if let Some(input_state) = _resources.get_mut::<InputState>() {
+                input_state.is_mouse_down = false;
+            }
+        }
         
         Ok(())
     }
@@ -173,8 +200,11 @@ pub struct DragSystem {
     // 前回のフレームで左ボタンが押されていたか
     left_button_pressed_prev: bool,
     
-    // ドラッグ中のエンティティの元のZ-index
-    original_z_index: i32,
+    // ドラッグ中のエンティティに子として付いてくるカード
+    dragged_children: Vec<EntityId>,
+    
+    // ドラッグ開始時にカードが属していたスタック
+    source_stack: Option<EntityId>,
 }
 impl DragSystem {
     /// 新しいドラッグシステムを作成
@@ -185,14 +215,15 @@ impl DragSystem {
             drag_started: false,
             last_mouse_pos: Vec2::zero(),
             left_button_pressed_prev: false,  // 明示的にfalseで初期化
-            original_z_index: 0,
+            dragged_children: Vec::new(),
+            source_stack: None,
         }
     }
     
     /// ドラッグ可能なエンティティを探す
     fn find_draggable_entity(&self, world: &World, position: Vec2) -> Option<EntityId> {
         // 座標にあるエンティティを取得
-        let entity_id = InputHandler::get_entity_at_position(world, position)?;
+        let entity_id = get_entity_at_position(world, position)?;
         
         // ドラッグ可能かチェック
         if world.has_component::<Draggable>(entity_id) {
@@ -203,164 +234,82 @@ impl DragSystem {
     }
     
     /// ドラッグを開始
-    fn start_drag(&mut self, world: &mut World, entity_id: EntityId, mouse_position: Vec2) -> Result<(), JsValue> {
+    /// 掴んだカードをスタックから切り離し、drag_childrenが有効なら上に重なったカードを子として付ける
+    fn start_drag(
+        &mut self,
+        world: &mut World,
+        card_index: Option<&CardIndex>,
+        entity_id: EntityId,
+        mouse_position: Vec2,
+    ) -> GameResult<()> {
         debug!("🚀 エンティティ {} のドラッグ開始処理を実行中...", entity_id);
-        debug!("🖱️ マウス位置=({:.1}, {:.1})", mouse_position.x, mouse_position.y);
-        
-        // 必要な情報を先に取得
-        let transform_position;
-        let transform_z_index;
         
         // 1. エンティティの現在位置を先に取得
-        {
-            if let Some(transform) = world.get_component::<crate::ecs::component::Transform>(entity_id) {
-                transform_position = transform.position.clone(); // cloneを明示的に呼び出す
-                transform_z_index = transform.z_index;
-                debug!("📍 エンティティ {} の位置: ({:.1}, {:.1}), Z-index: {}", 
-                    entity_id, transform_position.x, transform_position.y, transform_z_index);
-            } else {
-                // Transformがなければ処理を中止
+        let (transform_position, transform_z_index) = match world.get_component::<Transform>(entity_id) {
+            Some(transform) => (transform.position, transform.z_index),
+            None => {
                 debug!("❌ エラー: エンティティ {} にTransformコンポーネントがありません", entity_id);
                 return Ok(());
             }
-        }
-        
-        // 2. ドラッグオフセットを計算
-        let drag_offset = Vec2::new(
-            mouse_position.x - transform_position.x,
-            mouse_position.y - transform_position.y,
-        );
-        debug!("📏 ドラッグオフセット: ({:.1}, {:.1})", drag_offset.x, drag_offset.y);
-        
-        // 3. ドラッグ可能コンポーネントを更新
-        let drag_component_updated = if let Some(draggable) = world.get_component_mut::<Draggable>(entity_id) {
-            debug!("🔄 ドラッグ状態（更新前）: is_dragging={}, original_z_index={}", 
-                draggable.is_dragging, draggable.original_z_index);
-                
-            draggable.is_dragging = true;
-            draggable.original_position = transform_position;
-            draggable.original_z_index = transform_z_index;
-            draggable.drag_offset = drag_offset;
-            
-            debug!("✅ ドラッグ状態（更新後）: is_dragging=true, original_position=({:.1}, {:.1}), original_z_index={}, drag_offset=({:.1}, {:.1})", 
-                draggable.original_position.x, draggable.original_position.y, 
-                draggable.original_z_index, draggable.drag_offset.x, draggable.drag_offset.y);
-            true
-        } else {
-            debug!("❌ エラー: エンティティ {} にDraggableコンポーネントがありません", entity_id);
-            false
         };
         
-        if !drag_component_updated {
-            debug!("❌ Draggableコンポーネントの更新に失敗しました。処理を中止します。");
-            return Ok(());
-        }
-        
-        // 4. レンダラブルコンポーネントの不透明度を下げる
-        let opacity_updated = if let Some(renderable) = world.get_component_mut::<crate::ecs::component::Renderable>(entity_id) {
-            debug!("🎨 元の不透明度: {}", renderable.opacity);
-            renderable.opacity = crate::constants::DRAG_OPACITY;
-            debug!("🎨 新しい不透明度: {} に設定しました", renderable.opacity);
-            true
-        } else {
-            debug!("❌ エラー: エンティティ {} にRenderableコンポーネントがありません", entity_id);
-            false
+        // 2. ドラッグ可能コンポーネントを更新
+        let drag_children = match world.get_component_mut::<Draggable>(entity_id) {
+            Some(draggable) => {
+                draggable.is_dragging = true;
+                draggable.original_position = transform_position;
+                draggable.original_z_index = transform_z_index;
+                draggable.drag_offset = Vec2::new(
+                    mouse_position.x - transform_position.x,
+                    mouse_position.y - transform_position.y,
+                );
+                draggable.drag_children
+            },
+            None => {
+                debug!("❌ エラー: エンティティ {} にDraggableコンポーネントがありません", entity_id);
+                return Ok(());
+            }
         };
         
-        if !opacity_updated {
-            debug!("⚠️ 警告: 不透明度の更新に失敗しましたが、処理は続行します");
-        }
-        
-        // 5. カードがタブローのスタックにある場合、そのカード以降のカードも一緒にドラッグ
-        let mut cards_to_drag = Vec::new();
-        
-        // カードがどのスタックに属しているか確認
-        let stacks = world.get_entities_with_component::<crate::ecs::component::StackContainer>();
-        debug!("📦 スタックコンテナの総数: {}", stacks.len());
-        
-        let mut found_stack = false;
-        for &stack_id in &stacks {
-            if let Some(stack) = world.get_component::<crate::ecs::component::StackContainer>(stack_id) {
-                // カードがこのスタックに含まれているか確認
-                if let Some(card_index) = stack.cards.iter().position(|&card| card == entity_id) {
-                    debug!("📦 カードがスタック {} の {}番目に見つかりました。スタックタイプ: {:?}", 
-                        stack_id, card_index, stack.stack_type);
-                    found_stack = true;
-                    
-                    // タブローのスタックのみ、カード以降も一緒にドラッグ
-                    if let crate::ecs::component::StackType::Tableau { .. } = stack.stack_type {
-                        debug!("📦 これはタブローのスタックなので、このカード以降も一緒にドラッグします");
-                        cards_to_drag = stack.cards_from_index(card_index);
-                        debug!("📦 一緒にドラッグするカード: {} 枚 {:?}", cards_to_drag.len(), cards_to_drag);
-                        
-                        // 一番上のカード以外の不透明度も下げる
-                        if cards_to_drag.len() > 1 {
-                            debug!("📦 複数のカードをドラッグします: {} 枚", cards_to_drag.len());
-                            
-                            // カードの詳細情報を出力
-                            for (i, &card_id) in cards_to_drag.iter().enumerate() {
-                                if let Some(card_info) = world.get_component::<crate::ecs::component::CardInfo>(card_id) {
-                                    debug!("🃏 カード {}: ID={}, スート={}, ランク={}, 表向き={}", 
-                                        i, card_id, card_info.suit, card_info.rank, card_info.face_up);
-                                }
-                            }
-                            
-                            for (i, &card_id) in cards_to_drag.iter().enumerate().skip(1) {
-                                debug!("📦 追加カード {} の処理中...", card_id);
-                                
-                                // 1. 不透明度を下げる
-                                if let Some(card_renderable) = world.get_component_mut::<crate::ecs::component::Renderable>(card_id) {
-                                    debug!("🎨 カード {} の不透明度を {} に設定します", card_id, crate::constants::DRAG_OPACITY);
-                                    card_renderable.opacity = crate::constants::DRAG_OPACITY;
-                                } else {
-                                    debug!("❌ カード {} にRenderableコンポーネントがありません", card_id);
-                                }
-                                
-                                // 2. 必要なデータを先に取得
-                                let position;
-                                let z_index;
-                                {
-                                    if let Some(card_transform) = world.get_component::<crate::ecs::component::Transform>(card_id) {
-                                        position = card_transform.position.clone();
-                                        z_index = card_transform.z_index;
-                                        debug!("📍 カード {} の位置: ({:.1}, {:.1}), Z-index: {}", 
-                                            card_id, position.x, position.y, z_index);
-                                    } else {
-                                        debug!("❌ カード {} にTransformコンポーネントがありません", card_id);
-                                        continue;
-                                    }
-                                }
-                                
-                                // 3. Draggableコンポーネントを更新
-                                if let Some(card_draggable) = world.get_component_mut::<crate::ecs::component::Draggable>(card_id) {
-                                    card_draggable.original_position = position;
-                                    card_draggable.original_z_index = z_index;
-                                    // 実際にドラッグされてるようにフラグを設定
-                                    card_draggable.is_dragging = true;
-                                    debug!("✅ カード {} のドラッグ状態を更新しました", card_id);
-                                } else {
-                                    debug!("❌ カード {} にDraggableコンポーネントがありません", card_id);
-                                }
-                                
-                                // 4. 別のスコープでTransformコンポーネントを再度取得して更新
-                                if let Some(card_transform) = world.get_component_mut::<crate::ecs::component::Transform>(card_id) {
-                                    // Z-indexを調整して重なる順序を維持
-                                    let new_z_index = 1000 + i as i32;
-                                    debug!("📍 カード {} のZ-indexを {} から {} に更新します", card_id, card_transform.z_index, new_z_index);
-                                    card_transform.z_index = new_z_index;
-                                }
-                            }
-                        }
-                    } else {
-                        debug!("📦 これはタブロー以外のスタック（{:?}）なので、このカードのみドラッグします", stack.stack_type);
+        // 3. カードが属しているスタックと、一緒に運ぶカードを索引から引く
+        self.source_stack = None;
+        self.dragged_children.clear();
+        if let Some(location) = card_index.and_then(|index| index.location_of(entity_id)) {
+            self.source_stack = Some(location.stack);
+            
+            if let Some(stack) = world.get_component::<StackContainer>(location.stack) {
+                // タブローのスタックのみ、カード以降も一緒にドラッグ
+                if drag_children {
+                    if let StackType::Tableau { .. } = stack.stack_type {
+                        self.dragged_children = stack.cards_from_index(location.index + 1);
                     }
-                    break;
                 }
             }
+        } else {
+            debug!("⚠️ カード {} がどのスタックにも属していません", entity_id);
         }
         
-        if !found_stack {
-            debug!("⚠️ カードがどのスタックにも属していません");
+        // 4. 掴んだカードをスタックから切り離して最前面に出す
+        remove_parent(world, entity_id);
+        if let Some(transform) = world.get_component_mut::<Transform>(entity_id) {
+            transform.z_index = DRAG_Z_INDEX;
+        }
+        if let Some(renderable) = world.get_component_mut::<Renderable>(entity_id) {
+            renderable.opacity = DRAG_OPACITY;
+        }
+        
+        // 5. 一緒に運ぶカードを掴んだカードの子にする（以降は親を動かすだけで付いてくる）
+        let children = self.dragged_children.clone();
+        for (i, &card_id) in children.iter().enumerate() {
+            let local = LocalTransform::new(0.0, (i as f64 + 1.0) * STACK_OFFSET_Y).with_z_index(i as i32 + 1);
+            set_parent(world, card_id, entity_id, local)?;
+            
+            if let Some(card_draggable) = world.get_component_mut::<Draggable>(card_id) {
+                card_draggable.is_dragging = true;
+            }
+            if let Some(card_renderable) = world.get_component_mut::<Renderable>(card_id) {
+                card_renderable.opacity = DRAG_OPACITY;
+            }
         }
         
         // 6. ドラッグ中のエンティティを記録
@@ -368,104 +317,75 @@ impl DragSystem {
         self.drag_start_position = mouse_position;
         self.drag_started = true;
         
-        debug!("✨ エンティティ {} のドラッグを開始しました！一緒にドラッグするカード: {}枚", entity_id, cards_to_drag.len());
-        
-        // 現在のドラッグ状態を確認
-        debug!("📊 ドラッグ状態: dragged_entity={:?}, drag_started={}, drag_start_position=({:.1}, {:.1})", 
-            self.dragged_entity, self.drag_started, self.drag_start_position.x, self.drag_start_position.y);
-        
+        debug!("✨ エンティティ {} のドラッグを開始しました！一緒にドラッグするカード: {}枚", entity_id, children.len());
         Ok(())
     }
     
     /// ドラッグ中の更新
-    fn update_drag(&mut self, world: &mut World, entity_id: EntityId, mouse_position: Vec2) -> Result<(), JsValue> {
+    /// 掴んだカードだけを動かし、子のカードの位置はTransformPropagationSystemが計算する
+    fn update_drag(&mut self, world: &mut World, entity_id: EntityId, mouse_position: Vec2) -> GameResult<()> {
         // ドラッグオフセットを取得
-        let drag_offset = if let Some(draggable) = world.get_component::<Draggable>(entity_id) {
-            draggable.drag_offset
-        } else {
-            Vec2::zero() // デフォルト値
+        let drag_offset = match world.get_component::<Draggable>(entity_id) {
+            Some(draggable) => draggable.drag_offset,
+            None => Vec2::zero(),
         };
         
-        // エンティティの位置を更新
-        if let Some(transform) = world.get_component_mut::<Transform>(entity_id) {
-            transform.position.x = mouse_position.x - drag_offset.x;
-            transform.position.y = mouse_position.y - drag_offset.y;
-            
-            // Z-indexを大きくして最前面に表示
-            transform.z_index = 1000;
-        }
-        
-        // スタック内の追加カードも移動
-        let mut cards_to_update = Vec::new();
-        
-        // カードがどのスタックに属しているか確認
-        let stacks = world.get_entities_with_component::<StackContainer>();
-        for &stack_id in &stacks {
-            if let Some(stack) = world.get_component::<StackContainer>(stack_id) {
-                // カードがこのスタックに含まれているか確認
-                if let Some(card_index) = stack.cards.iter().position(|&card| card == entity_id) {
-                    // タブローのスタックのみ、カード以降も一緒にドラッグ
-                    if let crate::ecs::component::StackType::Tableau { .. } = stack.stack_type {
-                        cards_to_update = stack.cards_from_index(card_index + 1);
-                    }
-                    break;
+        // マウスの位置に合わせてオブジェクトを移動
+        let position = Vec2::new(mouse_position.x - drag_offset.x, mouse_position.y - drag_offset.y);
+        match world.get_component::<Transform>(entity_id) {
+            Some(transform) if transform.position.x == position.x && transform.position.y == position.y => {
+                // 動いていなければTransformに触れない
+            },
+            Some(_) => {
+                if let Some(transform) = world.get_component_mut::<Transform>(entity_id) {
+                    transform.position = position;
                 }
+            },
+            None => {
+                debug!("⚠️ ドラッグ中のエンティティ {} はTransformコンポーネントを持っていません", entity_id);
+                return Ok(());
             }
         }
         
-        // 追加カードの位置も更新
-        let base_x = mouse_position.x - drag_offset.x;
-        let base_y = mouse_position.y - drag_offset.y;
-        
-        for (i, &card_id) in cards_to_update.iter().enumerate() {
-            if let Some(transform) = world.get_component_mut::<Transform>(card_id) {
-                transform.position.x = base_x;
-                transform.position.y = base_y + (i as f64 + 1.0) * crate::constants::STACK_OFFSET_Y;
-                transform.z_index = 1000 + (i as i32 + 1);
-            }
-        }
-        
-        Ok(())
+        // ドロップ先の候補をハイライト
+        self.highlight_drop_target(world, &position)
     }
     
     /// ドラッグを終了
-    fn end_drag(&self, world: &mut World) -> Result<(), JsValue> {
-        if let Some(entity_id) = self.dragged_entity {
-            debug!("👆 エンティティ {} のドラッグを終了", entity_id);
-            
-            if let Some(draggable) = world.get_component_mut::<Draggable>(entity_id) {
+    /// 運んでいたカードを元のスタックに戻し、配置はスタックのレイアウトに任せる
+    fn end_drag(&mut self, world: &mut World) -> GameResult<()> {
+        let entity_id = match self.dragged_entity.take() {
+            Some(entity_id) => entity_id,
+            None => {
+                debug!("❓ ドラッグを終了しようとしましたが、ドラッグ中のエンティティがありません");
+                return Ok(());
+            }
+        };
+        debug!("👆 エンティティ {} のドラッグを終了", entity_id);
+        
+        // 1. ドラッグ状態と透明度を元に戻す
+        let mut cards = vec![entity_id];
+        cards.extend(self.dragged_children.drain(..));
+        for &card_id in &cards {
+            if let Some(draggable) = world.get_component_mut::<Draggable>(card_id) {
                 draggable.is_dragging = false;
-                
-                // 最終位置を記録
-                if let Some(transform) = world.get_component::<Transform>(entity_id) {
-                    debug!("📍 ドラッグ終了位置: ({:.1}, {:.1})", 
-                        transform.position.x, transform.position.y);
-                    
-                    // z-indexを元に戻す
-                    if let Some(mut transform) = world.get_component_mut::<Transform>(entity_id) {
-                        transform.z_index = self.original_z_index;
-                        debug!("📊 エンティティ {} のz_indexを元に戻しました: 1000 -> {}", 
-                            entity_id, self.original_z_index);
-                    }
-                }
-            } else {
-                debug!("❌ エンティティ {} には Draggable コンポーネントがありません", entity_id);
             }
-            
-            // オブジェクトの透明度を元に戻す
-            if let Some(mut renderable) = world.get_component_mut::<Renderable>(entity_id) {
+            if let Some(renderable) = world.get_component_mut::<Renderable>(card_id) {
                 renderable.opacity = 1.0;
-                debug!("🔅 エンティティ {} の透明度を元に戻しました: opacity=1.0", entity_id);
             }
-        } else {
-            debug!("❓ ドラッグを終了しようとしましたが、ドラッグ中のエンティティがありません");
         }
         
+        // 2. 元のスタックを変更済みにして、次のレイアウトで親子関係を作り直してもらう
+        if let Some(stack_id) = self.source_stack.take() {
+            world.get_component_mut::<StackContainer>(stack_id);
+        }
+        
+        self.drag_started = false;
         Ok(())
     }
     
     /// ドロップターゲットを見つける
-    fn find_drop_target(&self, world: &World, position: Vec2, dragged_entity: EntityId) -> Result<Option<EntityId>, JsValue> {
+    fn find_drop_target(&self, world: &World, position: Vec2, dragged_entity: EntityId) -> GameResult<Option<EntityId>> {
         // ドロップ可能なエンティティを探す
         let droppable_entities = world.get_entities_with_component::<Droppable>();
         
@@ -501,7 +421,7 @@ impl DragSystem {
     }
     
     /// ドロップが有効かどうかチェック
-    fn is_valid_drop(&self, world: &World, dragged_entity: EntityId, target_entity: EntityId) -> Result<bool, JsValue> {
+    fn is_valid_drop(&self, world: &World, dragged_entity: EntityId, target_entity: EntityId) -> GameResult<bool> {
         // ここでドロップの有効性をチェックするロジックを実装
         // 例: カードがスタックに追加できるか、アイテムが特定のスロットに配置できるかなど
         
@@ -516,7 +436,7 @@ impl DragSystem {
     }
     
     /// ドロップ先候補をハイライト表示する
-    fn highlight_drop_target(&self, world: &mut World, position: &Vec2) -> Result<(), JsValue> {
+    fn highlight_drop_target(&self, world: &mut World, position: &Vec2) -> GameResult<()> {
         // ドラッグ中のエンティティがない場合は何もしない
         let dragged_entity = match self.dragged_entity {
             Some(entity) => entity,
@@ -561,7 +481,7 @@ impl DragSystem {
     }
     
     /// ドロップ処理を行う
-    fn process_drop(&mut self, world: &mut World, dragged_entity: EntityId, drop_target: EntityId) -> Result<(), JsValue> {
+    fn process_drop(&mut self, world: &mut World, dragged_entity: EntityId, drop_target: EntityId) -> GameResult<()> {
         debug!("🎯 エンティティ {} をエンティティ {} の上にドロップ", dragged_entity, drop_target);
         
         // 必要な情報を先に取得
@@ -571,18 +491,10 @@ impl DragSystem {
         let _source_stack: Option<EntityId> = None;
         
         // カード情報を取得
-        let card_info = if let Some(info) = world.get_component::<crate::ecs::component::CardInfo>(dragged_entity) {
-            Some(info.clone())
-        } else {
-            None
-        };
+        let card_info = world.get_component::<crate::ecs::component::CardInfo>(dragged_entity).cloned();
         
         // ドロップ先がスタックコンテナかチェック
-        let target_stack_container = if let Some(stack) = world.get_component::<StackContainer>(drop_target) {
-            Some(stack.clone())
-        } else {
-            None
-        };
+        let target_stack_container = world.get_component::<StackContainer>(drop_target).cloned();
         
         // ドラッグしてるカードがどのスタックから来たかを調べる
         let source_stack_id = {
@@ -604,19 +516,17 @@ impl DragSystem {
         // ドロップが有効かチェック（ソリティアのルールに基づく）
         if let (Some(card_info), Some(target_stack)) = (card_info, target_stack_container) {
             match target_stack.stack_type {
-                crate::ecs::component::StackType::Foundation { suit } => {
-                    // 組み札のルール: 同じスートで昇順（A, 2, 3, ...）
-                    if card_info.suit as usize == suit {
-                        let top_card = target_stack.top_card();
-                        if let Some(top_id) = top_card {
-                            if let Some(top_info) = world.get_component::<crate::ecs::component::CardInfo>(top_id) {
-                                // 次のランクなら配置可能
-                                should_move_card = card_info.rank == top_info.rank + 1;
-                            }
-                        } else {
-                            // 空のファウンデーションにはAのみ置ける
-                            should_move_card = card_info.rank == 0; // A
+                // 組み札のルール: 同じスートで昇順（A, 2, 3, ...）
+                crate::ecs::component::StackType::Foundation { suit } if card_info.suit as usize == suit => {
+                    let top_card = target_stack.top_card();
+                    if let Some(top_id) = top_card {
+                        if let Some(top_info) = world.get_component::<crate::ecs::component::CardInfo>(top_id) {
+                            // 次のランクなら配置可能
+                            should_move_card = card_info.rank == top_info.rank + 1;
                         }
+                    } else {
+                        // 空のファウンデーションにはAのみ置ける
+                        should_move_card = card_info.rank == 0; // A
                     }
                 },
                 crate::ecs::component::StackType::Tableau { .. } => {
@@ -713,7 +623,7 @@ impl DragSystem {
         world: &mut World, 
         dragged_cards: Vec<EntityId>, 
         target_id: EntityId
-    ) -> Result<(), JsValue> {
+    ) -> GameResult<()> {
         debug!("🎯 複数のカード（{}枚）をエンティティ {} の上にドロップ", dragged_cards.len(), target_id);
         
         if dragged_cards.is_empty() {
@@ -724,18 +634,10 @@ impl DragSystem {
         let main_card_id = dragged_cards[0];
         
         // カード情報を取得
-        let card_info = if let Some(info) = world.get_component::<crate::ecs::component::CardInfo>(main_card_id) {
-            Some(info.clone())
-        } else {
-            None
-        };
+        let card_info = world.get_component::<crate::ecs::component::CardInfo>(main_card_id).cloned();
         
         // ドロップ先がスタックコンテナかチェック
-        let target_stack_container = if let Some(stack) = world.get_component::<StackContainer>(target_id) {
-            Some(stack.clone())
-        } else {
-            None
-        };
+        let target_stack_container = world.get_component::<StackContainer>(target_id).cloned();
         
         // ドラッグしてるカードがどのスタックから来たかを調べる
         let source_stack_id = {
@@ -832,7 +734,7 @@ impl DragSystem {
     }
     
     /// ドラッグしているすべてのカードを取得
-    fn get_dragged_cards(&self, world: &World, main_card_id: EntityId) -> Result<Vec<EntityId>, JsValue> {
+    fn get_dragged_cards(&self, world: &World, main_card_id: EntityId) -> GameResult<Vec<EntityId>> {
         let mut dragged_cards = vec![main_card_id];
         
         // カードがどのスタックに属しているか確認
@@ -854,7 +756,7 @@ impl DragSystem {
     }
     
     /// カードの位置を元に戻す
-    fn reset_card_positions(&self, world: &mut World, cards: &[EntityId]) -> Result<(), JsValue> {
+    fn reset_card_positions(&self, world: &mut World, cards: &[EntityId]) -> GameResult<()> {
         for &card_id in cards {
             if let Some(draggable) = world.get_component::<Draggable>(card_id) {
                 let original_position = draggable.original_position;
@@ -881,7 +783,7 @@ impl DragSystem {
     }
     
     /// マウスクリック位置にあるエンティティを見つける
-    fn find_clicked_entity(&self, world: &World, mouse_position: &Vec2) -> Result<Option<EntityId>, JsValue> {
+    fn find_clicked_entity(&self, world: &World, mouse_position: &Vec2) -> GameResult<Option<EntityId>> {
         debug!("🔍 find_clicked_entity: クリック座標=({:.1}, {:.1})", mouse_position.x, mouse_position.y);
         
         let mut clicked_entity = None;
@@ -942,7 +844,13 @@ impl DragSystem {
     }
     
     /// エンティティのクリックを処理
-    fn handle_entity_click(&mut self, world: &mut World, entity_id: EntityId) -> Result<(), JsValue> {
+    fn handle_entity_click(
+        &mut self,
+        world: &mut World,
+        card_index: Option<&CardIndex>,
+        entity_id: EntityId,
+        mouse_position: Vec2,
+    ) -> GameResult<()> {
         debug!("🖱️ handle_entity_click: エンティティID={}", entity_id);
         
         // エンティティがドラッグ可能か確認
@@ -950,33 +858,7 @@ impl DragSystem {
         debug!("🧩 エンティティ {} はドラッグ可能か: {}", entity_id, is_draggable);
         
         if is_draggable {
-            // ドラッグ中のエンティティをセット
-            self.dragged_entity = Some(entity_id);
-            
-            // エンティティの元の位置を保存
-            if let Some(transform) = world.get_component::<Transform>(entity_id) {
-                let original_position = transform.position.clone();
-                debug!("📍 エンティティ {} の元の位置を保存: ({:.1}, {:.1})", 
-                    entity_id, original_position.x, original_position.y);
-            } else {
-                debug!("⚠️ エンティティ {} はTransformコンポーネントを持っていません", entity_id);
-            }
-            
-            // オブジェクトの透明度を下げる（ドラッグ中の視覚的フィードバック）
-            if let Some(mut renderable) = world.get_component_mut::<Renderable>(entity_id) {
-                renderable.opacity = 0.7;
-                debug!("🔅 エンティティ {} の透明度を下げました: opacity=0.7", entity_id);
-            } else {
-                debug!("⚠️ エンティティ {} はRenderableコンポーネントを持っていません", entity_id);
-            }
-            
-            // z-indexを一時的に上げて、他のオブジェクトの上に表示
-            if let Some(mut transform) = world.get_component_mut::<Transform>(entity_id) {
-                self.original_z_index = transform.z_index;
-                transform.z_index = 1000; // 一時的に最前面に
-                debug!("📊 エンティティ {} のz_indexを一時的に上げました: {} -> 1000", 
-                    entity_id, self.original_z_index);
-            }
+            self.start_drag(world, card_index, entity_id, mouse_position)?;
         } else {
             debug!("❌ エンティティ {} はドラッグ可能ではありません", entity_id);
         }
@@ -994,8 +876,21 @@ impl System for DragSystem {
         SystemPhase::Input  // 入力フェーズで実行
     }
     
-    fn priority(&self) -> SystemPriority {
-        SystemPriority::new(1)  // InputSystemの後に実行
+    fn labels(&self) -> Vec<SystemLabel> {
+        vec![self.name(), LABEL_DRAG]
+    }
+    
+    fn after(&self) -> Vec<SystemLabel> {
+        vec![LABEL_INPUT]  // クリック処理の後に実行
+    }
+    
+    fn reset(&mut self) {
+        // ドラッグ中のカードは前のゲームと一緒に消えているので捨てる
+        // ボタンの押下状態だけは引き継ぎ、押しっぱなしのボタンを新しいクリックと見なさないようにする
+        *self = Self {
+            left_button_pressed_prev: self.left_button_pressed_prev,
+            ..Self::new()
+        };
     }
     
     fn run(
@@ -1003,7 +898,12 @@ impl System for DragSystem {
         world: &mut World,
         resources: &mut ResourceManager,
         _delta_time: f32,
-    ) -> Result<(), JsValue> {
+    ) -> GameResult<()> {
+        // 同じフレームで入力処理がスタックを変えていても正しく引けるように、索引を最新にする
+        if let Some(card_index) = resources.get_mut::<CardIndex>() {
+            card_index.sync(world);
+        }
+        
         // 結果を取得
         let result = self.update(world, resources);
         
@@ -1020,7 +920,7 @@ impl System for DragSystem {
 }
 
 impl DragSystem {
-    pub fn update(&mut self, world: &mut World, resources: &ResourceManager) -> Result<(), JsValue> {
+    pub fn update(&mut self, world: &mut World, resources: &ResourceManager) -> GameResult<()> {
         // マウスの状態を取得
         let mouse_state = match resources.get::<InputState>() {
             Some(state) => state,
@@ -1050,7 +950,8 @@ impl DragSystem {
             // クリックされたエンティティを検索
             if let Some(entity_id) = self.find_clicked_entity(world, &mouse_state.mouse_position)? {
                 debug!("🎯 クリックされたエンティティを検出: {}", entity_id);
-                self.handle_entity_click(world, entity_id)?;
+                let card_index = resources.get::<CardIndex>();
+                self.handle_entity_click(world, card_index.as_deref(), entity_id, mouse_state.mouse_position)?;
             } else {
                 debug!("🚫 クリック位置にエンティティが見つかりませんでした");
             }
@@ -1061,34 +962,14 @@ impl DragSystem {
             debug!("👆 マウス左ボタンが離されました");
             
             // ドラッグ中のエンティティがあれば終了処理を行う
-            if let Some(dragged_entity) = self.dragged_entity {
-                debug!("🔚 ドラッグ終了: エンティティID={}", dragged_entity);
-                self.end_drag(world)?;
-                self.dragged_entity = None; // ドラッグ状態をリセット
-            } else {
-                debug!("ℹ️ ドラッグ中のエンティティはありませんでした");
-            }
+            self.end_drag(world)?;
         }
         
         // ドラッグ中の処理
-        if mouse_state.mouse_buttons[0] && self.dragged_entity.is_some() {
-            let entity_id = self.dragged_entity.unwrap();
-            debug!("🔄 ドラッグ中: エンティティID={}", entity_id);
-            
-            if let Some(mut transform) = world.get_component_mut::<Transform>(entity_id) {
-                // マウスの移動に合わせてオブジェクトを移動
-                transform.position.x += mouse_delta.x;
-                transform.position.y += mouse_delta.y;
-                debug!("📍 エンティティ {} の位置を更新: ({:.1}, {:.1})", 
-                    entity_id, transform.position.x, transform.position.y);
-                
-                // positionをコピーしてから、highlight_drop_targetを呼び出す
-                let position_copy = transform.position.clone();
-                
-                // ドロップ先の候補をハイライト
-                self.highlight_drop_target(world, &position_copy)?;
-            } else {
-                debug!("⚠️ ドラッグ中のエンティティ {} はTransformコンポーネントを持っていません", entity_id);
+        if mouse_state.mouse_buttons[0] {
+            if let Some(entity_id) = self.dragged_entity {
+                debug!("🔄 ドラッグ中: エンティティID={}", entity_id);
+                self.update_drag(world, entity_id, mouse_state.mouse_position)?;
             }
         }
         
@@ -1098,3 +979,111 @@ impl DragSystem {
         Ok(())
     }
 }
+
+/// 指定した座標にあるエンティティを取得
+pub fn get_entity_at_position(
+    world: &World,
+    position: Vec2,
+) -> Option<EntityId> {
+    // レンダラブルコンポーネントを持つエンティティのうち、
+    // Z-indexが大きい（上に表示されている）順にソート
+    let mut entities = world.get_entities_with_component::<Renderable>();
+    
+    if entities.is_empty() {
+        return None;
+    }
+    
+    // Z-indexでソート（大きい順）
+    entities.sort_by(|&a, &b| {
+        let z_a = world
+            .get_component::<Transform>(a)
+            .map(|t| t.z_index)
+            .unwrap_or(0);
+            
+        let z_b = world
+            .get_component::<Transform>(b)
+            .map(|t| t.z_index)
+            .unwrap_or(0);
+            
+        z_b.cmp(&z_a)  // 降順
+    });
+    
+    // 座標が含まれるエンティティを探す
+    for &entity_id in &entities {
+        let transform = match world.get_component::<Transform>(entity_id) {
+            Some(t) => t,
+            None => continue,
+        };
+        
+        let renderable = match world.get_component::<Renderable>(entity_id) {
+            Some(r) => r,
+            None => continue,
+        };
+        
+        // エンティティの領域内にあるかチェック
+        let x = transform.position.x;
+        let y = transform.position.y;
+        let width = renderable.width;
+        let height = renderable.height;
+        
+        if position.x >= x && position.x <= x + width &&
+           position.y >= y && position.y <= y + height {
+            return Some(entity_id);
+        }
+    }
+    
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::history::MoveHistory;
+    use crate::game::solitaire;
+    
+    fn history_len(resources: &ResourceManager) -> usize {
+        resources.get::<MoveHistory>().map(|history| history.len()).unwrap()
+    }
+    
+    #[test]
+    fn click_records_history_only_when_the_board_changes() {
+        let mut world = World::new();
+        solitaire::setup_solitaire_board_with_seed(&mut world, 1).unwrap();
+        let registry = StackRegistry::from_world(&world);
+        let (stock_id, waste_id) = (registry.stock().unwrap(), registry.waste().unwrap());
+        let mut resources = ResourceManager::new();
+        resources.add(registry);
+        resources.add(MoveHistory::new());
+        let mut input = InputSystem::new();
+        
+        // 1. 山札から引くと1手として記録される
+        input.process_click(&mut world, &resources, stock_id).unwrap();
+        assert_eq!(history_len(&resources), 1);
+        
+        // 2. 山札も捨て札も空ならクリックしても何も記録しない
+        for stack_id in [stock_id, waste_id] {
+            world.get_component_mut::<StackContainer>(stack_id).unwrap().cards.clear();
+        }
+        input.process_click(&mut world, &resources, stock_id).unwrap();
+        assert_eq!(history_len(&resources), 1);
+        
+        // 3. 盤面を変えないクリックも記録しない
+        input.process_click(&mut world, &resources, waste_id).unwrap();
+        assert_eq!(history_len(&resources), 1);
+    }
+    
+    #[test]
+    fn flipping_a_card_face_up_makes_it_draggable() {
+        let mut world = World::new();
+        let card_id = world.create_entity().unwrap();
+        world.add_component(card_id, CardInfo::new(0, 0)).unwrap();
+        world.add_component(card_id, Clickable::new(ClickHandlerType::FlipCard)).unwrap();
+        let mut resources = ResourceManager::new();
+        resources.add(MoveHistory::new());
+        
+        InputSystem::new().process_click(&mut world, &resources, card_id).unwrap();
+        assert!(world.get_component::<CardInfo>(card_id).unwrap().face_up);
+        assert!(world.get_component::<Draggable>(card_id).is_some_and(|draggable| draggable.drag_children));
+        assert_eq!(history_len(&resources), 1);
+    }
+}
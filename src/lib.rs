@@ -1,21 +1,42 @@
+#[cfg(feature = "web")]
 use wasm_bindgen::prelude::*;
-use log::info;
+#[cfg(feature = "web")]
+use log::{info, debug};
 
 // モジュール定義
-mod ecs;
-mod game;
+// error・ecs・gameはsrc/bin/のヘッドレス実行など、ブラウザ以外からも使えるように公開する
+pub mod error;
+pub mod ecs;
+pub mod game;
 mod render;
 mod input;
+#[cfg(feature = "web")]
 mod network;
 mod utils;
 mod constants;
 mod components;
 mod resources;
 
+#[cfg(feature = "web")]
 use crate::game::Game;
+#[cfg(feature = "web")]
+use crate::game::config::GameConfig;
+#[cfg(feature = "web")]
+use crate::game::setup::{self, NewGameMode};
+#[cfg(feature = "web")]
+use crate::game::history::{self, MoveHistory};
+#[cfg(feature = "web")]
+use crate::error::GameError;
+#[cfg(feature = "web")]
+use crate::ecs::component::CardInfo;
+#[cfg(feature = "web")]
+use crate::ecs::resources::InputState;
+#[cfg(feature = "web")]
+use crate::ecs::snapshot::WorldSnapshot;
 
 /// ゲーム初期化関数
 /// Javascriptからこの関数を呼び出してゲームを開始する
+#[cfg(feature = "web")]
 #[wasm_bindgen(start)]
 pub fn start() {
     // パニック時にエラーメッセージをコンソールに表示するフックを設定
@@ -27,13 +48,21 @@ pub fn start() {
 }
 
 /// ゲームを初期化するJavaScript向け関数
+/// create_gameで作成したゲームがあるか確認し、盤面が空なら配る
+#[cfg(feature = "web")]
 #[wasm_bindgen]
-pub fn init_game() {
+pub fn init_game() -> Result<(), JsValue> {
     info!("init_game()が呼び出されました");
-    // 将来的に初期化ロジックをここに追加
+    game::with_active_app(|app| {
+        if app.world().get_entities_with_component::<CardInfo>().is_empty() {
+            setup::new_game(app, NewGameMode::NewDeal)?;
+        }
+        Ok(())
+    })
 }
 
 /// 新しいゲームを作成するJavaScript向け関数
+#[cfg(feature = "web")]
 #[wasm_bindgen]
 pub fn create_game(canvas_id: &str) -> Result<Game, JsValue> {
     info!("create_game({})が呼び出されました", canvas_id);
@@ -45,35 +74,81 @@ pub fn create_game(canvas_id: &str) -> Result<Game, JsValue> {
     Ok(game)
 }
 
+/// 設定を指定して新しいゲームを作成するJavaScript向け関数
+/// 1つのページに複数のゲームを並べるときに、キャンバスの大きさや入力の有無をゲームごとに変える
+#[cfg(feature = "web")]
+#[wasm_bindgen]
+pub fn create_game_with_config(canvas_id: &str, config: GameConfig) -> Result<Game, JsValue> {
+    info!("create_game_with_config({}, {:?})が呼び出されました", canvas_id, config);
+    let mut game = Game::with_config(canvas_id, config)?;
+    
+    // ゲームを開始
+    game.start()?;
+    
+    Ok(game)
+}
+
 /// 新しいゲームを開始するJavaScript向け関数
+/// 操作対象のゲーム（最後に作成したゲームか、Game::activateで選んだゲーム）をランダムに配り直す
+#[cfg(feature = "web")]
 #[wasm_bindgen]
-pub fn new_game() {
+pub fn new_game() -> Result<(), JsValue> {
     info!("new_game()が呼び出されました");
-    // 将来的に新規ゲーム開始ロジックをここに追加
+    game::with_active_app(|app| setup::new_game(app, NewGameMode::NewDeal))?;
+    Ok(())
 }
 
 /// 操作を元に戻すJavaScript向け関数
+/// 戻せる手がなければfalseを返す
+#[cfg(feature = "web")]
 #[wasm_bindgen]
-pub fn undo_move() {
+pub fn undo_move() -> Result<bool, JsValue> {
     info!("undo_move()が呼び出されました");
-    // 将来的にundo機能をここに追加
+    game::with_active_app(history::undo_move)
+}
+
+/// 元に戻した操作をやり直すJavaScript向け関数
+/// やり直せる手がなければfalseを返す
+#[cfg(feature = "web")]
+#[wasm_bindgen]
+pub fn redo_move() -> Result<bool, JsValue> {
+    info!("redo_move()が呼び出されました");
+    game::with_active_app(history::redo_move)
 }
 
 /// ゲーム状態を更新するJavaScript向け関数
+/// save_snapshotと同じ形式のJSON文字列（サーバーから届いた盤面など）で盤面を置き換え、手の履歴は捨てる
+#[cfg(feature = "web")]
 #[wasm_bindgen]
-pub fn update_game_state(state_json: &str) {
-    info!("update_game_state()が呼び出されました: {}", state_json);
-    // 将来的に状態更新ロジックをここに追加
+pub fn update_game_state(state_json: &str) -> Result<(), JsValue> {
+    info!("update_game_state()が呼び出されました（{}バイト）", state_json.len());
+    let snapshot = WorldSnapshot::from_json(state_json)?;
+    game::with_active_app(|app| {
+        history::restore_state(app, &snapshot)?;
+        if let Some(history) = app.resources_mut().get_mut::<MoveHistory>() {
+            history.clear();
+        }
+        Ok(())
+    })
 }
 
 /// クリック位置を処理するJavaScript向け関数
+/// 盤面の座標（既定の大きさ800x600での座標）でのクリックを入力状態に書き込み、次のフレームでInputSystemが処理する
+/// キャンバスを縮小している場合は、Game::clickと同じく呼び出し側で倍率を割ってから渡す
+#[cfg(feature = "web")]
 #[wasm_bindgen]
-pub fn handle_click(x: f64, y: f64) {
-    info!("handle_click({}, {})が呼び出されました", x, y);
-    // 将来的にクリック処理ロジックをここに追加
+pub fn handle_click(board_x: f64, board_y: f64) -> Result<(), JsValue> {
+    debug!("handle_click({}, {})が呼び出されました", board_x, board_y);
+    game::with_active_app(|app| {
+        let input_state = app.resources_mut().get_mut::<InputState>()
+            .ok_or(GameError::ResourceMissing("InputState"))?;
+        input_state.click(board_x, board_y);
+        Ok(())
+    })
 }
 
 /// テスト用Hello関数
+#[cfg(feature = "web")]
 #[wasm_bindgen]
 pub fn greet(name: &str) -> String {
     format!("こんにちは、{}さん！WebAssemblyのソリティアゲームへようこそ！🎮✨", name)
@@ -3,19 +3,23 @@
 // このモジュールは、ゲームのレンダリング機能を提供します。
 // キャンバス上にカードやUI要素を描画する処理を担当します。
 
-use wasm_bindgen::prelude::*;
-
 /// レンダリングモジュール
 /// ゲームの視覚的な表示を担当するコンポーネントとシステムを提供します。
 /// CanvasRenderingContext2dを使用してカードやUIエレメントを描画します。
 
 // レンダリングコンポーネントとシステム
+// キャンバスを使う部分はweb機能が有効なときだけビルドする
+#[cfg(feature = "web")]
 pub mod renderer;
 pub mod systems;
+#[cfg(feature = "web")]
 pub mod card_renderer;
+#[cfg(feature = "web")]
 pub mod render_context;
+pub mod plugin;
 
 // re-exports
+#[cfg(feature = "web")]
 pub use render_context::RenderContext;
 // アニメーションシステムは他の場所で定義されている場合があります
 // pub use systems::animation::{AnimationManager, AnimationSystem, Animation, AnimationType, EasingType}; 
\ No newline at end of file
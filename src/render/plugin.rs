@@ -0,0 +1,21 @@
+use crate::ecs::app::{App, Plugin};
+use crate::ecs::hierarchy::TransformPropagationSystem;
+use crate::render::systems::RenderSystem;
+
+/// 描画のプラグイン
+/// スタックのレイアウトと、親子関係に沿った座標の計算を追加する
+pub struct RenderPlugin;
+
+impl Plugin for RenderPlugin {
+    fn build(&self, app: &mut App) {
+        // レンダリングシステムを追加
+        app.add_system(RenderSystem::new());
+        
+        // 親子関係に沿って座標を計算するシステムを追加
+        app.add_system(TransformPropagationSystem);
+        
+        // デバッグ用コンソールから作り直せるように登録
+        app.register_system(RenderSystem::new)
+            .register_system(|| TransformPropagationSystem);
+    }
+}
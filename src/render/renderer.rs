@@ -1,6 +1,7 @@
 use wasm_bindgen::prelude::*;
 use web_sys::{HtmlCanvasElement, CanvasRenderingContext2d};
 use crate::ecs::world::World;
+use crate::ecs::entity::EntityId;
 use crate::ecs::resources::ResourceManager;
 use crate::ecs::component::{Transform, Renderable, CardInfo, RenderType, Position, Sprite, Draggable};
 use crate::constants::{
@@ -11,47 +12,61 @@ use crate::constants::{
 use log::error;
 use super::RenderContext;
 
+/// 描画するエンティティを描画順に並べる
+/// TransformとRenderableを持つエンティティをZ-indexの小さい順に並べる
+/// 安定ソートなので、Z-indexが同じエンティティはID順（作成順）に描画される
+pub fn render_order(world: &World) -> Vec<EntityId> {
+    let mut entities_to_render: Vec<_> = world
+        .get_all_entities()
+        .iter()
+        .filter_map(|&entity_id| {
+            if let Some(transform) = world.get_component::<Transform>(entity_id) {
+                if world.get_component::<Renderable>(entity_id).is_some() {
+                    Some((entity_id, transform.z_index))
+                } else {
+                    None
+                }
+            } else {
+                None
+            }
+        })
+        .collect();
+    
+    entities_to_render.sort_by_key(|&(_, z_index)| z_index);
+    entities_to_render.into_iter().map(|(entity_id, _)| entity_id).collect()
+}
+
 /// レンダラー
 /// キャンバスへの描画を担当
 #[derive(Clone)]
 pub struct Renderer {
     canvas: HtmlCanvasElement,
     context: CanvasRenderingContext2d,
+    scale: f64,  // 盤面の座標からキャンバスのピクセルへの倍率
 }
 
 impl Renderer {
     /// 新しいレンダラーを作成
     pub fn new(canvas: HtmlCanvasElement, context: CanvasRenderingContext2d) -> Self {
-        Self { canvas, context }
+        Self { canvas, context, scale: 1.0 }
+    }
+    
+    /// 盤面を拡大・縮小して描画する倍率を指定
+    pub fn with_scale(mut self, scale: f64) -> Self {
+        self.scale = scale;
+        self
     }
     
     /// ゲーム世界を描画
     pub fn render(&self, world: &World, _resources: &ResourceManager) -> Result<(), JsValue> {
+        // 盤面の座標のまま描けるように倍率を設定
+        self.context.set_transform(self.scale, 0.0, 0.0, self.scale, 0.0, 0.0)?;
+        
         // キャンバスをクリア
         self.clear_canvas()?;
         
-        // エンティティをZ-indexでソート（描画順序のため）
-        let mut entities_to_render: Vec<_> = world
-            .get_all_entities()
-            .iter()
-            .filter_map(|&entity_id| {
-                if let Some(transform) = world.get_component::<Transform>(entity_id) {
-                    if world.get_component::<Renderable>(entity_id).is_some() {
-                        Some((entity_id, transform.z_index))
-                    } else {
-                        None
-                    }
-                } else {
-                    None
-                }
-            })
-            .collect();
-        
-        // Z-indexでソート（小さい順に描画）
-        entities_to_render.sort_by_key(|&(_, z_index)| z_index);
-        
-        // 各エンティティを描画
-        for (entity_id, _) in entities_to_render {
+        // 各エンティティをZ-indexの小さい順に描画
+        for entity_id in render_order(world) {
             self.render_entity(world, entity_id)?;
         }
         
@@ -64,8 +79,8 @@ impl Renderer {
         self.context.fill_rect(
             0.0,
             0.0,
-            self.canvas.width() as f64,
-            self.canvas.height() as f64,
+            self.canvas.width() as f64 / self.scale,
+            self.canvas.height() as f64 / self.scale,
         );
         Ok(())
     }
@@ -215,7 +230,7 @@ impl Renderer {
             self.context.set_text_baseline("top");
             self.context.fill_text(&format!("{}{}", rank_text, suit_symbol), 5.0, 5.0)?;
             self.context.restore();
-            
+        
         } else {
             // 裏向きカードを描画
             self.render_rectangle(
@@ -318,7 +333,7 @@ impl GameRenderer {
         let context = RenderContext::new(canvas_id)?;
         Ok(Self { context })
     }
-
+    
     /// ゲーム世界を描画
     pub fn render(&self, world: &World) -> Result<(), JsValue> {
         // キャンバスをクリア
@@ -460,4 +475,38 @@ impl GameRenderer {
     pub fn context(&self) -> &RenderContext {
         &self.context
     }
-} 
\ No newline at end of file
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn spawn(world: &mut World, z_index: i32) -> EntityId {
+        let entity_id = world.create_entity().unwrap();
+        world.add_component(entity_id, Transform::new(0.0, 0.0).with_z_index(z_index)).unwrap();
+        world.add_component(entity_id, Renderable::card(CARD_WIDTH, CARD_HEIGHT)).unwrap();
+        entity_id
+    }
+    
+    #[test]
+    fn equal_z_index_is_drawn_in_creation_order() {
+        let mut world = World::new();
+        let a = spawn(&mut world, 1);
+        let b = spawn(&mut world, 0);
+        let c = spawn(&mut world, 1);
+        let d = spawn(&mut world, 0);
+        
+        assert_eq!(render_order(&world), vec![b, d, a, c]);
+    }
+    
+    #[test]
+    fn recreated_entity_is_drawn_after_existing_ones_with_equal_z_index() {
+        let mut world = World::new();
+        let a = spawn(&mut world, 0);
+        let b = spawn(&mut world, 0);
+        world.remove_entity(a);
+        world.update();
+        let c = spawn(&mut world, 0);
+        
+        assert_eq!(render_order(&world), vec![b, c]);
+    }
+}
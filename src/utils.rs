@@ -1,6 +1,9 @@
+#[cfg(feature = "web")]
 use wasm_bindgen::prelude::*;
+use serde::{Serialize, Deserialize};
 
 /// パニックが発生したときにコンソールにエラーメッセージを表示するためのフックを設定する
+#[cfg(feature = "web")]
 pub fn set_panic_hook() {
     // パニックをキャッチしてJavaScriptコンソールに表示するフックを設定する
     // 開発時にエラーの原因が分かりやすくなるよ👍
@@ -8,18 +11,21 @@ pub fn set_panic_hook() {
 }
 
 /// JavaScriptのconsole.logへのラッパー
+#[cfg(feature = "web")]
 #[wasm_bindgen]
 pub fn console_log(message: &str) {
     web_sys::console::log_1(&JsValue::from_str(message));
 }
 
 /// JavaScriptのconsole.errorへのラッパー
+#[cfg(feature = "web")]
 #[wasm_bindgen]
 pub fn console_error(message: &str) {
     web_sys::console::error_1(&JsValue::from_str(message));
 }
 
 /// 現在の時間をミリ秒で取得
+#[cfg(feature = "web")]
 pub fn get_current_time() -> Result<f64, JsValue> {
     // ブラウザのパフォーマンスAPIを使って現在時刻を取得
     let window = web_sys::window().ok_or_else(|| JsValue::from_str("ウィンドウが見つかりません"))?;
@@ -27,6 +33,42 @@ pub fn get_current_time() -> Result<f64, JsValue> {
     Ok(performance.now())
 }
 
+/// 計測用の現在時刻をミリ秒で取得
+/// wasm（web機能）ではperformance.now()、それ以外ではプロセス起動後に初めて呼ばれた時点からの経過時間を返す
+#[cfg(all(target_arch = "wasm32", feature = "web"))]
+pub fn now_ms() -> f64 {
+    get_current_time().unwrap_or(0.0)
+}
+
+/// 計測用の現在時刻をミリ秒で取得
+/// wasm（web機能）ではperformance.now()、それ以外ではプロセス起動後に初めて呼ばれた時点からの経過時間を返す
+#[cfg(not(all(target_arch = "wasm32", feature = "web")))]
+pub fn now_ms() -> f64 {
+    use std::sync::OnceLock;
+    use std::time::Instant;
+    
+    static START: OnceLock<Instant> = OnceLock::new();
+    START.get_or_init(Instant::now).elapsed().as_secs_f64() * 1000.0
+}
+
+/// シリアライズできる値をJavaScriptのオブジェクトにする
+/// JSON文字列を経由するので、フィールド名やenumの表現はserdeの設定に従う
+#[cfg(feature = "web")]
+pub fn to_js_value<T: Serialize>(value: &T) -> Result<JsValue, JsValue> {
+    let json = serde_json::to_string(value).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    js_sys::JSON::parse(&json)
+}
+
+/// 型名からモジュールのパスを取り除いた短い名前を返す
+/// 例: "ecs_wasm_game4::ecs::resources::TimeInfo" -> "TimeInfo"
+pub fn short_type_name(full_name: &'static str) -> &'static str {
+    let base = full_name.split('<').next().unwrap_or(full_name);
+    match base.rfind("::") {
+        Some(position) => &full_name[position + 2..],
+        None => full_name,
+    }
+}
+
 /// 二つの値の間の距離を計算
 pub fn distance(x1: f64, y1: f64, x2: f64, y2: f64) -> f64 {
     ((x2 - x1).powi(2) + (y2 - y1).powi(2)).sqrt()
@@ -44,7 +86,7 @@ pub fn clamp<T: PartialOrd>(value: T, min: T, max: T) -> T {
 }
 
 /// 二次元ベクトルを表す補助構造体
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
 pub struct Vec2 {
     pub x: f64,
     pub y: f64,